@@ -0,0 +1,307 @@
+//! Validating GLSL without a GL context, run with `shader_preview check FILES`
+//!
+//! Shaders are compiled by `glslangValidator` when it is installed, otherwise parsed by the
+//! naga GLSL front end after [`translate::to_vulkan`]. Only naga's front end runs: it type
+//! checks like a driver, while naga's validator would reject the OpenGL style interfaces of
+//! valid shaders.
+use crate::cli::CheckArgs;
+use crate::run::shader_program::{Diagnostic, Severity, ShaderError, ShaderStage, parse_info_log};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+
+pub mod translate;
+
+const GLSLANG: &str = "glslangValidator";
+
+/// The compiler shaders are checked with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Validator {
+    /// glslangValidator when it is on the `PATH`, naga otherwise
+    #[default]
+    Auto,
+    Naga,
+    Glslang,
+}
+
+impl FromStr for Validator {
+    type Err = String;
+
+    fn from_str(validator: &str) -> Result<Self, Self::Err> {
+        match validator {
+            "auto" => Ok(Validator::Auto),
+            "naga" => Ok(Validator::Naga),
+            "glslang" => Ok(Validator::Glslang),
+            _ => Err(format!(
+                "Unknown validator '{validator}', expected auto, naga or glslang"
+            )),
+        }
+    }
+}
+
+impl Validator {
+    /// Decides what [`Validator::Auto`] stands for
+    pub fn resolve(self) -> Self {
+        match self {
+            Validator::Auto if glslang_installed() => Validator::Glslang,
+            Validator::Auto => Validator::Naga,
+            validator => validator,
+        }
+    }
+}
+
+fn glslang_installed() -> bool {
+    Command::new(GLSLANG)
+        .arg("--version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// Stage of a shader file: `.vert` and `.vs` files, and other files with "vertex" in their
+/// name, are vertex shaders, everything else fragment shaders
+pub fn stage_of(path: &Path) -> ShaderStage {
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    let name = path.file_stem().and_then(|n| n.to_str()).unwrap_or("");
+    match extension.to_ascii_lowercase().as_str() {
+        "vert" | "vs" => ShaderStage::Vertex,
+        "frag" | "fs" => ShaderStage::Fragment,
+        _ if name.to_ascii_lowercase().contains("vertex") => ShaderStage::Vertex,
+        _ => ShaderStage::Fragment,
+    }
+}
+
+/// Checks a shader, returning the warnings when it compiles
+pub fn check_source(
+    source: &str,
+    stage: ShaderStage,
+    validator: Validator,
+) -> Result<Vec<Diagnostic>, ShaderError> {
+    match validator.resolve() {
+        Validator::Glslang => check_glslang(source, stage),
+        _ => check_naga(source, stage),
+    }
+}
+
+fn check_naga(source: &str, stage: ShaderStage) -> Result<Vec<Diagnostic>, ShaderError> {
+    use naga::front::glsl::{Frontend, Options};
+
+    let translation = translate::to_vulkan(source);
+    let naga_stage = match stage {
+        ShaderStage::Vertex => naga::ShaderStage::Vertex,
+        ShaderStage::Fragment => naga::ShaderStage::Fragment,
+    };
+    let errors = match Frontend::default().parse(&Options::from(naga_stage), &translation.source) {
+        Ok(_) => return Ok(Vec::new()),
+        Err(errors) => errors.errors,
+    };
+    let diagnostics = errors
+        .iter()
+        .map(|error| Diagnostic {
+            severity: Severity::Error,
+            line: error
+                .location(&translation.source)
+                .map(|location| translation.original_line(location.line_number)),
+            message: error.kind.to_string(),
+        })
+        .collect();
+    Err(ShaderError::Compile { stage, diagnostics })
+}
+
+fn check_glslang(source: &str, stage: ShaderStage) -> Result<Vec<Diagnostic>, ShaderError> {
+    let failed = |message: String| ShaderError::Compile {
+        stage,
+        diagnostics: vec![Diagnostic {
+            severity: Severity::Error,
+            line: None,
+            message,
+        }],
+    };
+    let stage_name = match stage {
+        ShaderStage::Vertex => "vert",
+        ShaderStage::Fragment => "frag",
+    };
+    let mut child = Command::new(GLSLANG)
+        .args(["--stdin", "-S", stage_name])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| failed(format!("Couldn't run {GLSLANG}: {e}")))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(source.as_bytes())
+            .map_err(|e| failed(format!("Couldn't write to {GLSLANG}: {e}")))?;
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|e| failed(format!("{GLSLANG} failed: {e}")))?;
+
+    let log = String::from_utf8_lossy(&output.stdout);
+    let diagnostics = glslang_diagnostics(&log);
+    if output.status.success() {
+        return Ok(diagnostics);
+    }
+    if diagnostics.is_empty() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(failed(format!("{} {}", log.trim(), stderr.trim())));
+    }
+    Err(ShaderError::Compile { stage, diagnostics })
+}
+
+/// The messages about lines of the source in glslangValidator output, which also names the
+/// file and counts the errors
+fn glslang_diagnostics(log: &str) -> Vec<Diagnostic> {
+    parse_info_log(log)
+        .into_iter()
+        .filter(|diagnostic| diagnostic.line.is_some())
+        .collect()
+}
+
+/// The shader files of `paths`, directories are searched for `.glsl`, `.vert`, `.frag`,
+/// `.vs` and `.fs` files
+fn shader_files(paths: &[PathBuf]) -> Result<Vec<PathBuf>, String> {
+    let mut files = Vec::new();
+    for path in paths {
+        if !path.is_dir() {
+            files.push(path.clone());
+            continue;
+        }
+        let entries = std::fs::read_dir(path)
+            .map_err(|e| format!("Couldn't read directory '{}': {e}", path.display()))?;
+        let mut shaders: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.extension().and_then(|e| e.to_str()).is_some_and(|e| {
+                    matches!(
+                        e.to_ascii_lowercase().as_str(),
+                        "glsl" | "vert" | "frag" | "vs" | "fs"
+                    )
+                })
+            })
+            .collect();
+        shaders.sort();
+        files.extend(shaders);
+    }
+    Ok(files)
+}
+
+pub fn run_check(args: &CheckArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let files = shader_files(&args.files)?;
+    if files.is_empty() {
+        return Err("No shaders to check".into());
+    }
+    let validator = args.validator.resolve();
+    log::debug!("Checking shaders with {validator:?}");
+
+    let mut failures = 0;
+    for path in &files {
+        let stage = args.stage.unwrap_or_else(|| stage_of(path));
+        let result = std::fs::read_to_string(path)
+            .map_err(|e| format!("Couldn't read shader: {e}"))
+            .and_then(|source| Ok(check_source(&source, stage, validator)?));
+        match result {
+            Ok(warnings) => {
+                println!("ok    {}", path.display());
+                for warning in warnings {
+                    println!("  {warning}");
+                }
+            }
+            Err(e) => {
+                failures += 1;
+                println!("ERROR {}: {e}", path.display());
+            }
+        }
+    }
+    if failures > 0 {
+        return Err(format!("{failures} of {} shaders failed to compile", files.len()).into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stage_of() {
+        assert_eq!(stage_of(Path::new("a/blur.vert")), ShaderStage::Vertex);
+        assert_eq!(
+            stage_of(Path::new("vertex_shader_3d.glsl")),
+            ShaderStage::Vertex
+        );
+        assert_eq!(stage_of(Path::new("crt.glsl")), ShaderStage::Fragment);
+        assert_eq!(stage_of(Path::new("vertex.frag")), ShaderStage::Fragment);
+    }
+
+    #[test]
+    fn test_builtin_shaders_pass_naga() {
+        let shaders = [
+            ("vertex_shader", include_str!("vertex_shader.glsl")),
+            ("vertex_shader_3d", include_str!("vertex_shader_3d.glsl")),
+            (
+                "vertex_shader_post",
+                include_str!("vertex_shader_post.glsl"),
+            ),
+            (
+                "vertex_shader_overlay",
+                include_str!("vertex_shader_overlay.glsl"),
+            ),
+            ("fragment_shader", include_str!("fragment_shader.glsl")),
+            (
+                "fragment_shader_3d",
+                include_str!("fragment_shader_3d.glsl"),
+            ),
+            (
+                "fragment_shader_overlay",
+                include_str!("fragment_shader_overlay.glsl"),
+            ),
+            (
+                "fragment_shader_post_crt",
+                include_str!("fragment_shader_post_crt.glsl"),
+            ),
+            (
+                "fragment_shader_post_output",
+                include_str!("fragment_shader_post_output.glsl"),
+            ),
+        ];
+        for (name, source) in shaders {
+            let stage = stage_of(Path::new(name));
+            if let Err(e) = check_source(source, stage, Validator::Naga) {
+                panic!("{name}: {e}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_naga_reports_original_lines() {
+        let source = "#version 330 core\n\
+                      uniform sampler2D u_texture;\n\
+                      out vec4 FragColor;\n\
+                      void main() {\n\
+                          FragColor = texture(u_texture, v_uv);\n\
+                      }\n";
+        let Err(ShaderError::Compile { stage, diagnostics }) =
+            check_source(source, ShaderStage::Fragment, Validator::Naga)
+        else {
+            panic!("expected a compile error");
+        };
+        assert_eq!(stage, ShaderStage::Fragment);
+        assert_eq!(diagnostics[0].line, Some(5));
+        assert!(diagnostics[0].message.contains("v_uv"));
+    }
+
+    #[test]
+    fn test_glslang_diagnostics() {
+        let log = "stdin\n\
+                   WARNING: 0:2: '' : unused\n\
+                   ERROR: 0:5: 'v_uv' : undeclared identifier\n\
+                   ERROR: 1 compilation errors.  No code generated.\n";
+        let diagnostics = glslang_diagnostics(log);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+        assert_eq!(diagnostics[1].line, Some(5));
+    }
+}
@@ -0,0 +1,183 @@
+//! Rewriting OpenGL GLSL into the Vulkan flavored GLSL 4.50 the naga front end parses
+//!
+//! naga wants explicit bindings for uniforms, separate textures and samplers, and only
+//! knows GLSL 4.40 and newer. Declarations are rewritten on their own line so that
+//! diagnostics keep pointing at the original source, the only added lines are `#define`s
+//! right after `#version`, see [`Translation::original_line`].
+
+/// A rewritten shader source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Translation {
+    pub source: String,
+    /// Line of the original source the added lines follow, 0 for the top
+    after_line: u32,
+    added_lines: u32,
+}
+
+impl Translation {
+    /// Maps a 1-based line of the translated source back to the original one
+    pub fn original_line(&self, line: u32) -> u32 {
+        if line <= self.after_line {
+            line
+        } else if line <= self.after_line + self.added_lines {
+            // an added line, blame the directive before it
+            self.after_line.max(1)
+        } else {
+            line - self.added_lines
+        }
+    }
+}
+
+/// Rewrites `source` for the naga GLSL front end
+pub fn to_vulkan(source: &str) -> Translation {
+    let mut defines = vec![
+        "#define gl_VertexID gl_VertexIndex".to_string(),
+        "#define gl_InstanceID gl_InstanceIndex".to_string(),
+    ];
+    let mut version_line = None;
+    let mut binding = 0;
+    let mut lines = Vec::new();
+    for (index, line) in source.lines().enumerate() {
+        let trimmed = line.trim_start();
+        let rewritten = if version_line.is_none() && trimmed.starts_with("#version") {
+            version_line = Some(index as u32 + 1);
+            "#version 450 core".to_string()
+        } else if trimmed.starts_with("precision ") {
+            // default precision of GLSL ES, naga always uses high precision
+            String::new()
+        } else if let Some(declaration) = trimmed.strip_prefix("uniform ") {
+            uniform(declaration, &mut binding, &mut defines)
+        } else if trimmed.starts_with("layout")
+            && trimmed.contains(" uniform ")
+            && !trimmed.contains("binding")
+        {
+            let (layout, rest) = trimmed.split_once('(').unwrap_or((trimmed, ""));
+            let rewritten = format!("{layout}(binding = {binding}, {rest}");
+            binding += 1;
+            rewritten
+        } else {
+            line.to_string()
+        };
+        lines.push(rewritten);
+    }
+
+    if version_line.is_none() {
+        defines.insert(0, "#version 450 core".to_string());
+    }
+    let after_line = version_line.unwrap_or(0);
+    let added_lines = defines.len() as u32;
+    let at = after_line as usize;
+    lines.splice(at..at, defines);
+    Translation {
+        source: lines.join("\n") + "\n",
+        after_line,
+        added_lines,
+    }
+}
+
+/// Rewrites the declaration after `uniform `, giving it a binding
+///
+/// `uniform sampler2D name;` becomes a `texture2D` and a `sampler`, and `name` a macro
+/// combining them so that `texture(name, uv)` keeps working. Only the declaration up to the
+/// first `;` is rewritten, the rest of the line stays as it is.
+fn uniform(line: &str, binding: &mut u32, defines: &mut Vec<String>) -> String {
+    let (declaration, rest) = match line.split_once(';') {
+        Some((declaration, rest)) => (declaration.trim_end(), format!(";{rest}")),
+        None => (line, String::new()),
+    };
+    // precision qualifiers of GLSL ES, dropped for samplers
+    let type_and_names = match declaration.split_once(char::is_whitespace) {
+        Some(("lowp" | "mediump" | "highp", rest)) => rest.trim_start(),
+        _ => declaration,
+    };
+    let (type_name, names) = type_and_names
+        .split_once(char::is_whitespace)
+        .unwrap_or((type_and_names, ""));
+    let names = names.trim();
+    let sampler_kind = type_name
+        .strip_prefix("sampler")
+        .filter(|kind| !kind.is_empty() && !kind.contains("Shadow"));
+    match sampler_kind {
+        Some(kind) if !names.contains('[') => {
+            let mut declarations = Vec::new();
+            for name in names.split(',').map(str::trim) {
+                declarations.push(format!(
+                    "layout(binding = {}) uniform texture{kind} {name}_texture; \
+                     layout(binding = {}) uniform sampler {name}_sampler",
+                    *binding,
+                    *binding + 1
+                ));
+                defines.push(format!(
+                    "#define {name} sampler{kind}({name}_texture, {name}_sampler)"
+                ));
+                *binding += 2;
+            }
+            declarations.join("; ") + &rest
+        }
+        _ => {
+            let rewritten = format!("layout(binding = {}) uniform {declaration}{rest}", *binding);
+            *binding += 1;
+            rewritten
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_vulkan() {
+        let source = "#version 330 core\n\
+                      uniform sampler2D u_texture, u_mask;\n\
+                      uniform float u_time;\n\
+                      out vec4 FragColor;\n";
+        let translation = to_vulkan(source);
+        let lines: Vec<&str> = translation.source.lines().collect();
+        assert_eq!(lines[0], "#version 450 core");
+        assert!(lines.contains(&"#define u_mask sampler2D(u_mask_texture, u_mask_sampler)"));
+        assert!(lines.contains(&"layout(binding = 4) uniform float u_time;"));
+        assert_eq!(*lines.last().unwrap(), "out vec4 FragColor;");
+        assert_eq!(lines.len(), source.lines().count() + 4);
+
+        // declarations stay on their line
+        assert_eq!(translation.original_line(1), 1);
+        assert_eq!(translation.original_line(3), 1);
+        assert_eq!(translation.original_line(8), 4);
+    }
+
+    #[test]
+    fn test_to_vulkan_keeps_rest_of_line() {
+        let source = "uniform sampler2D tex; // input\n\
+                      uniform highp sampler2D u_image;\n\
+                      uniform mediump float u_time; // seconds\n";
+        let lines: Vec<String> = to_vulkan(source).source.lines().map(String::from).collect();
+        assert_eq!(
+            lines[5],
+            "layout(binding = 0) uniform texture2D tex_texture; \
+             layout(binding = 1) uniform sampler tex_sampler; // input"
+        );
+        assert_eq!(
+            lines[6],
+            "layout(binding = 2) uniform texture2D u_image_texture; \
+             layout(binding = 3) uniform sampler u_image_sampler;"
+        );
+        assert_eq!(
+            lines[7],
+            "layout(binding = 4) uniform mediump float u_time; // seconds"
+        );
+        assert!(lines.contains(&"#define tex sampler2D(tex_texture, tex_sampler)".to_string()));
+        assert!(
+            lines.contains(
+                &"#define u_image sampler2D(u_image_texture, u_image_sampler)".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_to_vulkan_without_version() {
+        let translation = to_vulkan("void main() {}\n");
+        assert!(translation.source.starts_with("#version 450 core\n"));
+        assert_eq!(translation.original_line(4), 1);
+    }
+}
@@ -0,0 +1,307 @@
+//! Command line arguments
+//!
+//! Window options given on the command line override the `[window]` section of the config.
+use crate::check::Validator;
+use crate::config::Config;
+use crate::run::context::{GlProfile, GlVersion};
+use crate::run::output::Tonemap;
+use crate::run::post::PassSource;
+use crate::run::shader_program::ShaderStage;
+use crate::run::texture::TextureFormat;
+use crate::run::window::WindowConfig;
+use clap::{Args, Parser, Subcommand};
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Default, Parser)]
+#[command(version, about = "Live preview for 2D shaders and 3D models")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// A `.obj`, `.gltf` or `.glb` model to preview in 3D, a `.json` Shadertoy export or a
+    /// `.fs` ISF shader, instead of the 2D scene, and `NAME=IMAGE` pairs binding images to
+    /// `sampler2D` uniforms and ISF image inputs, e.g. `iChannel0=noise.png`
+    #[arg(value_name = "FILES")]
+    pub files: Vec<String>,
+
+    /// Config file to read instead of `shader_preview.toml`
+    #[arg(long, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub window: WindowArgs,
+
+    /// Post-processing pass to run, the name of a builtin (bloom, blur, vignette, crt,
+    /// chromatic_aberration) or a fragment shader file, repeat for a chain. Replaces the passes
+    /// of the config file
+    #[arg(long, value_name = "PASS")]
+    pub post: Vec<PassSource>,
+
+    /// Format to render the frame into: rgba8, or rgba16f and rgba32f for HDR
+    #[arg(long, value_name = "FORMAT")]
+    pub target_format: Option<TextureFormat>,
+    /// Tonemapping operator: none, reinhard, aces or filmic
+    #[arg(long, value_name = "OPERATOR")]
+    pub tonemap: Option<Tonemap>,
+    /// Multiplies colors before tonemapping
+    #[arg(long)]
+    pub exposure: Option<f32>,
+    /// Treat shader output as linear light: decode images from sRGB and encode the frame
+    #[arg(long)]
+    pub srgb: bool,
+
+    /// OpenGL context version to request, e.g. 4.1
+    #[arg(long, value_name = "VERSION")]
+    pub gl_version: Option<GlVersion>,
+    /// OpenGL profile to request: any, core, compat or es
+    #[arg(long, value_name = "PROFILE")]
+    pub gl_profile: Option<GlProfile>,
+    /// Request a debug context and log the messages of the GL driver
+    #[arg(long)]
+    pub gl_debug: bool,
+    /// Stop on the first high severity GL message or GL error
+    #[arg(long)]
+    pub gl_fatal: bool,
+}
+
+impl Cli {
+    /// Overrides the settings of `config` that were given on the command line
+    pub fn apply(&self, config: &mut Config) {
+        self.window.apply(&mut config.window);
+        if !self.post.is_empty() {
+            config.post.passes = self.post.clone();
+        }
+        let output = &mut config.output;
+        if let Some(format) = self.target_format {
+            output.format = format;
+        }
+        if let Some(tonemap) = self.tonemap {
+            output.tonemap = tonemap;
+        }
+        if let Some(exposure) = self.exposure {
+            output.exposure = exposure;
+        }
+        output.srgb |= self.srgb;
+        if self.gl_version.is_some() {
+            config.gl.version = self.gl_version;
+        }
+        if let Some(profile) = self.gl_profile {
+            config.gl.profile = profile;
+        }
+        config.gl.debug |= self.gl_debug;
+        config.gl.fatal_errors |= self.gl_fatal;
+    }
+
+    /// Splits [`Cli::files`] into the `NAME=IMAGE` texture arguments and the model path
+    ///
+    /// Fails when more than one file to preview is given.
+    pub fn textures_and_model(&self) -> Result<(Vec<String>, Option<PathBuf>), String> {
+        let (textures, models): (Vec<String>, Vec<String>) = self
+            .files
+            .iter()
+            .cloned()
+            .partition(|arg| arg.contains('='));
+        match &models[..] {
+            [] => Ok((textures, None)),
+            [model] => Ok((textures, Some(PathBuf::from(model)))),
+            [first, second, ..] => Err(format!(
+                "Only one file can be previewed at a time, got '{first}' and '{second}'"
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum Command {
+    /// Render the shaders and models of a directory headless and compare them with reference
+    /// images
+    Test(TestArgs),
+    /// Compile shaders without a GL context and report their errors, for pre-commit hooks
+    Check(CheckArgs),
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct TestArgs {
+    /// Directory with the fragment shaders and models to test, and optionally
+    /// `shader_test.toml`
+    pub dir: PathBuf,
+    /// Write the rendered images as the new references instead of comparing
+    #[arg(long)]
+    pub update: bool,
+    /// Directory for rendered images, diffs and the report, `DIR/output` by default
+    #[arg(long, value_name = "PATH")]
+    pub output: Option<PathBuf>,
+    /// Largest color difference (CIE76 delta E) of a pixel, overrides `shader_test.toml`
+    #[arg(long, value_name = "DELTA_E")]
+    pub tolerance: Option<f32>,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct CheckArgs {
+    /// Shader files, or directories of them, to check
+    #[arg(required = true)]
+    pub files: Vec<PathBuf>,
+    /// Stage of all the shaders: vertex or fragment. By default `.vert` files and files with
+    /// "vertex" in their name are vertex shaders
+    #[arg(long, value_name = "STAGE")]
+    pub stage: Option<ShaderStage>,
+    /// Compiler to check with: auto, naga or glslang. auto uses glslangValidator when it is
+    /// installed
+    #[arg(long, value_name = "VALIDATOR", default_value = "auto")]
+    pub validator: Validator,
+}
+
+#[derive(Debug, Clone, Default, Args)]
+pub struct WindowArgs {
+    /// Window width in screen coordinates
+    #[arg(long)]
+    pub width: Option<u32>,
+    /// Window height in screen coordinates
+    #[arg(long)]
+    pub height: Option<u32>,
+    #[arg(long)]
+    pub title: Option<String>,
+    /// Start in fullscreen, F11 toggles it at runtime
+    #[arg(long)]
+    pub fullscreen: bool,
+    /// Index of the monitor to go fullscreen on, the primary monitor by default
+    #[arg(long, value_name = "INDEX")]
+    pub monitor: Option<usize>,
+    /// Don't wait for the vertical blank, for benchmarking
+    #[arg(long)]
+    pub no_vsync: bool,
+    /// Multisample anti-aliasing samples per pixel, 0 turns it off
+    #[arg(long, value_name = "SAMPLES")]
+    pub msaa: Option<u32>,
+}
+
+impl WindowArgs {
+    /// Overrides the settings of `config` that were given on the command line
+    pub fn apply(&self, config: &mut WindowConfig) {
+        if let Some(width) = self.width {
+            config.width = width;
+        }
+        if let Some(height) = self.height {
+            config.height = height;
+        }
+        if let Some(title) = &self.title {
+            config.title = title.clone();
+        }
+        config.fullscreen |= self.fullscreen;
+        if self.monitor.is_some() {
+            config.monitor = self.monitor;
+        }
+        config.vsync &= !self.no_vsync;
+        if let Some(samples) = self.msaa {
+            config.samples = samples;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_cli() {
+        Cli::command().debug_assert();
+
+        let cli = Cli::parse_from(["shader_preview", "iChannel0=a.png", "model.obj", "b=c.jpg"]);
+        let (textures, model) = cli.textures_and_model().unwrap();
+        assert_eq!(textures, ["iChannel0=a.png", "b=c.jpg"]);
+        assert_eq!(model, Some(PathBuf::from("model.obj")));
+        let cli = Cli::parse_from(["shader_preview", "a.obj", "t=a.png", "b.fs"]);
+        assert!(cli.textures_and_model().is_err());
+        assert!(cli.command.is_none());
+
+        let cli = Cli::parse_from(["shader_preview", "--config", "a.toml", "test", "shaders"]);
+        let Some(Command::Test(args)) = &cli.command else {
+            panic!("expected the test subcommand");
+        };
+        assert_eq!(args.dir, PathBuf::from("shaders"));
+        assert_eq!(cli.config, Some(PathBuf::from("a.toml")));
+
+        let cli = Cli::parse_from(["shader_preview", "check", "a.glsl", "--stage", "vertex"]);
+        let Some(Command::Check(args)) = &cli.command else {
+            panic!("expected the check subcommand");
+        };
+        assert_eq!(args.stage, Some(ShaderStage::Vertex));
+        assert_eq!(args.validator, Validator::Auto);
+        assert!(Cli::try_parse_from(["shader_preview", "check"]).is_err());
+    }
+
+    #[test]
+    fn test_post_passes_replace_config() {
+        let mut config = Config::parse("[post]\npasses = [\"vignette\"]").unwrap();
+        Cli::parse_from(["shader_preview"]).apply(&mut config);
+        assert_eq!(config.post.passes, ["vignette".parse().unwrap()]);
+
+        let cli = Cli::parse_from(["shader_preview", "--post", "crt", "--post", "pass.glsl"]);
+        cli.apply(&mut config);
+        assert_eq!(
+            config.post.passes,
+            ["crt".parse().unwrap(), "pass.glsl".parse().unwrap()]
+        );
+    }
+
+    #[test]
+    fn test_output_args() {
+        let mut config = Config::default();
+        let cli = Cli::parse_from([
+            "shader_preview",
+            "--target-format",
+            "rgba16f",
+            "--tonemap",
+            "reinhard",
+            "--srgb",
+        ]);
+        cli.apply(&mut config);
+        assert_eq!(config.output.format, TextureFormat::Rgba16F);
+        assert_eq!(config.output.tonemap, Tonemap::Reinhard);
+        assert_eq!(config.output.exposure, 1.0);
+        assert!(config.output.srgb);
+        assert!(Cli::try_parse_from(["shader_preview", "--tonemap", "hable"]).is_err());
+    }
+
+    #[test]
+    fn test_gl_args() {
+        let mut config = Config::default();
+        let cli = Cli::parse_from([
+            "shader_preview",
+            "--gl-version",
+            "3.0",
+            "--gl-profile",
+            "es",
+        ]);
+        cli.apply(&mut config);
+        assert_eq!(config.gl.version, Some(GlVersion { major: 3, minor: 0 }));
+        assert_eq!(config.gl.profile, GlProfile::Es);
+        assert!(Cli::try_parse_from(["shader_preview", "--gl-version", "three"]).is_err());
+    }
+
+    #[test]
+    fn test_window_args_override_config() {
+        let cli = Cli::parse_from([
+            "shader_preview",
+            "--width",
+            "1280",
+            "--no-vsync",
+            "--msaa",
+            "4",
+        ]);
+        let mut config = WindowConfig {
+            height: 400,
+            samples: 8,
+            fullscreen: true,
+            ..Default::default()
+        };
+        cli.window.apply(&mut config);
+        assert_eq!(config.width, 1280);
+        assert_eq!(config.height, 400);
+        assert_eq!(config.samples, 4);
+        assert!(!config.vsync);
+        // flags that weren't given keep the config value
+        assert!(config.fullscreen);
+    }
+}
@@ -0,0 +1,144 @@
+//! User configuration, read from [`CONFIG_FILE`] in the working directory
+//!
+//! Every section and field is optional, missing ones keep their defaults.
+//!
+//! # Examples
+//! ```
+//! use shader_preview::config::Config;
+//!
+//! let config = Config::parse(r#"
+//!     [bindings]
+//!     pause = ["P", "Space"]
+//! "#).unwrap();
+//! assert_eq!(config.bindings.bindings(shader_preview::run::input::Action::Pause).len(), 2);
+//! ```
+use crate::run::camera::MotionConfig;
+use crate::run::context::GlConfig;
+use crate::run::input::InputMap;
+use crate::run::output::OutputConfig;
+use crate::run::post::PostConfig;
+use crate::run::scene::SceneConfig;
+use crate::run::window::WindowConfig;
+use serde::Deserialize;
+use std::path::Path;
+
+pub const CONFIG_FILE: &str = "shader_preview.toml";
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Action names mapped to lists of keys, mouse buttons or scroll directions
+    pub bindings: InputMap,
+    /// Keyboard camera speeds
+    pub camera: MotionConfig,
+    /// Window size, fullscreen, v-sync and anti-aliasing, read once at startup
+    pub window: WindowConfig,
+    /// Background color, and the render state of the 2D scene
+    pub scene: SceneConfig,
+    /// Post-processing passes applied to the 2D scene and the model
+    pub post: PostConfig,
+    /// Render target format, tonemapping and sRGB handling
+    pub output: OutputConfig,
+    /// Debug context and GL error handling
+    pub gl: GlConfig,
+}
+
+impl Config {
+    pub fn parse(source: &str) -> Result<Self, String> {
+        toml::from_str(source).map_err(|e| format!("Invalid config: {e}"))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| format!("Couldn't read config '{}': {e}", path.display()))?;
+        Self::parse(&source).map_err(|e| format!("{e} in '{}'", path.display()))
+    }
+
+    /// Loads [`CONFIG_FILE`] if it exists, the default config otherwise
+    pub fn load_or_default() -> Result<Self, String> {
+        let path = Path::new(CONFIG_FILE);
+        if path.exists() {
+            Self::load(path)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    /// Loads `path` if given, falls back to [`Config::load_or_default`] otherwise
+    pub fn load_from(path: Option<&Path>) -> Result<Self, String> {
+        path.map_or_else(Self::load_or_default, Self::load)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::run::input::{Action, Binding};
+    use crate::run::render_state::{BlendMode, PolygonMode, RenderState};
+    use glfw::Key;
+
+    #[test]
+    fn test_bindings_merge_with_defaults() {
+        let config = Config::parse(
+            r#"
+            [bindings]
+            quit = ["Q"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.bindings.bindings(Action::Quit),
+            &[Binding::Key(Key::Q)]
+        );
+        assert_eq!(
+            config.bindings.bindings(Action::Pause),
+            Config::default().bindings.bindings(Action::Pause)
+        );
+    }
+
+    #[test]
+    fn test_scene_config() {
+        let config = Config::parse(
+            r#"
+            [scene]
+            clear_color = [0.0, 0.2, 0.4]
+
+            [scene.render_state]
+            blend = "additive"
+            polygon_mode = "wireframe"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.scene,
+            SceneConfig {
+                clear_color: [0.0, 0.2, 0.4],
+                render_state: RenderState::default()
+                    .with_blend(BlendMode::Additive)
+                    .with_polygon_mode(PolygonMode::Wireframe),
+            }
+        );
+        assert!(
+            Config::parse(
+                "[scene]
+clear_color = [0.0, 0.2]"
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_example_config() {
+        let example = Config::parse(include_str!("../shader_preview.example.toml")).unwrap();
+        assert_eq!(example, Config::default());
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        assert!(Config::parse("[bindings]\nquit = [\"NotAKey\"]").is_err());
+        assert!(Config::parse("[bindings]\nfly = [\"F\"]").is_err());
+        assert!(Config::parse("[unknown]").is_err());
+        assert!(Config::parse("[window]\nwidth = -1").is_err());
+    }
+}
@@ -0,0 +1,16 @@
+#version 330 core
+uniform float u_time;
+uniform vec3 u_color;
+
+in vec3 vNormal;
+in vec2 vUv;
+
+out vec4 FragColor;
+
+const vec3 LIGHT_DIR = normalize(vec3(0.4, 1.0, 0.6));
+
+void main() {
+    // simple lambert shading with some ambient so the back side isn't black
+    float diffuse = max(dot(normalize(vNormal), LIGHT_DIR), 0.0);
+    FragColor = vec4(u_color * (0.2 + 0.8 * diffuse), 1.0);
+}
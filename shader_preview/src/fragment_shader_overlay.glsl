@@ -0,0 +1,12 @@
+#version 330 core
+in vec2 vUv;
+in vec4 vColor;
+
+// font atlas, glyph coverage is stored in alpha
+uniform sampler2D u_font;
+
+out vec4 FragColor;
+
+void main() {
+    FragColor = vec4(vColor.rgb, vColor.a * texture(u_font, vUv).a);
+}
@@ -0,0 +1,29 @@
+#version 330 core
+in vec2 v_uv;
+out vec4 FragColor;
+
+uniform sampler2D u_texture;
+uniform vec2 u_resolution;
+
+// brightness above which pixels glow, and how strongly
+const float THRESHOLD = 0.6;
+const float INTENSITY = 1.5;
+const int RADIUS = 4;
+// pixels between samples, spreads the glow without more samples
+const float SPREAD = 3.0;
+
+void main() {
+    vec2 texel = SPREAD / u_resolution;
+    vec3 color = texture(u_texture, v_uv).rgb;
+    vec3 glow = vec3(0.0);
+    float total = 0.0;
+    for (int x = -RADIUS; x <= RADIUS; x++) {
+        for (int y = -RADIUS; y <= RADIUS; y++) {
+            float weight = exp(-float(x * x + y * y) / float(RADIUS * RADIUS));
+            vec3 neighbour = texture(u_texture, v_uv + vec2(x, y) * texel).rgb;
+            glow += max(neighbour - THRESHOLD, 0.0) * weight;
+            total += weight;
+        }
+    }
+    FragColor = vec4(color + glow / total * INTENSITY, 1.0);
+}
@@ -0,0 +1,23 @@
+#version 330 core
+in vec2 v_uv;
+out vec4 FragColor;
+
+uniform sampler2D u_texture;
+uniform vec2 u_resolution;
+
+const int RADIUS = 3;
+const float SIGMA = 2.0;
+
+void main() {
+    vec2 texel = 1.0 / u_resolution;
+    vec3 sum = vec3(0.0);
+    float total = 0.0;
+    for (int x = -RADIUS; x <= RADIUS; x++) {
+        for (int y = -RADIUS; y <= RADIUS; y++) {
+            float weight = exp(-float(x * x + y * y) / (2.0 * SIGMA * SIGMA));
+            sum += texture(u_texture, v_uv + vec2(x, y) * texel).rgb * weight;
+            total += weight;
+        }
+    }
+    FragColor = vec4(sum / total, 1.0);
+}
@@ -0,0 +1,17 @@
+#version 330 core
+in vec2 v_uv;
+out vec4 FragColor;
+
+uniform sampler2D u_texture;
+uniform vec2 u_resolution;
+
+// separation of the color channels in pixels at the corners
+const float STRENGTH = 6.0;
+
+void main() {
+    vec2 offset = (v_uv - 0.5) * 2.0 * STRENGTH / u_resolution;
+    float r = texture(u_texture, v_uv + offset).r;
+    float g = texture(u_texture, v_uv).g;
+    float b = texture(u_texture, v_uv - offset).b;
+    FragColor = vec4(r, g, b, 1.0);
+}
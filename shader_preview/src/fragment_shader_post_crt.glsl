@@ -0,0 +1,25 @@
+#version 330 core
+in vec2 v_uv;
+out vec4 FragColor;
+
+uniform sampler2D u_texture;
+uniform vec2 u_resolution;
+uniform float u_time;
+
+const float CURVATURE = 0.08;
+
+void main() {
+    // bulge the image like a curved screen
+    vec2 centered = v_uv * 2.0 - 1.0;
+    centered *= 1.0 + CURVATURE * dot(centered, centered);
+    vec2 uv = centered * 0.5 + 0.5;
+    if (any(lessThan(uv, vec2(0.0))) || any(greaterThan(uv, vec2(1.0)))) {
+        FragColor = vec4(0.0, 0.0, 0.0, 1.0);
+        return;
+    }
+
+    vec3 color = texture(u_texture, uv).rgb;
+    float scanline = 0.8 + 0.2 * sin(uv.y * u_resolution.y * 3.14159);
+    float flicker = 0.97 + 0.03 * sin(u_time * 50.0);
+    FragColor = vec4(color * scanline * flicker, 1.0);
+}
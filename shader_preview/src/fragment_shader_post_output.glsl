@@ -0,0 +1,46 @@
+#version 330 core
+in vec2 v_uv;
+out vec4 FragColor;
+
+uniform sampler2D u_texture;
+uniform float u_exposure;
+// 0 none, 1 Reinhard, 2 ACES, 3 filmic, matches Tonemap in output.rs
+uniform int u_tonemap;
+uniform bool u_srgb;
+
+vec3 aces(vec3 x) {
+    return (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14);
+}
+
+vec3 hable(vec3 x) {
+    const float A = 0.15, B = 0.50, C = 0.10, D = 0.20, E = 0.02, F = 0.30;
+    return (x * (A * x + C * B) + D * E) / (x * (A * x + B) + D * F) - E / F;
+}
+
+vec3 filmic(vec3 x) {
+    const float WHITE = 11.2;
+    const float EXPOSURE_BIAS = 2.0;
+    return hable(x * EXPOSURE_BIAS) / hable(vec3(WHITE));
+}
+
+vec3 srgb_encode(vec3 linear) {
+    vec3 low = linear * 12.92;
+    vec3 high = 1.055 * pow(linear, vec3(1.0 / 2.4)) - 0.055;
+    return mix(high, low, lessThanEqual(linear, vec3(0.0031308)));
+}
+
+void main() {
+    vec3 color = max(texture(u_texture, v_uv).rgb * u_exposure, 0.0);
+    if (u_tonemap == 1) {
+        color = color / (1.0 + color);
+    } else if (u_tonemap == 2) {
+        color = aces(color);
+    } else if (u_tonemap == 3) {
+        color = filmic(color);
+    }
+    color = clamp(color, 0.0, 1.0);
+    if (u_srgb) {
+        color = srgb_encode(color);
+    }
+    FragColor = vec4(color, 1.0);
+}
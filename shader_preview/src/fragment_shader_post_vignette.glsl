@@ -0,0 +1,14 @@
+#version 330 core
+in vec2 v_uv;
+out vec4 FragColor;
+
+uniform sampler2D u_texture;
+uniform vec2 u_resolution;
+
+void main() {
+    vec3 color = texture(u_texture, v_uv).rgb;
+    vec2 centered = v_uv - 0.5;
+    centered.x *= u_resolution.x / u_resolution.y;
+    float vignette = smoothstep(0.9, 0.3, length(centered));
+    FragColor = vec4(color * mix(0.25, 1.0, vignette), 1.0);
+}
@@ -0,0 +1,469 @@
+//! Golden image tests of shaders and models, run with `shader_preview test DIR`
+//!
+//! Every fragment shader (`.glsl`, `.frag`) and model (`.obj`, `.gltf`, `.glb`) in the
+//! directory is a test case. Each is rendered headless at the resolutions and times of
+//! [`TEST_CONFIG_FILE`] and compared with `reference/<case>_<width>x<height>_<time>s.png`.
+//! Shaders are drawn like post-processing passes, with a test pattern or the configured input
+//! image bound to `u_texture`.
+//!
+//! Rendered images and diffs of failing cases, and `report.md`, are written to the output
+//! directory. `--update` writes the rendered images as the new references instead.
+use crate::cli::{Cli, TestArgs};
+use crate::config::Config;
+use crate::run::headless::Headless;
+use crate::run::model_view::ModelView;
+use crate::run::shader_program::ShaderProgram;
+use crate::run::texture::{Pixels, Texture, TextureData, TextureOptions};
+use compare::{Comparison, Tolerance};
+use image::RgbaImage;
+use serde::{Deserialize, Deserializer};
+use std::collections::HashMap;
+use std::fmt::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+pub mod compare;
+
+pub const TEST_CONFIG_FILE: &str = "shader_test.toml";
+
+/// Size of a rendered image, written as `"640x480"`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Resolution {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl FromStr for Resolution {
+    type Err = String;
+
+    fn from_str(resolution: &str) -> Result<Self, Self::Err> {
+        let error = || format!("Invalid resolution '{resolution}', expected e.g. '640x480'");
+        let (width, height) = resolution.split_once('x').ok_or_else(error)?;
+        let (width, height) = (
+            width.parse().map_err(|_| error())?,
+            height.parse().map_err(|_| error())?,
+        );
+        if width == 0 || height == 0 {
+            return Err(error());
+        }
+        Ok(Resolution { width, height })
+    }
+}
+
+impl<'de> Deserialize<'de> for Resolution {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let resolution = String::deserialize(deserializer)?;
+        resolution.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl fmt::Display for Resolution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}", self.width, self.height)
+    }
+}
+
+/// Settings of a test directory, read from [`TEST_CONFIG_FILE`] in it
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TestConfig {
+    /// Every case is rendered at each resolution and time
+    pub resolutions: Vec<Resolution>,
+    /// Values of `u_time` in seconds
+    pub times: Vec<f64>,
+    /// Largest CIE76 color difference of a pixel that still counts as equal
+    pub tolerance: f32,
+    /// Share of pixels, from 0 to 1, that may differ more than `tolerance`
+    pub max_failing_pixels: f32,
+    /// Image bound to `u_texture` of the shaders, relative to the test directory
+    pub input: Option<PathBuf>,
+}
+
+impl Default for TestConfig {
+    fn default() -> Self {
+        TestConfig {
+            resolutions: vec![Resolution {
+                width: 256,
+                height: 256,
+            }],
+            times: vec![0.0],
+            tolerance: 2.0,
+            max_failing_pixels: 0.0,
+            input: None,
+        }
+    }
+}
+
+impl TestConfig {
+    /// Reads [`TEST_CONFIG_FILE`] in `dir`, the defaults if there is none
+    pub fn load(dir: &Path) -> Result<Self, String> {
+        let path = dir.join(TEST_CONFIG_FILE);
+        if !path.exists() {
+            return Ok(TestConfig::default());
+        }
+        let text = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Couldn't read '{}': {e}", path.display()))?;
+        toml::from_str(&text).map_err(|e| format!("Invalid '{}': {e}", path.display()))
+    }
+
+    pub fn tolerance(&self) -> Tolerance {
+        Tolerance {
+            max_delta_e: self.tolerance,
+            max_failing_pixels: self.max_failing_pixels,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CaseKind {
+    Shader,
+    Model,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Case {
+    /// File name without the extension, reference images start with it
+    name: String,
+    path: PathBuf,
+    kind: CaseKind,
+}
+
+/// The shaders and models in `dir`, sorted by file name
+///
+/// Fails when two of them only differ in the extension, they would share reference images.
+fn find_cases(dir: &Path) -> Result<Vec<Case>, String> {
+    let entries = std::fs::read_dir(dir)
+        .map_err(|e| format!("Couldn't read test directory '{}': {e}", dir.display()))?;
+    let mut cases: Vec<Case> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter_map(|path| {
+            let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+            let kind = match extension.as_str() {
+                "glsl" | "frag" => CaseKind::Shader,
+                "obj" | "gltf" | "glb" => CaseKind::Model,
+                _ => return None,
+            };
+            let name = path.file_stem()?.to_str()?.to_string();
+            Some(Case { name, path, kind })
+        })
+        .collect();
+    cases.sort_by(|a, b| a.path.cmp(&b.path));
+    let mut paths = HashMap::new();
+    for case in &cases {
+        if let Some(other) = paths.insert(&case.name, &case.path) {
+            return Err(format!(
+                "'{}' and '{}' would share reference images, rename one of them",
+                other.display(),
+                case.path.display()
+            ));
+        }
+    }
+    Ok(cases)
+}
+
+/// File name of the reference image of one rendering of a case
+fn image_name(case: &str, resolution: Resolution, time: f64) -> String {
+    format!("{case}_{resolution}_{time}s.png")
+}
+
+enum Outcome {
+    Passed(Comparison),
+    Failed(Comparison),
+    /// The rendered image doesn't match the size of the reference, or the case didn't render
+    Error(String),
+    MissingReference,
+    Updated,
+}
+
+impl Outcome {
+    fn is_failure(&self) -> bool {
+        !matches!(self, Outcome::Passed(_) | Outcome::Updated)
+    }
+}
+
+/// 256x256 texture with red and green gradients along x and y and a blue checkerboard
+fn test_pattern() -> TextureData {
+    let size = 256;
+    let pixels = (0..size)
+        .flat_map(|y| {
+            (0..size).flat_map(move |x| {
+                let checker = if (x / 32 + y / 32) % 2 == 0 { 255 } else { 64 };
+                [x as u8, y as u8, checker, 255]
+            })
+        })
+        .collect();
+    TextureData {
+        width: size,
+        height: size,
+        pixels: Pixels::Rgba8(pixels),
+    }
+}
+
+/// Runs the golden image tests of `args.dir`, fails if any image doesn't match
+pub fn run_tests(cli: &Cli, args: &TestArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let mut config = Config::load_from(cli.config.as_deref())?;
+    cli.apply(&mut config);
+    let mut test_config = TestConfig::load(&args.dir)?;
+    if let Some(tolerance) = args.tolerance {
+        test_config.tolerance = tolerance;
+    }
+    let tolerance = test_config.tolerance();
+    let cases = find_cases(&args.dir)?;
+    if cases.is_empty() {
+        return Err(format!("No shaders or models to test in '{}'", args.dir.display()).into());
+    }
+    let reference_dir = args.dir.join("reference");
+    let output_dir = args
+        .output
+        .clone()
+        .unwrap_or_else(|| args.dir.join("output"));
+    let create_dir = |dir: &Path| {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Couldn't create directory '{}': {e}", dir.display()))
+    };
+    create_dir(&output_dir)?;
+    if args.update {
+        create_dir(&reference_dir)?;
+    }
+
+    let headless = Headless::new(&config.gl)?;
+    let input = match &test_config.input {
+        Some(path) => Texture::load(args.dir.join(path), &TextureOptions::default())?,
+        None => Texture::upload(&test_pattern(), &TextureOptions::default()),
+    };
+
+    let mut results = Vec::new();
+    for case in &cases {
+        let renderer = match case.kind {
+            CaseKind::Shader => headless.load_shader(&case.path).map(Renderer::Shader),
+            CaseKind::Model => ModelView::load(&case.path)
+                .map(|model| Renderer::Model(Box::new(model)))
+                .map_err(|e| e.to_string()),
+        };
+        let renderer = match renderer {
+            Ok(renderer) => renderer,
+            Err(e) => {
+                let name = case.path.file_name().unwrap_or_default().to_string_lossy();
+                let outcome = Outcome::Error(e);
+                print_result(&name, &outcome);
+                results.push((name.into_owned(), outcome));
+                continue;
+            }
+        };
+
+        for &resolution in &test_config.resolutions {
+            for &time in &test_config.times {
+                let name = image_name(&case.name, resolution, time);
+                let size = (resolution.width, resolution.height);
+                let image = match &renderer {
+                    Renderer::Shader(program) => {
+                        headless.render_shader(program, &input, size, time)
+                    }
+                    Renderer::Model(model) => headless.render_model(model, size, time),
+                };
+                let outcome = match image {
+                    Ok(image) if args.update => {
+                        save(&image, &reference_dir.join(&name))?;
+                        Outcome::Updated
+                    }
+                    Ok(image) => {
+                        let outcome = check(&image, &reference_dir.join(&name), &tolerance);
+                        // keep what was rendered for review, and where it differs
+                        if outcome.is_failure() {
+                            save(&image, &output_dir.join(&name))?;
+                        }
+                        if let Outcome::Failed(comparison) = &outcome {
+                            save(&comparison.diff, &output_dir.join(diff_name(&name)))?;
+                        }
+                        outcome
+                    }
+                    Err(e) => Outcome::Error(e),
+                };
+                print_result(&name, &outcome);
+                results.push((name, outcome));
+            }
+        }
+    }
+
+    let report_path = output_dir.join("report.md");
+    std::fs::write(&report_path, report(&results, &tolerance))
+        .map_err(|e| format!("Couldn't write '{}': {e}", report_path.display()))?;
+    let failures = results.iter().filter(|(_, o)| o.is_failure()).count();
+    let verb = if args.update { "rendered" } else { "match" };
+    println!(
+        "{} of {} images {verb}, report written to '{}'",
+        results.len() - failures,
+        results.len(),
+        report_path.display()
+    );
+    if failures > 0 {
+        return Err(format!("{failures} golden image tests failed").into());
+    }
+    Ok(())
+}
+
+/// What a case is drawn with, loaded once for all its images
+enum Renderer {
+    Shader(ShaderProgram),
+    Model(Box<ModelView>),
+}
+
+fn save(image: &RgbaImage, path: &Path) -> Result<(), String> {
+    image
+        .save(path)
+        .map_err(|e| format!("Couldn't save '{}': {e}", path.display()))
+}
+
+fn print_result(name: &str, outcome: &Outcome) {
+    match outcome {
+        Outcome::Passed(_) => println!("ok      {name}"),
+        Outcome::Updated => println!("updated {name}"),
+        Outcome::Failed(comparison) => println!(
+            "FAILED  {name}: {} pixels differ, max ΔE {:.2}",
+            comparison.failing_pixels, comparison.max_delta_e
+        ),
+        Outcome::Error(e) => println!("ERROR   {name}: {e}"),
+        Outcome::MissingReference => println!("MISSING {name}: no reference, run with --update"),
+    }
+}
+
+/// Compares `image` with the reference at `path`
+///
+/// A reference that can't be loaded is an error of this image only, the run goes on.
+fn check(image: &RgbaImage, path: &Path, tolerance: &Tolerance) -> Outcome {
+    if !path.exists() {
+        return Outcome::MissingReference;
+    }
+    let reference = match image::open(path) {
+        Ok(reference) => reference.into_rgba8(),
+        Err(e) => {
+            return Outcome::Error(format!("Couldn't load reference '{}': {e}", path.display()));
+        }
+    };
+    match compare::compare(image, &reference, tolerance) {
+        Ok(comparison) if comparison.passes(tolerance) => Outcome::Passed(comparison),
+        Ok(comparison) => Outcome::Failed(comparison),
+        Err(e) => Outcome::Error(e),
+    }
+}
+
+/// Markdown table of the results
+fn report(results: &[(String, Outcome)], tolerance: &Tolerance) -> String {
+    let count = |f: fn(&Outcome) -> bool| results.iter().filter(|(_, o)| f(o)).count();
+    let mut report = String::from("# Golden image report\n\n");
+    let _ = writeln!(
+        report,
+        "{} passed, {} failed, {} errors, {} without reference, {} updated. Tolerance: \
+         ΔE {}, {}% of the pixels.\n",
+        count(|o| matches!(o, Outcome::Passed(_))),
+        count(|o| matches!(o, Outcome::Failed(_))),
+        count(|o| matches!(o, Outcome::Error(_))),
+        count(|o| matches!(o, Outcome::MissingReference)),
+        count(|o| matches!(o, Outcome::Updated)),
+        tolerance.max_delta_e,
+        tolerance.max_failing_pixels * 100.0,
+    );
+    report.push_str("| Image | Result | Max ΔE | Failing pixels |\n|---|---|---|---|\n");
+    for (name, outcome) in results {
+        let _ = match outcome {
+            Outcome::Passed(comparison) | Outcome::Failed(comparison) => {
+                let result = if matches!(outcome, Outcome::Passed(_)) {
+                    "passed".to_string()
+                } else {
+                    format!("**failed**, [diff]({})", diff_name(name))
+                };
+                writeln!(
+                    report,
+                    "| {name} | {result} | {:.2} | {} ({:.2}%) |",
+                    comparison.max_delta_e,
+                    comparison.failing_pixels,
+                    comparison.failing_pixels as f32 / comparison.total_pixels as f32 * 100.0
+                )
+            }
+            Outcome::Error(e) => {
+                writeln!(
+                    report,
+                    "| {name} | **error**: {} | | |",
+                    e.replace('\n', " ")
+                )
+            }
+            Outcome::MissingReference => {
+                writeln!(
+                    report,
+                    "| {name} | **no reference**, run with `--update` | | |"
+                )
+            }
+            Outcome::Updated => writeln!(report, "| {name} | updated | | |"),
+        };
+    }
+    report
+}
+
+fn diff_name(image_name: &str) -> String {
+    image_name.replace(".png", "_diff.png")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_test_config() {
+        let config: TestConfig =
+            toml::from_str("resolutions = [\"64x32\", \"8x8\"]\ntimes = [0.0, 1.5]").unwrap();
+        assert_eq!(
+            config.resolutions,
+            [
+                Resolution {
+                    width: 64,
+                    height: 32
+                },
+                Resolution {
+                    width: 8,
+                    height: 8
+                }
+            ]
+        );
+        assert_eq!(config.tolerance, 2.0);
+        assert!("64".parse::<Resolution>().is_err());
+        assert!("0x64".parse::<Resolution>().is_err());
+
+        let resolution = config.resolutions[0];
+        assert_eq!(image_name("crt", resolution, 1.5), "crt_64x32_1.5s.png");
+        assert_eq!(image_name("crt", resolution, 0.0), "crt_64x32_0s.png");
+    }
+
+    #[test]
+    fn test_find_cases() {
+        let dir = std::env::temp_dir().join(format!("shader_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for file in [
+            "b.frag",
+            "a.glsl",
+            "model.glb",
+            "notes.txt",
+            TEST_CONFIG_FILE,
+        ] {
+            std::fs::write(dir.join(file), "").unwrap();
+        }
+        let cases = find_cases(&dir).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        let names: Vec<(&str, CaseKind)> =
+            cases.iter().map(|c| (c.name.as_str(), c.kind)).collect();
+        assert_eq!(
+            names,
+            [
+                ("a", CaseKind::Shader),
+                ("b", CaseKind::Shader),
+                ("model", CaseKind::Model)
+            ]
+        );
+
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("blur.glsl"), "").unwrap();
+        std::fs::write(dir.join("blur.frag"), "").unwrap();
+        let duplicate = find_cases(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(duplicate.is_err());
+    }
+}
@@ -0,0 +1,154 @@
+//! Comparing rendered images with references by perceived color difference
+use image::{Rgba, RgbaImage};
+
+/// How different a rendered image may be from its reference
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tolerance {
+    /// Largest CIE76 color difference of a pixel that still counts as equal, 2.3 is about
+    /// the smallest difference people notice
+    pub max_delta_e: f32,
+    /// Share of pixels, from 0 to 1, that may differ more than `max_delta_e`
+    pub max_failing_pixels: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct Comparison {
+    /// Pixels differing more than the tolerance
+    pub failing_pixels: usize,
+    pub total_pixels: usize,
+    /// Largest difference of any pixel
+    pub max_delta_e: f32,
+    /// The reference in faded gray, with failing pixels in red
+    pub diff: RgbaImage,
+}
+
+impl Comparison {
+    pub fn passes(&self, tolerance: &Tolerance) -> bool {
+        self.failing_pixels as f32 <= tolerance.max_failing_pixels * self.total_pixels as f32
+    }
+}
+
+/// Compares every pixel of `actual` with `reference`, which must have the same size
+pub fn compare(
+    actual: &RgbaImage,
+    reference: &RgbaImage,
+    tolerance: &Tolerance,
+) -> Result<Comparison, String> {
+    if actual.dimensions() != reference.dimensions() {
+        let (width, height) = reference.dimensions();
+        return Err(format!(
+            "The image is {}x{}, the reference {width}x{height}",
+            actual.width(),
+            actual.height()
+        ));
+    }
+
+    let mut diff = RgbaImage::new(actual.width(), actual.height());
+    let mut failing_pixels = 0;
+    let mut max_delta_e = 0.0f32;
+    for ((a, r), d) in actual
+        .pixels()
+        .zip(reference.pixels())
+        .zip(diff.pixels_mut())
+    {
+        let delta = delta_e(a.0, r.0);
+        max_delta_e = max_delta_e.max(delta);
+        *d = if delta > tolerance.max_delta_e {
+            failing_pixels += 1;
+            Rgba([255, 0, 0, 255])
+        } else {
+            let [red, green, blue] = on_white(r.0);
+            let luma = 0.299 * red as f32 + 0.587 * green as f32 + 0.114 * blue as f32;
+            let faded = (255.0 - (255.0 - luma) * 0.2) as u8;
+            Rgba([faded, faded, faded, 255])
+        };
+    }
+    Ok(Comparison {
+        failing_pixels,
+        total_pixels: (actual.width() * actual.height()) as usize,
+        max_delta_e,
+        diff,
+    })
+}
+
+/// CIE76 difference of two sRGB colors, 0 for equal colors and 100 between black and white
+///
+/// Translucent colors are blended onto white first.
+pub fn delta_e(a: [u8; 4], b: [u8; 4]) -> f32 {
+    let (a, b) = (lab(on_white(a)), lab(on_white(b)));
+    ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt()
+}
+
+fn on_white([r, g, b, a]: [u8; 4]) -> [u8; 3] {
+    let alpha = a as f32 / 255.0;
+    [r, g, b].map(|c| (c as f32 * alpha + 255.0 * (1.0 - alpha)).round() as u8)
+}
+
+/// Converts an sRGB color to CIELAB under the D65 white point
+fn lab(rgb: [u8; 3]) -> [f32; 3] {
+    let [r, g, b] = rgb.map(|c| {
+        let c = c as f32 / 255.0;
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    });
+    let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.95047;
+    let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.08883;
+    let f = |t: f32| {
+        if t > 216.0 / 24389.0 {
+            t.cbrt()
+        } else {
+            (24389.0 / 27.0 * t + 16.0) / 116.0
+        }
+    };
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOLERANCE: Tolerance = Tolerance {
+        max_delta_e: 2.0,
+        max_failing_pixels: 0.0,
+    };
+
+    #[test]
+    fn test_delta_e() {
+        assert_eq!(delta_e([10, 20, 30, 255], [10, 20, 30, 255]), 0.0);
+        assert!((delta_e([0, 0, 0, 255], [255, 255, 255, 255]) - 100.0).abs() < 0.1);
+        // transparent pixels look like the white background
+        assert!(delta_e([0, 0, 0, 0], [255, 255, 255, 255]) < 0.1);
+        // one step of 8 bit rounding isn't visible, a clearly different red is
+        assert!(delta_e([128, 64, 32, 255], [129, 64, 32, 255]) < 1.0);
+        assert!(delta_e([200, 0, 0, 255], [160, 0, 0, 255]) > 10.0);
+    }
+
+    #[test]
+    fn test_compare() {
+        let reference = RgbaImage::from_pixel(4, 4, Rgba([100, 150, 200, 255]));
+        let mut actual = reference.clone();
+        actual.put_pixel(1, 2, Rgba([101, 150, 200, 255]));
+        let comparison = compare(&actual, &reference, &TOLERANCE).unwrap();
+        assert_eq!(comparison.failing_pixels, 0);
+        assert!(comparison.passes(&TOLERANCE));
+
+        actual.put_pixel(3, 0, Rgba([0, 0, 0, 255]));
+        let comparison = compare(&actual, &reference, &TOLERANCE).unwrap();
+        assert_eq!(comparison.failing_pixels, 1);
+        assert!(!comparison.passes(&TOLERANCE));
+        assert!(comparison.passes(&Tolerance {
+            max_failing_pixels: 1.0 / 16.0,
+            ..TOLERANCE
+        }));
+        assert_eq!(comparison.diff.get_pixel(3, 0).0, [255, 0, 0, 255]);
+        assert_ne!(comparison.diff.get_pixel(0, 0).0, [255, 0, 0, 255]);
+
+        let smaller = RgbaImage::new(2, 4);
+        assert!(compare(&smaller, &reference, &TOLERANCE).is_err());
+    }
+}
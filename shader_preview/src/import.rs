@@ -0,0 +1,6 @@
+//! Importers turning external files into shapes, meshes and shaders
+pub mod gltf;
+pub mod isf;
+pub mod obj;
+pub mod shadertoy;
+pub mod svg;
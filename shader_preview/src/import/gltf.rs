@@ -0,0 +1,186 @@
+//! Loader for glTF 2.0 models (`.gltf` with external or embedded buffers, and binary `.glb`)
+//!
+//! Walks the node hierarchy of the default scene and bakes node transforms into the vertices,
+//! so every triangle primitive becomes one [`MeshData`] in world space. The base color factor
+//! of the material becomes the color of the mesh.
+//!
+//! glTF puts the UV origin in the top left corner, the V coordinate is flipped to match
+//! OpenGL textures, which start at the bottom left.
+use crate::run::mesh::{Color, MeshData};
+use std::path::Path;
+use vatnar_linalg::{Matrix, Vector3};
+
+/// Loads all triangle meshes of a glTF file
+pub fn load_gltf(path: impl AsRef<Path>) -> Result<Vec<MeshData>, String> {
+    let path = path.as_ref();
+    let error = |e: ::gltf::Error| format!("Couldn't load glTF file '{}': {e}", path.display());
+
+    let gltf = ::gltf::Gltf::open(path).map_err(error)?;
+    let buffers =
+        ::gltf::import_buffers(&gltf.document, path.parent(), gltf.blob.clone()).map_err(error)?;
+    convert(&gltf.document, &buffers)
+}
+
+/// Parses a glTF or GLB file from memory, buffers have to be embedded
+pub fn parse_gltf(data: &[u8]) -> Result<Vec<MeshData>, String> {
+    let error = |e: ::gltf::Error| format!("Couldn't parse glTF data: {e}");
+
+    let gltf = ::gltf::Gltf::from_slice(data).map_err(error)?;
+    let buffers = ::gltf::import_buffers(&gltf.document, None, gltf.blob.clone()).map_err(error)?;
+    convert(&gltf.document, &buffers)
+}
+
+fn convert(
+    document: &::gltf::Document,
+    buffers: &[::gltf::buffer::Data],
+) -> Result<Vec<MeshData>, String> {
+    let scene = document
+        .default_scene()
+        .or_else(|| document.scenes().next())
+        .ok_or("glTF file contains no scene")?;
+
+    let mut meshes = Vec::new();
+    for node in scene.nodes() {
+        convert_node(&node, &Matrix::identity(4), buffers, &mut meshes)?;
+    }
+    Ok(meshes)
+}
+
+fn convert_node(
+    node: &::gltf::Node,
+    parent_transform: &Matrix,
+    buffers: &[::gltf::buffer::Data],
+    meshes: &mut Vec<MeshData>,
+) -> Result<(), String> {
+    let transform = parent_transform * &to_matrix(node.transform().matrix());
+
+    if let Some(mesh) = node.mesh() {
+        for primitive in mesh.primitives() {
+            if primitive.mode() != ::gltf::mesh::Mode::Triangles {
+                continue;
+            }
+            let reader = primitive.reader(|buffer| buffers.get(buffer.index()).map(|d| &d.0[..]));
+
+            let positions: Vec<[f32; 3]> = reader
+                .read_positions()
+                .ok_or_else(|| format!("Primitive of mesh {} has no positions", mesh.index()))?
+                .map(|p| to_array(transform.transform_point3(to_vector(p))))
+                .collect();
+            let normals = reader
+                .read_normals()
+                .map(|normals| normals.map(|n| transform_normal(&transform, n)).collect())
+                .unwrap_or_default();
+            let uvs = reader
+                .read_tex_coords(0)
+                .map(|uvs| uvs.into_f32().map(|[u, v]| [u, 1.0 - v]).collect())
+                .unwrap_or_default();
+            let indices = reader
+                .read_indices()
+                .map(|indices| indices.into_u32().collect())
+                .unwrap_or_default();
+
+            let [r, g, b, _] = primitive
+                .material()
+                .pbr_metallic_roughness()
+                .base_color_factor();
+
+            let mut data = MeshData {
+                positions,
+                normals,
+                uvs,
+                indices,
+                color: Color::from((r, g, b)),
+            };
+            if data.normals.len() != data.positions.len() {
+                data.compute_normals();
+            }
+            data.validate()
+                .map_err(|e| format!("Primitive of mesh {}: {e}", mesh.index()))?;
+            meshes.push(data);
+        }
+    }
+
+    for child in node.children() {
+        convert_node(&child, &transform, buffers, meshes)?;
+    }
+    Ok(())
+}
+
+/// Converts a column-major glTF matrix into a [`Matrix`]
+fn to_matrix(columns: [[f32; 4]; 4]) -> Matrix {
+    let data: Vec<f64> = (0..4)
+        .flat_map(|row| columns.iter().map(move |column| column[row] as f64))
+        .collect();
+    Matrix::new(4, 4, &data)
+}
+
+/// Rotates and scales a normal by the upper 3x3 part of `transform`,
+/// exact for rotations and uniform scaling
+fn transform_normal(transform: &Matrix, normal: [f32; 3]) -> [f32; 3] {
+    let n = to_vector(normal);
+    let row =
+        |r: usize| transform[(r, 0)] * n.x + transform[(r, 1)] * n.y + transform[(r, 2)] * n.z;
+    to_array(Vector3::new(row(0), row(1), row(2)).normalized())
+}
+
+fn to_vector(p: [f32; 3]) -> Vector3<f64> {
+    Vector3::new(p[0] as f64, p[1] as f64, p[2] as f64)
+}
+
+fn to_array(v: Vector3<f64>) -> [f32; 3] {
+    [v.x as f32, v.y as f32, v.z as f32]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One triangle in a node translated along z, with a parent node scaling by 2
+    const TRIANGLE: &str = r#"{
+        "asset": { "version": "2.0" },
+        "scene": 0,
+        "scenes": [{ "nodes": [0] }],
+        "nodes": [
+            { "scale": [2, 2, 2], "children": [1] },
+            { "translation": [0, 0, 1], "mesh": 0 }
+        ],
+        "meshes": [{ "primitives": [{ "attributes": { "POSITION": 0 }, "indices": 1, "material": 0 }] }],
+        "materials": [{ "pbrMetallicRoughness": { "baseColorFactor": [1, 0.5, 0, 1] } }],
+        "buffers": [{
+            "byteLength": 44,
+            "uri": "data:application/octet-stream;base64,AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAAAAABAAIAAAA="
+        }],
+        "bufferViews": [
+            { "buffer": 0, "byteOffset": 0, "byteLength": 36 },
+            { "buffer": 0, "byteOffset": 36, "byteLength": 6 }
+        ],
+        "accessors": [
+            { "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+              "min": [0, 0, 0], "max": [1, 1, 0] },
+            { "bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR" }
+        ]
+    }"#;
+
+    #[test]
+    fn test_parse_gltf() {
+        let meshes = parse_gltf(TRIANGLE.as_bytes()).unwrap();
+        assert_eq!(meshes.len(), 1);
+        let triangle = &meshes[0];
+
+        assert_eq!(triangle.indices, vec![0, 1, 2]);
+        // translated first, then scaled by the parent
+        assert_eq!(triangle.positions[0], [0.0, 0.0, 2.0]);
+        assert_eq!(triangle.positions[1], [2.0, 0.0, 2.0]);
+        // normals are generated, facing +z for a counter-clockwise triangle
+        assert_eq!(triangle.normals[0], [0.0, 0.0, 1.0]);
+        assert!(triangle.uvs.is_empty());
+
+        let color: (f32, f32, f32) = triangle.color.into();
+        assert_eq!(color, (1.0, 0.5, 0.0));
+    }
+
+    #[test]
+    fn test_parse_gltf_invalid() {
+        assert!(parse_gltf(b"{}").is_err());
+    }
+}
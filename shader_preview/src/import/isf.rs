@@ -0,0 +1,666 @@
+//! Shaders in the Interactive Shader Format, GLSL fragment shaders with a JSON header
+//!
+//! The header is the `/*{ ... }*/` comment at the top of the file. It declares the `INPUTS`
+//! shown as controls, the `PASSES` rendered in order into named targets, and `IMPORTED`
+//! images. [`Isf::fragment_source`] wraps the code with the ISF uniforms and macros so the
+//! passes share one program, told apart by `PASSINDEX`. Companion vertex shaders aren't
+//! supported, passes are drawn with the fullscreen triangle of the post effects.
+use serde::Deserialize;
+use serde_json::Value;
+use std::path::Path;
+
+/// A control of an ISF shader, declared in `INPUTS`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Input {
+    /// Name of the uniform
+    pub name: String,
+    /// Shown in the control panel, the name unless `LABEL` is given
+    pub label: String,
+    pub kind: InputKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputKind {
+    /// A `bool` that is true for one frame when triggered
+    Event,
+    Bool(bool),
+    /// An `int` picked from `values`, shown as `labels`
+    Long {
+        default: i32,
+        values: Vec<i32>,
+        labels: Vec<String>,
+    },
+    Float {
+        default: f32,
+        min: f32,
+        max: f32,
+    },
+    /// A `vec2`, in pixels of the render size unless a range is given
+    Point2D {
+        default: [f32; 2],
+        range: Option<([f32; 2], [f32; 2])>,
+    },
+    Color([f32; 4]),
+    /// A `sampler2D`, bound to the texture of the same name on the command line
+    Image,
+}
+
+/// One render pass, drawn into the window when it has no target
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Pass {
+    pub target: Option<String>,
+    /// The target keeps its contents between frames
+    pub persistent: bool,
+    /// The target holds 32 bit floats instead of 8 bit colors
+    pub float: bool,
+    /// Size expressions like `$WIDTH / 2.0`, the render size when missing
+    pub width: Option<String>,
+    pub height: Option<String>,
+}
+
+/// Largest pass size as a multiple of the render size
+const MAX_PASS_SCALE: f64 = 8.0;
+
+impl Pass {
+    /// Size of the target for `render_size`, `variable` looks up the values of inputs
+    ///
+    /// Sizes are at least 1 and at most [`MAX_PASS_SCALE`] times the render size, expressions
+    /// that aren't finite, like a division by 0, are an error.
+    pub fn size(
+        &self,
+        (width, height): (u32, u32),
+        variable: impl Fn(&str) -> Option<f64>,
+    ) -> Result<(u32, u32), String> {
+        let lookup = |name: &str| match name {
+            "WIDTH" => Some(width as f64),
+            "HEIGHT" => Some(height as f64),
+            name => variable(name),
+        };
+        let dimension = |expression: &Option<String>, default: u32| match expression {
+            Some(expression) => match evaluate(expression, &lookup)? {
+                value if value.is_finite() => {
+                    Ok(value.clamp(1.0, default as f64 * MAX_PASS_SCALE) as u32)
+                }
+                value => Err(format!("'{expression}' is {value}")),
+            },
+            None => Ok(default),
+        };
+        Ok((
+            dimension(&self.width, width)?,
+            dimension(&self.height, height)?,
+        ))
+    }
+}
+
+/// An image loaded from a file next to the shader, declared in `IMPORTED`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedImage {
+    pub name: String,
+    pub path: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Isf {
+    pub description: String,
+    pub inputs: Vec<Input>,
+    /// At least one, a single pass without a target for shaders without `PASSES`
+    pub passes: Vec<Pass>,
+    pub imported: Vec<ImportedImage>,
+    /// The GLSL after the header
+    pub code: String,
+    /// Line of the file the code starts on
+    code_line: usize,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+struct HeaderJson {
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    inputs: Vec<InputJson>,
+    #[serde(default)]
+    passes: Vec<PassJson>,
+    /// A map of names to `{"PATH": ...}`, or a list with `NAME`s in newer files
+    #[serde(default)]
+    imported: Value,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+struct InputJson {
+    name: String,
+    #[serde(rename = "TYPE")]
+    kind: String,
+    label: Option<String>,
+    #[serde(default)]
+    default: Value,
+    #[serde(default)]
+    min: Value,
+    #[serde(default)]
+    max: Value,
+    #[serde(default)]
+    values: Vec<Value>,
+    #[serde(default)]
+    labels: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+struct PassJson {
+    target: Option<String>,
+    #[serde(default)]
+    persistent: Value,
+    #[serde(default)]
+    float: Value,
+    #[serde(default)]
+    width: Value,
+    #[serde(default)]
+    height: Value,
+}
+
+/// A boolean written as `true`, `"true"` or a number
+fn flag(value: &Value) -> bool {
+    match value {
+        Value::Bool(flag) => *flag,
+        Value::Number(number) => number.as_f64().is_some_and(|n| n != 0.0),
+        Value::String(flag) => flag == "true" || flag == "1",
+        _ => false,
+    }
+}
+
+fn number(value: &Value) -> Option<f32> {
+    match value {
+        Value::Number(number) => number.as_f64().map(|n| n as f32),
+        Value::Bool(flag) => Some(*flag as i32 as f32),
+        _ => None,
+    }
+}
+
+/// A vector written as a list of numbers, missing components are `fill`
+fn vector<const N: usize>(value: &Value, fill: f32) -> Option<[f32; N]> {
+    let components = value.as_array()?;
+    let mut vector = [fill; N];
+    for (component, value) in vector.iter_mut().zip(components) {
+        *component = number(value)?;
+    }
+    Some(vector)
+}
+
+/// A size expression written as a string or a number
+fn expression(value: &Value) -> Option<String> {
+    match value {
+        Value::String(expression) => Some(expression.clone()),
+        Value::Number(number) => Some(number.to_string()),
+        _ => None,
+    }
+}
+
+impl InputJson {
+    fn into_input(self) -> Option<Input> {
+        let kind = match self.kind.as_str() {
+            "event" => InputKind::Event,
+            "bool" => InputKind::Bool(number(&self.default).is_some_and(|n| n != 0.0)),
+            "long" => {
+                let values: Vec<i32> = self
+                    .values
+                    .iter()
+                    .filter_map(|value| number(value).map(|n| n as i32))
+                    .collect();
+                let default = number(&self.default)
+                    .map(|n| n as i32)
+                    .or(values.first().copied())
+                    .unwrap_or(0);
+                let labels = match self.labels.len() == values.len() {
+                    true => self.labels,
+                    false => values.iter().map(i32::to_string).collect(),
+                };
+                InputKind::Long {
+                    default,
+                    values,
+                    labels,
+                }
+            }
+            "float" => {
+                let min = number(&self.min).unwrap_or(0.0);
+                let max = number(&self.max).unwrap_or(1.0);
+                InputKind::Float {
+                    default: number(&self.default).unwrap_or(min).max(min).min(max),
+                    min,
+                    max,
+                }
+            }
+            "point2D" => InputKind::Point2D {
+                default: vector(&self.default, 0.0).unwrap_or_default(),
+                range: vector(&self.min, 0.0).zip(vector(&self.max, 0.0)),
+            },
+            "color" => InputKind::Color(vector(&self.default, 1.0).unwrap_or([0.0, 0.0, 0.0, 1.0])),
+            "image" => InputKind::Image,
+            "audio" | "audioFFT" => {
+                log::warn!(
+                    "Input '{}' is {} audio, it gets an image instead",
+                    self.name,
+                    self.kind
+                );
+                InputKind::Image
+            }
+            kind => {
+                log::warn!("Skipping input '{}' of unknown type {kind}", self.name);
+                return None;
+            }
+        };
+        Some(Input {
+            label: self.label.unwrap_or_else(|| self.name.clone()),
+            name: self.name,
+            kind,
+        })
+    }
+}
+
+/// The entries of `IMPORTED`
+fn imported_images(imported: &Value) -> Vec<ImportedImage> {
+    let path = |image: &Value| image.get("PATH").and_then(Value::as_str).map(String::from);
+    match imported {
+        Value::Object(images) => images
+            .iter()
+            .filter_map(|(name, image)| {
+                Some(ImportedImage {
+                    name: name.clone(),
+                    path: path(image)?,
+                })
+            })
+            .collect(),
+        Value::Array(images) => images
+            .iter()
+            .filter_map(|image| {
+                Some(ImportedImage {
+                    name: image.get("NAME")?.as_str()?.to_string(),
+                    path: path(image)?,
+                })
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+impl Isf {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| format!("Couldn't read '{}': {e}", path.display()))?;
+        Self::parse(&source).map_err(|e| format!("{e} in '{}'", path.display()))
+    }
+
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let start = source
+            .find("/*")
+            .filter(|&start| source[..start].trim().is_empty())
+            .ok_or("No ISF header, the file has to start with a /*{ ... }*/ comment")?;
+        let end = source[start..]
+            .find("*/")
+            .map(|end| start + end)
+            .ok_or("The ISF header comment isn't closed")?;
+        let header: HeaderJson = serde_json::from_str(&source[start + 2..end])
+            .map_err(|e| format!("Invalid ISF header: {e}"))?;
+
+        let code_start = end + 2;
+        let code_line = source[..code_start].matches('\n').count() + 1;
+        let mut passes: Vec<Pass> = header
+            .passes
+            .into_iter()
+            .map(|pass| Pass {
+                target: pass.target.filter(|target| !target.is_empty()),
+                persistent: flag(&pass.persistent),
+                float: flag(&pass.float),
+                width: expression(&pass.width),
+                height: expression(&pass.height),
+            })
+            .collect();
+        if passes.is_empty() {
+            passes.push(Pass::default());
+        }
+        Ok(Isf {
+            description: header.description,
+            inputs: header
+                .inputs
+                .into_iter()
+                .filter_map(InputJson::into_input)
+                .collect(),
+            passes,
+            imported: imported_images(&header.imported),
+            code: source[code_start..].to_string(),
+            code_line,
+        })
+    }
+
+    /// Names of the targets of the passes, each once, in order of the first pass drawing
+    /// into them
+    pub fn targets(&self) -> Vec<&str> {
+        let mut targets: Vec<&str> = Vec::new();
+        for target in self.passes.iter().filter_map(|pass| pass.target.as_deref()) {
+            if !targets.contains(&target) {
+                targets.push(target);
+            }
+        }
+        targets
+    }
+
+    /// Complete fragment shader: the ISF uniforms and macros, then the code
+    ///
+    /// A `#line` directive makes compile errors point at lines of the file.
+    pub fn fragment_source(&self) -> String {
+        let mut source = String::from(
+            "#version 330 core\n\
+             in vec2 v_uv;\n\
+             out vec4 isf_FragColor;\n\
+             #define gl_FragColor isf_FragColor\n\
+             #define isf_FragNormCoord v_uv\n\
+             #define vv_FragNormCoord v_uv\n\
+             #define texture2D texture\n\
+             #define IMG_SIZE(image) vec2(textureSize(image, 0))\n\
+             #define IMG_NORM_PIXEL(image, coord) texture(image, coord)\n\
+             #define IMG_PIXEL(image, coord) texture(image, (coord) / IMG_SIZE(image))\n\
+             #define IMG_THIS_NORM_PIXEL(image) texture(image, isf_FragNormCoord)\n\
+             #define IMG_THIS_PIXEL(image) texture(image, isf_FragNormCoord)\n\
+             uniform int PASSINDEX;\n\
+             uniform vec2 RENDERSIZE;\n\
+             uniform float TIME;\n\
+             uniform float TIMEDELTA;\n\
+             uniform vec4 DATE;\n\
+             uniform int FRAMEINDEX;\n",
+        );
+        for input in &self.inputs {
+            let glsl_type = match input.kind {
+                InputKind::Event | InputKind::Bool(_) => "bool",
+                InputKind::Long { .. } => "int",
+                InputKind::Float { .. } => "float",
+                InputKind::Point2D { .. } => "vec2",
+                InputKind::Color(_) => "vec4",
+                InputKind::Image => "sampler2D",
+            };
+            source.push_str(&format!("uniform {glsl_type} {};\n", input.name));
+        }
+        let images = self.imported.iter().map(|image| image.name.as_str());
+        for name in images.chain(self.targets()) {
+            source.push_str(&format!("uniform sampler2D {name};\n"));
+        }
+        source.push_str(&format!("#line {}\n", self.code_line));
+        source.push_str(&self.code);
+        source
+    }
+}
+
+/// Evaluates a size expression of `+ - * /`, parentheses, numbers, `$variables` and the
+/// functions `floor`, `ceil`, `round`, `abs`, `sqrt`, `min`, `max` and `pow`
+pub fn evaluate(expression: &str, variable: &impl Fn(&str) -> Option<f64>) -> Result<f64, String> {
+    let mut parser = Parser {
+        rest: expression,
+        variable,
+    };
+    let value = parser.sum()?;
+    if !parser.rest.trim().is_empty() {
+        return Err(format!(
+            "Unexpected '{}' in '{expression}'",
+            parser.rest.trim()
+        ));
+    }
+    Ok(value)
+}
+
+/// Recursive descent over the rest of an expression
+struct Parser<'a, F> {
+    rest: &'a str,
+    variable: &'a F,
+}
+
+impl<'a, F: Fn(&str) -> Option<f64>> Parser<'a, F> {
+    /// Skips whitespace and consumes `c` if it comes next
+    fn eat(&mut self, c: char) -> bool {
+        self.rest = self.rest.trim_start();
+        match self.rest.strip_prefix(c) {
+            Some(rest) => {
+                self.rest = rest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Consumes an identifier or a number
+    fn word(&mut self) -> &'a str {
+        self.rest = self.rest.trim_start();
+        let end = self
+            .rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '.'))
+            .unwrap_or(self.rest.len());
+        let (word, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        word
+    }
+
+    fn sum(&mut self) -> Result<f64, String> {
+        let mut value = self.product()?;
+        loop {
+            if self.eat('+') {
+                value += self.product()?;
+            } else if self.eat('-') {
+                value -= self.product()?;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn product(&mut self) -> Result<f64, String> {
+        let mut value = self.factor()?;
+        loop {
+            if self.eat('*') {
+                value *= self.factor()?;
+            } else if self.eat('/') {
+                value /= self.factor()?;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn factor(&mut self) -> Result<f64, String> {
+        if self.eat('-') {
+            return Ok(-self.factor()?);
+        }
+        if self.eat('(') {
+            let value = self.sum()?;
+            return match self.eat(')') {
+                true => Ok(value),
+                false => Err("Missing ')'".to_string()),
+            };
+        }
+        if self.eat('$') {
+            let name = self.word();
+            return (self.variable)(name).ok_or_else(|| format!("Unknown variable '${name}'"));
+        }
+        let word = self.word();
+        if let Ok(number) = word.parse() {
+            return Ok(number);
+        }
+        if word.is_empty() || !self.eat('(') {
+            return Err(format!("Expected a value at '{}'", self.rest.trim()));
+        }
+        let mut arguments = vec![self.sum()?];
+        while self.eat(',') {
+            arguments.push(self.sum()?);
+        }
+        if !self.eat(')') {
+            return Err(format!("Missing ')' after the arguments of {word}"));
+        }
+        match (word, arguments.as_slice()) {
+            ("floor", &[x]) => Ok(x.floor()),
+            ("ceil", &[x]) => Ok(x.ceil()),
+            ("round", &[x]) => Ok(x.round()),
+            ("abs", &[x]) => Ok(x.abs()),
+            ("sqrt", &[x]) => Ok(x.sqrt()),
+            ("min", &[a, b]) => Ok(a.min(b)),
+            ("max", &[a, b]) => Ok(a.max(b)),
+            ("pow", &[a, b]) => Ok(a.powf(b)),
+            _ => Err(format!(
+                "Unknown function {word} with {} arguments",
+                arguments.len()
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLUR: &str = r#"/*{
+    "DESCRIPTION": "Feedback blur",
+    "ISFVSN": "2",
+    "INPUTS": [
+        {"NAME": "inputImage", "TYPE": "image"},
+        {"NAME": "amount", "TYPE": "float", "DEFAULT": 0.5, "MIN": 0.0, "MAX": 2.0, "LABEL": "Amount"},
+        {"NAME": "tint", "TYPE": "color", "DEFAULT": [1.0, 0.5, 0.0]},
+        {"NAME": "center", "TYPE": "point2D", "DEFAULT": [0.5, 0.5], "MIN": [0, 0], "MAX": [1, 1]},
+        {"NAME": "invert", "TYPE": "bool", "DEFAULT": 1},
+        {"NAME": "mode", "TYPE": "long", "VALUES": [0, 1, 2], "LABELS": ["Off", "Soft", "Hard"], "DEFAULT": 1},
+        {"NAME": "reset", "TYPE": "event"},
+        {"NAME": "wobble", "TYPE": "spline"}
+    ],
+    "PASSES": [
+        {"TARGET": "small", "WIDTH": "floor($WIDTH / 4.0)", "HEIGHT": "$HEIGHT * $amount"},
+        {"TARGET": "trail", "PERSISTENT": true, "FLOAT": "true"},
+        {}
+    ],
+    "IMPORTED": {"noise": {"PATH": "noise.png"}}
+}*/
+
+void main() {
+    gl_FragColor = IMG_THIS_PIXEL(inputImage);
+}
+"#;
+
+    #[test]
+    fn test_parse() {
+        let isf = Isf::parse(BLUR).unwrap();
+        assert_eq!(isf.description, "Feedback blur");
+        let names: Vec<&str> = isf.inputs.iter().map(|input| input.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "inputImage",
+                "amount",
+                "tint",
+                "center",
+                "invert",
+                "mode",
+                "reset"
+            ]
+        );
+        assert_eq!(isf.inputs[1].label, "Amount");
+        assert_eq!(
+            isf.inputs[1].kind,
+            InputKind::Float {
+                default: 0.5,
+                min: 0.0,
+                max: 2.0
+            }
+        );
+        assert_eq!(isf.inputs[2].kind, InputKind::Color([1.0, 0.5, 0.0, 1.0]));
+        assert_eq!(
+            isf.inputs[3].kind,
+            InputKind::Point2D {
+                default: [0.5, 0.5],
+                range: Some(([0.0, 0.0], [1.0, 1.0]))
+            }
+        );
+        assert_eq!(isf.inputs[4].kind, InputKind::Bool(true));
+        let InputKind::Long {
+            default, labels, ..
+        } = &isf.inputs[5].kind
+        else {
+            panic!("mode is a long");
+        };
+        assert_eq!((*default, labels[2].as_str()), (1, "Hard"));
+
+        assert_eq!(isf.passes.len(), 3);
+        assert!(isf.passes[1].persistent && isf.passes[1].float);
+        assert_eq!(isf.passes[2], Pass::default());
+        assert_eq!(isf.targets(), ["small", "trail"]);
+        assert_eq!(
+            isf.imported,
+            [ImportedImage {
+                name: "noise".into(),
+                path: "noise.png".into()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(Isf::parse("void main() {}").is_err());
+        assert!(Isf::parse("/*{\"INPUTS\": [}*/").is_err());
+        assert!(Isf::parse("/*{}").is_err());
+        // the header is all that's required
+        let isf = Isf::parse("/*{}*/\nvoid main() {}").unwrap();
+        assert_eq!(isf.passes, [Pass::default()]);
+    }
+
+    #[test]
+    fn test_pass_size() {
+        let isf = Isf::parse(BLUR).unwrap();
+        let amount = |name: &str| (name == "amount").then_some(0.5);
+        assert_eq!(isf.passes[0].size((801, 600), amount), Ok((200, 300)));
+        assert_eq!(isf.passes[1].size((801, 600), amount), Ok((801, 600)));
+        let missing = isf.passes[0].size((801, 600), |_| None);
+        assert_eq!(missing, Err("Unknown variable '$amount'".to_string()));
+
+        let pass = Pass {
+            width: Some("$WIDTH / $scale".into()),
+            height: Some("$HEIGHT * $scale".into()),
+            ..isf.passes[1].clone()
+        };
+        let scale = |value: f64| move |name: &str| (name == "scale").then_some(value);
+        assert!(pass.size((800, 600), scale(0.0)).is_err());
+        assert_eq!(pass.size((800, 600), scale(100.0)), Ok((8, 4800)));
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let variable = |name: &str| (name == "x").then_some(3.0);
+        let value = |expression| evaluate(expression, &variable);
+        assert_eq!(value("1 + 2 * 3"), Ok(7.0));
+        assert_eq!(value("(1 + 2) * -$x"), Ok(-9.0));
+        assert_eq!(value("max(1, $x / 2) + pow(2, 3)"), Ok(9.5));
+        assert_eq!(value("round(2.5) - ceil(0.2)"), Ok(2.0));
+        assert!(value("1 +").is_err());
+        assert!(value("(1").is_err());
+        assert!(value("2 3").is_err());
+        assert!(value("nope(1)").is_err());
+    }
+
+    #[test]
+    fn test_fragment_source() {
+        let isf = Isf::parse(BLUR).unwrap();
+        let source = isf.fragment_source();
+        assert!(source.starts_with("#version 330 core\n"));
+        for declaration in [
+            "uniform sampler2D inputImage;",
+            "uniform float amount;",
+            "uniform vec4 tint;",
+            "uniform bool reset;",
+            "uniform int mode;",
+            "uniform sampler2D noise;",
+            "uniform sampler2D trail;",
+        ] {
+            assert!(source.contains(declaration), "{declaration}");
+        }
+        // the code keeps its line numbers
+        let code = &source[source.find("#line").unwrap()..];
+        let line: usize = code["#line ".len()..code.find('\n').unwrap()]
+            .parse()
+            .unwrap();
+        let main = BLUR.lines().position(|l| l == "void main() {").unwrap() + 1;
+        let main_in_code = code.lines().position(|l| l == "void main() {").unwrap();
+        assert_eq!(line + main_in_code - 1, main);
+    }
+}
@@ -0,0 +1,150 @@
+//! Loader for Wavefront OBJ models
+//!
+//! Faces are triangulated and vertices are re-indexed so positions, normals and UVs share one
+//! index buffer. The diffuse color of the material becomes the color of the mesh, and missing
+//! normals are generated from the faces.
+use crate::run::mesh::{Color, MeshData};
+use std::io::BufReader;
+use std::path::Path;
+
+const LOAD_OPTIONS: tobj::LoadOptions = tobj::LoadOptions {
+    single_index: true,
+    triangulate: true,
+    ignore_points: true,
+    ignore_lines: true,
+};
+
+/// Loads every object of an OBJ file, along with colors from its `.mtl` material library
+///
+/// A missing or broken material library is not an error, the meshes are white instead.
+pub fn load_obj(path: impl AsRef<Path>) -> Result<Vec<MeshData>, String> {
+    let path = path.as_ref();
+    let (models, materials) = tobj::load_obj(path, &LOAD_OPTIONS)
+        .map_err(|e| format!("Couldn't load OBJ file '{}': {e}", path.display()))?;
+    convert(models, materials.unwrap_or_default())
+        .map_err(|e| format!("Invalid OBJ file '{}': {e}", path.display()))
+}
+
+/// Parses OBJ data from memory, material libraries are ignored
+///
+/// # Examples
+/// ```
+/// use shader_preview::import::obj::parse_obj;
+///
+/// let quad = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 3 4\n";
+/// let meshes = parse_obj(quad).unwrap();
+/// assert_eq!(meshes[0].indices.len(), 6);
+/// ```
+pub fn parse_obj(source: &str) -> Result<Vec<MeshData>, String> {
+    let (models, _) = tobj::load_obj_buf(
+        &mut BufReader::new(source.as_bytes()),
+        &LOAD_OPTIONS,
+        |_| Err(tobj::LoadError::OpenFileFailed),
+    )
+    .map_err(|e| format!("Couldn't parse OBJ data: {e}"))?;
+    convert(models, Vec::new()).map_err(|e| format!("Invalid OBJ data: {e}"))
+}
+
+fn convert(
+    models: Vec<tobj::Model>,
+    materials: Vec<tobj::Material>,
+) -> Result<Vec<MeshData>, String> {
+    models
+        .into_iter()
+        .filter(|model| !model.mesh.indices.is_empty())
+        .map(|model| {
+            let mesh = model.mesh;
+            let color = mesh
+                .material_id
+                .and_then(|id| materials.get(id))
+                .and_then(|material| material.diffuse)
+                .map(|[r, g, b]| Color::from((r, g, b)))
+                .unwrap_or_else(|| (1.0, 1.0, 1.0).into());
+
+            let mut data = MeshData {
+                positions: mesh
+                    .positions
+                    .chunks_exact(3)
+                    .map(|p| [p[0], p[1], p[2]])
+                    .collect(),
+                normals: mesh
+                    .normals
+                    .chunks_exact(3)
+                    .map(|n| [n[0], n[1], n[2]])
+                    .collect(),
+                uvs: mesh
+                    .texcoords
+                    .chunks_exact(2)
+                    .map(|t| [t[0], t[1]])
+                    .collect(),
+                indices: mesh.indices,
+                color,
+            };
+            if data.normals.len() != data.positions.len() {
+                data.compute_normals();
+            }
+            if data.uvs.len() != data.positions.len() {
+                data.uvs.clear();
+            }
+            data.validate()
+                .map_err(|e| format!("Object '{}': {e}", model.name))?;
+            Ok(data)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CUBE: &str = "
+        o cube
+        v -1 -1 -1
+        v 1 -1 -1
+        v 1 1 -1
+        v -1 1 -1
+        v -1 -1 1
+        v 1 -1 1
+        v 1 1 1
+        v -1 1 1
+        vt 0 0
+        vt 1 0
+        vt 1 1
+        vt 0 1
+        f 1/1 4/4 3/3 2/2
+        f 5/1 6/2 7/3 8/4
+        f 1/1 2/2 6/3 5/4
+        f 4/1 8/2 7/3 3/4
+        f 1/1 5/2 8/3 4/4
+        f 2/1 3/2 7/3 6/4
+    ";
+
+    #[test]
+    fn test_parse_obj_cube() {
+        let meshes = parse_obj(CUBE).unwrap();
+        assert_eq!(meshes.len(), 1);
+        let cube = &meshes[0];
+
+        assert_eq!(cube.indices.len(), 6 * 2 * 3);
+        assert_eq!(cube.uvs.len(), cube.positions.len());
+        // generated normals point away from the center
+        assert_eq!(cube.normals.len(), cube.positions.len());
+        for (position, normal) in cube.positions.iter().zip(&cube.normals) {
+            let dot: f32 = (0..3).map(|i| position[i] * normal[i]).sum();
+            assert!(dot > 0.0);
+        }
+
+        let (min, max) = cube.bounds();
+        assert_eq!(min, [-1.0; 3]);
+        assert_eq!(max, [1.0; 3]);
+
+        let (vertices, layout) = cube.interleaved();
+        assert_eq!(layout.len(), 3);
+        assert_eq!(vertices.len(), cube.positions.len() * 8);
+    }
+
+    #[test]
+    fn test_parse_obj_invalid() {
+        assert!(parse_obj("f 1 2 3\n").is_err());
+    }
+}
@@ -0,0 +1,445 @@
+//! Shaders exported from Shadertoy as JSON
+//!
+//! Reads the `renderpass` list of an export, as returned by the Shadertoy API or saved by
+//! export tools, into buffer passes, the image pass and the common code shared by them.
+//! Inputs keep their channel, sampler settings and the `src` of their media, which
+//! [`crate::run::shadertoy_view`] looks up next to the JSON file.
+use crate::run::texture::{Filter, TextureOptions, Wrap};
+use serde::Deserialize;
+use serde_json::Value;
+use std::path::Path;
+
+/// The `id` of the outputs of Buffer A to D
+const BUFFER_OUTPUT_IDS: [&str; 4] = ["4dXGR8", "XsXGR8", "4sXGR8", "XdfGR8"];
+
+/// What a pass renders into, buffers are drawn in order before the image
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PassKind {
+    /// Buffer A to D, by index
+    Buffer(usize),
+    Image,
+}
+
+/// Where the texture of a channel comes from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChannelSource {
+    /// Output of a buffer pass, of the previous frame for the pass itself and later passes
+    Buffer(usize),
+    /// An image by its `src` in the export, e.g. `/media/a/<hash>.png`
+    Texture(String),
+    Cubemap(String),
+    Keyboard,
+    /// Video, webcam, sound and other inputs that can't be played, by their `ctype`
+    Unsupported(String),
+}
+
+impl ChannelSource {
+    /// GLSL type of the `iChannel` uniform
+    pub fn sampler_type(&self) -> &'static str {
+        match self {
+            ChannelSource::Cubemap(_) => "samplerCube",
+            ChannelSource::Unsupported(ctype) if ctype == "volume" => "sampler3D",
+            _ => "sampler2D",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Channel {
+    /// 0 to 3, the `N` of `iChannelN`
+    pub index: usize,
+    pub source: ChannelSource,
+    pub options: TextureOptions,
+    /// Images are uploaded bottom row first, as Shadertoy does by default
+    pub vflip: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderPass {
+    pub name: String,
+    pub kind: PassKind,
+    pub code: String,
+    pub channels: Vec<Channel>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shadertoy {
+    pub name: String,
+    /// Code of the common tab, put before every pass
+    pub common: String,
+    /// Buffers by index, then the image pass
+    pub passes: Vec<RenderPass>,
+}
+
+#[derive(Deserialize)]
+struct ShaderJson {
+    #[serde(default)]
+    info: InfoJson,
+    renderpass: Vec<PassJson>,
+}
+
+#[derive(Default, Deserialize)]
+struct InfoJson {
+    #[serde(default)]
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct PassJson {
+    #[serde(default)]
+    name: String,
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    code: String,
+    #[serde(default)]
+    inputs: Vec<InputJson>,
+    #[serde(default)]
+    outputs: Vec<OutputJson>,
+}
+
+#[derive(Deserialize)]
+struct InputJson {
+    #[serde(default)]
+    id: Value,
+    /// `filepath` and `type` in older exports
+    #[serde(default, alias = "filepath")]
+    src: String,
+    #[serde(alias = "type")]
+    ctype: String,
+    channel: usize,
+    #[serde(default)]
+    sampler: SamplerJson,
+}
+
+#[derive(Deserialize)]
+struct OutputJson {
+    #[serde(default)]
+    id: Value,
+}
+
+/// Sampler settings, Shadertoy writes the flags as strings
+#[derive(Default, Deserialize)]
+struct SamplerJson {
+    #[serde(default)]
+    filter: String,
+    #[serde(default)]
+    wrap: String,
+    #[serde(default)]
+    vflip: Value,
+    #[serde(default)]
+    srgb: Value,
+}
+
+impl SamplerJson {
+    fn options(&self) -> TextureOptions {
+        let (filter, mipmaps) = match self.filter.as_str() {
+            "nearest" => (Filter::Nearest, false),
+            "linear" => (Filter::Linear, false),
+            _ => (Filter::Linear, true),
+        };
+        TextureOptions {
+            min_filter: filter,
+            mag_filter: filter,
+            wrap: match self.wrap.as_str() {
+                "clamp" => Wrap::ClampToEdge,
+                _ => Wrap::Repeat,
+            },
+            mipmaps,
+            srgb: flag(&self.srgb).unwrap_or(false),
+        }
+    }
+}
+
+/// A boolean written as `true` or `"true"`
+fn flag(value: &Value) -> Option<bool> {
+    match value {
+        Value::Bool(flag) => Some(*flag),
+        Value::String(flag) => flag.parse().ok(),
+        _ => None,
+    }
+}
+
+/// An `id` written as a string or a number
+fn id_string(id: &Value) -> String {
+    match id {
+        Value::String(id) => id.clone(),
+        id => id.to_string(),
+    }
+}
+
+impl PassJson {
+    fn output_ids(&self) -> Vec<String> {
+        self.outputs
+            .iter()
+            .map(|output| id_string(&output.id))
+            .collect()
+    }
+
+    /// Index of a buffer pass by its name, `Buffer A` to `Buffer D`, or the id of its output
+    fn buffer_index(&self) -> Option<usize> {
+        let by_name = self
+            .name
+            .strip_prefix("Buffer ")
+            .and_then(|letter| ["A", "B", "C", "D"].iter().position(|l| *l == letter));
+        by_name.or_else(|| {
+            self.output_ids()
+                .iter()
+                .find_map(|id| BUFFER_OUTPUT_IDS.iter().position(|known| known == id))
+        })
+    }
+}
+
+impl Shadertoy {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| format!("Couldn't read '{}': {e}", path.display()))?;
+        Self::parse(&json).map_err(|e| format!("{e} in '{}'", path.display()))
+    }
+
+    /// Parses an export, either `{"Shader": {...}}` as the API returns it, the shader object
+    /// itself, or a list of shaders of which the first is used
+    pub fn parse(json: &str) -> Result<Self, String> {
+        let document: Value =
+            serde_json::from_str(json).map_err(|e| format!("Invalid Shadertoy JSON: {e}"))?;
+        let mut shader = document.get("Shader").unwrap_or(&document);
+        if let Value::Array(shaders) = shader {
+            shader = shaders.first().ok_or("Empty list of shaders")?;
+        }
+        let shader = ShaderJson::deserialize(shader)
+            .map_err(|e| format!("Invalid Shadertoy export: {e}"))?;
+
+        let mut common = String::new();
+        let mut buffers = Vec::new();
+        for pass in &shader.renderpass {
+            match pass.kind.as_str() {
+                "common" => common = pass.code.clone(),
+                "buffer" => {
+                    let index = pass
+                        .buffer_index()
+                        .ok_or_else(|| format!("Unknown buffer '{}'", pass.name))?;
+                    buffers.push((index, pass.output_ids()));
+                }
+                _ => {}
+            }
+        }
+
+        let mut passes = Vec::new();
+        for pass in &shader.renderpass {
+            let kind = match pass.kind.as_str() {
+                "image" => PassKind::Image,
+                // checked above
+                "buffer" => PassKind::Buffer(pass.buffer_index().unwrap_or_default()),
+                "common" => continue,
+                kind => {
+                    log::warn!(
+                        "Skipping the {kind} pass '{}', it isn't supported",
+                        pass.name
+                    );
+                    continue;
+                }
+            };
+            let channels = pass
+                .inputs
+                .iter()
+                .filter(|input| input.channel < 4)
+                .map(|input| Channel {
+                    index: input.channel,
+                    source: channel_source(input, &buffers),
+                    options: input.sampler.options(),
+                    vflip: flag(&input.sampler.vflip).unwrap_or(true),
+                })
+                .collect();
+            passes.push(RenderPass {
+                name: pass.name.clone(),
+                kind,
+                code: pass.code.clone(),
+                channels,
+            });
+        }
+        passes.sort_by_key(|pass| pass.kind);
+        if passes
+            .last()
+            .is_none_or(|pass| pass.kind != PassKind::Image)
+        {
+            return Err("The Shadertoy export has no image pass".to_string());
+        }
+        Ok(Shadertoy {
+            name: shader.info.name,
+            common,
+            passes,
+        })
+    }
+
+    /// Complete fragment shader of `pass`: the Shadertoy uniforms, the common code, the pass
+    /// code and a `main` calling `mainImage`
+    ///
+    /// `#line` directives make compile errors point at lines of the tabs.
+    pub fn fragment_source(&self, pass: &RenderPass) -> String {
+        let mut source = String::from(
+            "#version 330 core\n\
+             uniform vec3 iResolution;\n\
+             uniform float iTime;\n\
+             uniform float iTimeDelta;\n\
+             uniform float iFrameRate;\n\
+             uniform int iFrame;\n\
+             uniform float iChannelTime[4];\n\
+             uniform vec3 iChannelResolution[4];\n\
+             uniform vec4 iMouse;\n\
+             uniform vec4 iDate;\n\
+             uniform float iSampleRate;\n",
+        );
+        for index in 0..4 {
+            let sampler_type = pass
+                .channels
+                .iter()
+                .find(|channel| channel.index == index)
+                .map_or("sampler2D", |channel| channel.source.sampler_type());
+            source.push_str(&format!("uniform {sampler_type} iChannel{index};\n"));
+        }
+        source.push_str("out vec4 shadertoy_FragColor;\n");
+        if !self.common.is_empty() {
+            source.push_str("#line 1\n");
+            source.push_str(&self.common);
+            source.push('\n');
+        }
+        source.push_str("#line 1\n");
+        source.push_str(&pass.code);
+        // the image is shown opaque whatever alpha the shader writes
+        let alpha = match pass.kind {
+            PassKind::Image => "\n    shadertoy_FragColor.a = 1.0;",
+            PassKind::Buffer(_) => "",
+        };
+        source.push_str(&format!(
+            "\nvoid main() {{\n    mainImage(shadertoy_FragColor, gl_FragCoord.xy);{alpha}\n}}\n"
+        ));
+        source
+    }
+}
+
+fn channel_source(input: &InputJson, buffers: &[(usize, Vec<String>)]) -> ChannelSource {
+    match input.ctype.as_str() {
+        "texture" => ChannelSource::Texture(input.src.clone()),
+        "cubemap" => ChannelSource::Cubemap(input.src.clone()),
+        "keyboard" => ChannelSource::Keyboard,
+        "buffer" => {
+            let id = id_string(&input.id);
+            let by_id = buffers
+                .iter()
+                .find(|(_, output_ids)| output_ids.contains(&id))
+                .map(|(index, _)| *index);
+            // older exports only name the buffer in the path, e.g. /media/previz/buffer01.png
+            let by_src = || {
+                let digit = input.src.strip_suffix(".png")?.chars().last()?;
+                let index = digit.to_digit(10)? as usize;
+                (index < BUFFER_OUTPUT_IDS.len()).then_some(index)
+            };
+            by_id.or_else(by_src).map_or_else(
+                || ChannelSource::Unsupported("buffer".into()),
+                ChannelSource::Buffer,
+            )
+        }
+        ctype => ChannelSource::Unsupported(ctype.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPORT: &str = r#"{"Shader": {
+        "ver": "0.1",
+        "info": {"id": "abcd12", "name": "Feedback"},
+        "renderpass": [
+            {
+                "name": "Image", "type": "image", "code": "void mainImage(out vec4 c, in vec2 p) { c = texture(iChannel0, p); }",
+                "inputs": [{"id": "4dXGR8", "src": "/media/previz/buffer00.png", "ctype": "buffer", "channel": 0,
+                    "sampler": {"filter": "linear", "wrap": "clamp", "vflip": "true", "srgb": "false", "internal": "byte"}}],
+                "outputs": [{"id": "4dfGRr", "channel": 0}]
+            },
+            {
+                "name": "Buffer A", "type": "buffer", "code": "void mainImage(out vec4 c, in vec2 p) { c = vec4(0.0); }",
+                "inputs": [
+                    {"id": "4dXGR8", "src": "/media/previz/buffer00.png", "ctype": "buffer", "channel": 0,
+                        "sampler": {"filter": "nearest", "wrap": "clamp", "vflip": "true", "srgb": "false"}},
+                    {"id": 17, "src": "/media/a/noise.png", "ctype": "texture", "channel": 2,
+                        "sampler": {"filter": "mipmap", "wrap": "repeat", "vflip": "false", "srgb": "true"}},
+                    {"id": 33, "src": "/media/a/cube.jpg", "ctype": "cubemap", "channel": 3}
+                ],
+                "outputs": [{"id": "4dXGR8", "channel": 0}]
+            },
+            {"name": "Common", "type": "common", "code": "float twice(float x) { return 2.0 * x; }", "inputs": [], "outputs": []}
+        ]
+    }}"#;
+
+    #[test]
+    fn test_parse_export() {
+        let shadertoy = Shadertoy::parse(EXPORT).unwrap();
+        assert_eq!(shadertoy.name, "Feedback");
+        assert!(shadertoy.common.contains("twice"));
+        let kinds: Vec<PassKind> = shadertoy.passes.iter().map(|pass| pass.kind).collect();
+        assert_eq!(kinds, [PassKind::Buffer(0), PassKind::Image]);
+
+        let buffer = &shadertoy.passes[0];
+        assert_eq!(buffer.channels[0].source, ChannelSource::Buffer(0));
+        assert_eq!(buffer.channels[0].options.min_filter, Filter::Nearest);
+        assert_eq!(buffer.channels[0].options.wrap, Wrap::ClampToEdge);
+        let noise = &buffer.channels[1];
+        assert_eq!(noise.index, 2);
+        assert_eq!(
+            noise.source,
+            ChannelSource::Texture("/media/a/noise.png".into())
+        );
+        assert!(noise.options.mipmaps && noise.options.srgb && !noise.vflip);
+        assert_eq!(buffer.channels[2].source.sampler_type(), "samplerCube");
+
+        // a bare shader object and a list of them work too
+        let document: Value = serde_json::from_str(EXPORT).unwrap();
+        let bare = document["Shader"].to_string();
+        assert_eq!(Shadertoy::parse(&bare).unwrap(), shadertoy);
+        assert_eq!(Shadertoy::parse(&format!("[{bare}]")).unwrap(), shadertoy);
+    }
+
+    #[test]
+    fn test_buffer_from_src() {
+        let source = |src: &str| {
+            let input: InputJson = serde_json::from_value(serde_json::json!({
+                "id": 1, "filepath": src, "type": "buffer", "channel": 0
+            }))
+            .unwrap();
+            channel_source(&input, &[])
+        };
+        assert_eq!(
+            source("/media/previz/buffer03.png"),
+            ChannelSource::Buffer(3)
+        );
+        assert_eq!(
+            source("/media/previz/buffer07.png"),
+            ChannelSource::Unsupported("buffer".into())
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_export() {
+        assert!(Shadertoy::parse("not json").is_err());
+        assert!(Shadertoy::parse(r#"{"Shader": {"renderpass": []}}"#).is_err());
+        let no_image = r#"{"renderpass": [{"name": "Buffer A", "type": "buffer", "code": ""}]}"#;
+        assert!(Shadertoy::parse(no_image).is_err());
+    }
+
+    #[test]
+    fn test_fragment_source() {
+        let shadertoy = Shadertoy::parse(EXPORT).unwrap();
+        let source = shadertoy.fragment_source(&shadertoy.passes[0]);
+        assert!(source.starts_with("#version 330 core\n"));
+        assert!(source.contains("uniform sampler2D iChannel0;"));
+        assert!(source.contains("uniform samplerCube iChannel3;"));
+        let common = source.find("twice").unwrap();
+        let code = source.find("c = vec4(0.0)").unwrap();
+        assert!(common < code);
+        assert!(!source.contains("shadertoy_FragColor.a = 1.0"));
+
+        let image = shadertoy.fragment_source(&shadertoy.passes[1]);
+        assert!(image.contains("shadertoy_FragColor.a = 1.0"));
+    }
+}
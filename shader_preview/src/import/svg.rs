@@ -0,0 +1,906 @@
+//! Importer for SVG files
+//!
+//! Supports `<path>`, `<polygon>`, `<rect>` and `<circle>` elements, nested `<g>` groups,
+//! `transform` attributes and solid `fill` colors. Curves and arcs are flattened to line
+//! segments within a given tolerance. Fills that can't be imported, like gradients, keep the
+//! inherited fill and transforms that can't be parsed are ignored, both with a warning.
+//!
+//! SVG has its y-axis pointing down, the imported shapes are flipped so they appear upright
+//! in the y-up world space of ShaderPreview. Every closed subpath becomes its own [`SvgShape`],
+//! holes are not cut out of the shapes they lie in.
+use crate::run::mesh::{Color, Mesh};
+use crate::{Polygon, shapes};
+use roxmltree::Node;
+use std::f32::consts::TAU;
+use std::path::Path;
+use vatnar_linalg::Point2;
+
+/// Default flattening tolerance used by [`load_svg`], in SVG user units
+pub const DEFAULT_TOLERANCE: f32 = 0.25;
+
+/// A filled outline read from an SVG file
+#[derive(Debug, Clone)]
+pub struct SvgShape {
+    /// Outline of the shape, not triangulated
+    pub outline: Polygon,
+    pub fill: Color,
+}
+
+impl SvgShape {
+    /// Triangulates the outline for drawing with `gl::TRIANGLES`
+    pub fn triangles(&self) -> Polygon {
+        shapes::triangulate(&self.outline)
+    }
+
+    /// Uploads the triangulated shape as a [`Mesh`], requires a current GL context
+    pub fn to_mesh(&self) -> Mesh {
+        Mesh::from_polygon(self.triangles(), gl::TRIANGLES, self.fill)
+    }
+}
+
+/// Reads and imports an SVG file using [`DEFAULT_TOLERANCE`]
+pub fn load_svg(path: impl AsRef<Path>) -> Result<Vec<SvgShape>, String> {
+    let path = path.as_ref();
+    let source = std::fs::read_to_string(path)
+        .map_err(|e| format!("Couldn't read SVG file '{}': {e}", path.display()))?;
+    parse_svg(&source, DEFAULT_TOLERANCE)
+}
+
+/// Imports all supported elements of an SVG document
+///
+/// # Arguments
+/// * `source` - SVG document as text
+/// * `tolerance` - max distance between a curve and its flattened line segments
+///
+/// # Examples
+/// ```
+/// use shader_preview::import::svg::parse_svg;
+///
+/// let svg = r#"<svg><rect x="0" y="0" width="2" height="1" fill="red"/></svg>"#;
+/// let shapes = parse_svg(svg, 0.1).unwrap();
+/// assert_eq!(shapes.len(), 1);
+/// assert_eq!(shapes[0].outline.len(), 4);
+/// ```
+pub fn parse_svg(source: &str, tolerance: f32) -> Result<Vec<SvgShape>, String> {
+    if tolerance <= 0.0 {
+        return Err(format!("Tolerance has to be positive, got {tolerance}"));
+    }
+    let document = roxmltree::Document::parse(source).map_err(|e| format!("Invalid SVG: {e}"))?;
+
+    let root = document.root_element();
+    if root.tag_name().name() != "svg" {
+        return Err(format!(
+            "Expected <svg> root element, found <{}>",
+            root.tag_name().name()
+        ));
+    }
+
+    let mut shapes = Vec::new();
+    // flip the y-axis, SVG is y-down
+    let flip = Transform([1.0, 0.0, 0.0, -1.0, 0.0, 0.0]);
+    let context = Context {
+        transform: flip,
+        fill: Some((0.0, 0.0, 0.0)),
+        color: (0.0, 0.0, 0.0),
+        tolerance,
+    };
+    import_children(root, &context, &mut shapes)?;
+    Ok(shapes)
+}
+
+/// Inherited state while walking the element tree
+#[derive(Clone, Copy)]
+struct Context {
+    transform: Transform,
+    /// `None` when the fill is `none`
+    fill: Option<(f32, f32, f32)>,
+    /// The `color` property, used by `currentColor`
+    color: (f32, f32, f32),
+    tolerance: f32,
+}
+
+fn import_children(node: Node, parent: &Context, shapes: &mut Vec<SvgShape>) -> Result<(), String> {
+    for child in node.children().filter(Node::is_element) {
+        let mut context = *parent;
+        let tag = child.tag_name().name();
+        if let Some(transform) = child.attribute("transform") {
+            match parse_transform(transform) {
+                Ok(transform) => context.transform = parent.transform.then(&transform),
+                Err(e) => log::warn!("{e}, ignoring the transform of <{tag}>"),
+            }
+        }
+        if let Some(color) = style_property(child, "color") {
+            match parse_color(color, parent.color) {
+                Ok(Some(color)) => context.color = color,
+                Ok(None) => {}
+                Err(e) => log::warn!("{e}, keeping the inherited color of <{tag}>"),
+            }
+        }
+        if let Some(fill) = style_property(child, "fill") {
+            match parse_fill(fill, context.color) {
+                Ok(fill) => context.fill = fill,
+                Err(e) => log::warn!("{e}, keeping the inherited fill of <{tag}>"),
+            }
+        }
+
+        let outlines = match tag {
+            "g" | "svg" => {
+                import_children(child, &context, shapes)?;
+                continue;
+            }
+            "path" => parse_path(child.attribute("d").unwrap_or(""), &context)?,
+            "polygon" => vec![parse_points(child.attribute("points").unwrap_or(""))?],
+            "rect" => vec![rect_outline(child, &context)?],
+            "circle" => {
+                let radius = number_attribute(child, "r")?;
+                let center = Point2::new(
+                    number_attribute(child, "cx")?,
+                    number_attribute(child, "cy")?,
+                );
+                let segments =
+                    circle_segments(radius * context.transform.scale(), context.tolerance);
+                vec![shapes::circle(center, radius, segments).0]
+            }
+            _ => continue,
+        };
+        // path outlines are transformed while flattening
+        let transform_points = tag != "path";
+
+        let Some(fill) = context.fill else {
+            continue;
+        };
+        for outline in outlines.into_iter().filter(|o| o.len() >= 3) {
+            let outline = if transform_points {
+                outline
+                    .into_iter()
+                    .map(|p| context.transform.apply(p))
+                    .collect()
+            } else {
+                outline
+            };
+            shapes.push(SvgShape {
+                outline: Polygon(outline),
+                fill: fill.into(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Returns a presentation property, from the `style` attribute if present there
+fn style_property<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    let from_style = node.attribute("style").and_then(|style| {
+        style.split(';').find_map(|declaration| {
+            let (key, value) = declaration.split_once(':')?;
+            (key.trim() == name).then(|| value.trim())
+        })
+    });
+    from_style.or_else(|| node.attribute(name).map(str::trim))
+}
+
+fn number_attribute(node: Node, name: &str) -> Result<f32, String> {
+    match node.attribute(name) {
+        None => Ok(0.0),
+        Some(value) => parse_length(value)
+            .ok_or_else(|| format!("Invalid number '{value}' for attribute '{name}'")),
+    }
+}
+
+/// Parses a number with an optional `px` unit
+fn parse_length(value: &str) -> Option<f32> {
+    let value = value.trim();
+    value.strip_suffix("px").unwrap_or(value).parse().ok()
+}
+
+fn rect_outline(node: Node, context: &Context) -> Result<Vec<Point2<f32>>, String> {
+    let min = Point2::new(number_attribute(node, "x")?, number_attribute(node, "y")?);
+    let width = number_attribute(node, "width")?;
+    let height = number_attribute(node, "height")?;
+    if !(width.is_finite() && height.is_finite() && width >= 0.0 && height >= 0.0) {
+        return Err(format!("Invalid <rect> size {width} x {height}"));
+    }
+    // a missing rx or ry takes the value of the other one
+    let radius = match (node.attribute("rx"), node.attribute("ry")) {
+        (None, None) => 0.0,
+        (Some(_), _) => number_attribute(node, "rx")?,
+        (None, Some(_)) => number_attribute(node, "ry")?,
+    };
+
+    if radius <= 0.0 {
+        return Ok(shapes::rectangle(min, width, height).0);
+    }
+    let segments = circle_segments(radius * context.transform.scale(), context.tolerance) / 4;
+    Ok(shapes::rounded_rectangle(min, width, height, radius, segments.max(1)).0)
+}
+
+/// Segments needed for a full circle to stay within `tolerance` of the true circle
+fn circle_segments(radius: f32, tolerance: f32) -> usize {
+    if radius <= tolerance {
+        return 8;
+    }
+    let angle = 2.0 * (1.0 - tolerance / radius).acos();
+    ((TAU / angle).ceil() as usize).clamp(8, 1024)
+}
+
+/// Parses the `points` attribute of `<polygon>`
+fn parse_points(points: &str) -> Result<Vec<Point2<f32>>, String> {
+    let mut tokens = Tokenizer::new(points);
+    let mut outline = Vec::new();
+    while !tokens.at_end() {
+        outline.push(Point2::new(tokens.number()?, tokens.number()?));
+    }
+    Ok(outline)
+}
+
+/// Affine transform `[a, b, c, d, e, f]` as in SVG's `matrix(a b c d e f)`
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Transform([f32; 6]);
+
+impl Transform {
+    const IDENTITY: Transform = Transform([1.0, 0.0, 0.0, 1.0, 0.0, 0.0]);
+
+    fn apply(&self, p: Point2<f32>) -> Point2<f32> {
+        let [a, b, c, d, e, f] = self.0;
+        Point2::new(a * p.x + c * p.y + e, b * p.x + d * p.y + f)
+    }
+
+    /// Transform applying `inner` first, then `self`
+    fn then(&self, inner: &Transform) -> Transform {
+        let [a1, b1, c1, d1, e1, f1] = self.0;
+        let [a2, b2, c2, d2, e2, f2] = inner.0;
+        Transform([
+            a1 * a2 + c1 * b2,
+            b1 * a2 + d1 * b2,
+            a1 * c2 + c1 * d2,
+            b1 * c2 + d1 * d2,
+            a1 * e2 + c1 * f2 + e1,
+            b1 * e2 + d1 * f2 + f1,
+        ])
+    }
+
+    /// Average scale factor, used to pick segment counts for curves
+    fn scale(&self) -> f32 {
+        let [a, b, c, d, ..] = self.0;
+        (a * d - b * c).abs().sqrt()
+    }
+}
+
+/// Parses a `transform` attribute, a list of transform functions applied right to left
+fn parse_transform(value: &str) -> Result<Transform, String> {
+    let mut transform = Transform::IDENTITY;
+    let mut rest = value.trim();
+    while !rest.is_empty() {
+        let open = rest
+            .find('(')
+            .ok_or_else(|| format!("Invalid transform '{value}'"))?;
+        let close = rest
+            .find(')')
+            .ok_or_else(|| format!("Invalid transform '{value}'"))?;
+        let name = rest[..open].trim();
+        let mut tokens = Tokenizer::new(&rest[open + 1..close]);
+        let mut args = Vec::new();
+        while !tokens.at_end() {
+            args.push(tokens.number()?);
+        }
+
+        let arg = |i: usize| args.get(i).copied();
+        let function = match (name, args.len()) {
+            ("matrix", 6) => Transform([args[0], args[1], args[2], args[3], args[4], args[5]]),
+            ("translate", 1 | 2) => Transform([1.0, 0.0, 0.0, 1.0, args[0], arg(1).unwrap_or(0.0)]),
+            ("scale", 1 | 2) => Transform([args[0], 0.0, 0.0, arg(1).unwrap_or(args[0]), 0.0, 0.0]),
+            ("rotate", 1 | 3) => {
+                let (sin, cos) = args[0].to_radians().sin_cos();
+                let rotation = Transform([cos, sin, -sin, cos, 0.0, 0.0]);
+                let (cx, cy) = (arg(1).unwrap_or(0.0), arg(2).unwrap_or(0.0));
+                Transform([1.0, 0.0, 0.0, 1.0, cx, cy])
+                    .then(&rotation)
+                    .then(&Transform([1.0, 0.0, 0.0, 1.0, -cx, -cy]))
+            }
+            ("skewX", 1) => Transform([1.0, 0.0, args[0].to_radians().tan(), 1.0, 0.0, 0.0]),
+            ("skewY", 1) => Transform([1.0, args[0].to_radians().tan(), 0.0, 1.0, 0.0, 0.0]),
+            _ => return Err(format!("Unsupported transform '{}'", &rest[..=close])),
+        };
+        transform = transform.then(&function);
+        rest = rest[close + 1..].trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+    }
+    Ok(transform)
+}
+
+/// Parses a `fill`, a color or a paint server reference with a fallback color
+fn parse_fill(value: &str, current: (f32, f32, f32)) -> Result<Option<(f32, f32, f32)>, String> {
+    let Some(reference) = value.strip_prefix("url(") else {
+        return parse_color(value, current);
+    };
+    match reference.split_once(')') {
+        Some((_, fallback)) if !fallback.trim().is_empty() => parse_color(fallback.trim(), current),
+        _ => Err(format!("Unsupported paint server '{value}'")),
+    }
+}
+
+/// Parses a color, `None` for `none` and fully transparent colors
+///
+/// Alpha is ignored otherwise, `current` is the color of `currentColor`.
+fn parse_color(value: &str, current: (f32, f32, f32)) -> Result<Option<(f32, f32, f32)>, String> {
+    let invalid = || format!("Unsupported color '{value}'");
+    let channel = |hex: &str| u8::from_str_radix(hex, 16).ok().map(|v| v as f32 / 255.0);
+    let visible = |rgb, alpha: f32| (alpha > 0.0).then_some(rgb);
+
+    if value == "none" || value == "transparent" {
+        return Ok(None);
+    }
+    if value == "currentColor" {
+        return Ok(Some(current));
+    }
+    if let Some(hex) = value.strip_prefix('#') {
+        let expanded: String = match hex.len() {
+            3 | 4 => hex.chars().flat_map(|c| [c, c]).collect(),
+            6 | 8 => hex.to_string(),
+            _ => return Err(invalid()),
+        };
+        let channels: Vec<f32> = (0..expanded.len())
+            .step_by(2)
+            .map(|i| expanded.get(i..i + 2).and_then(channel))
+            .collect::<Option<_>>()
+            .ok_or_else(invalid)?;
+        return match channels[..] {
+            [r, g, b] => Ok(Some((r, g, b))),
+            [r, g, b, a] => Ok(visible((r, g, b), a)),
+            _ => Err(invalid()),
+        };
+    }
+    let function = value
+        .strip_prefix("rgb(")
+        .or_else(|| value.strip_prefix("rgba("));
+    if let Some(args) = function.and_then(|rest| rest.strip_suffix(')')) {
+        let channels: Vec<f32> = args
+            .split(',')
+            .enumerate()
+            .map(|(i, arg)| {
+                let arg = arg.trim();
+                match arg.strip_suffix('%') {
+                    Some(percent) => percent.parse::<f32>().map(|p| p / 100.0),
+                    // alpha is a fraction, the color channels go up to 255
+                    None if i == 3 => arg.parse::<f32>(),
+                    None => arg.parse::<f32>().map(|v| v / 255.0),
+                }
+            })
+            .collect::<Result<_, _>>()
+            .map_err(|_| invalid())?;
+        return match channels[..] {
+            [r, g, b] => Ok(Some((r, g, b))),
+            [r, g, b, a] => Ok(visible((r, g, b), a)),
+            _ => Err(invalid()),
+        };
+    }
+
+    let named = match value {
+        "black" => (0, 0, 0),
+        "white" => (255, 255, 255),
+        "red" => (255, 0, 0),
+        "lime" => (0, 255, 0),
+        "green" => (0, 128, 0),
+        "blue" => (0, 0, 255),
+        "yellow" => (255, 255, 0),
+        "cyan" | "aqua" => (0, 255, 255),
+        "magenta" | "fuchsia" => (255, 0, 255),
+        "gray" | "grey" => (128, 128, 128),
+        "silver" => (192, 192, 192),
+        "maroon" => (128, 0, 0),
+        "olive" => (128, 128, 0),
+        "purple" => (128, 0, 128),
+        "teal" => (0, 128, 128),
+        "navy" => (0, 0, 128),
+        "orange" => (255, 165, 0),
+        _ => return Err(invalid()),
+    };
+    Ok(Some((
+        named.0 as f32 / 255.0,
+        named.1 as f32 / 255.0,
+        named.2 as f32 / 255.0,
+    )))
+}
+
+/// Parses path data into flattened, transformed outlines, one per subpath
+fn parse_path(data: &str, context: &Context) -> Result<Vec<Vec<Point2<f32>>>, String> {
+    let mut path = PathBuilder::new(context);
+    let mut tokens = Tokenizer::new(data);
+    let mut command = None;
+
+    while !tokens.at_end() {
+        if let Some(c) = tokens.command() {
+            command = Some(c);
+        } else if command.is_none() {
+            return Err(format!("Path data has to start with a command: '{data}'"));
+        }
+        let c = command.unwrap();
+        let relative = c.is_ascii_lowercase();
+        let base = if relative {
+            path.current
+        } else {
+            Point2::new(0.0, 0.0)
+        };
+        let point = |tokens: &mut Tokenizer| -> Result<Point2<f32>, String> {
+            Ok(Point2::new(
+                base.x + tokens.number()?,
+                base.y + tokens.number()?,
+            ))
+        };
+
+        match c.to_ascii_uppercase() {
+            'M' => {
+                let to = point(&mut tokens)?;
+                path.move_to(to);
+                // further coordinate pairs are implicit line-tos
+                command = Some(if relative { 'l' } else { 'L' });
+            }
+            'L' => {
+                let to = point(&mut tokens)?;
+                path.line_to(to);
+            }
+            'H' => {
+                let x = tokens.number()? + if relative { path.current.x } else { 0.0 };
+                path.line_to(Point2::new(x, path.current.y));
+            }
+            'V' => {
+                let y = tokens.number()? + if relative { path.current.y } else { 0.0 };
+                path.line_to(Point2::new(path.current.x, y));
+            }
+            'C' => {
+                let (c1, c2, to) = (
+                    point(&mut tokens)?,
+                    point(&mut tokens)?,
+                    point(&mut tokens)?,
+                );
+                path.cubic_to(c1, c2, to);
+            }
+            'S' => {
+                let c1 = path.reflected_control('C');
+                let (c2, to) = (point(&mut tokens)?, point(&mut tokens)?);
+                path.cubic_to(c1, c2, to);
+            }
+            'Q' => {
+                let (control, to) = (point(&mut tokens)?, point(&mut tokens)?);
+                path.quadratic_to(control, to);
+            }
+            'T' => {
+                let control = path.reflected_control('Q');
+                let to = point(&mut tokens)?;
+                path.quadratic_to(control, to);
+            }
+            'A' => {
+                let (rx, ry, rotation) = (tokens.number()?, tokens.number()?, tokens.number()?);
+                let (large_arc, sweep) = (tokens.flag()?, tokens.flag()?);
+                let to = point(&mut tokens)?;
+                path.arc_to(rx, ry, rotation, large_arc, sweep, to);
+            }
+            'Z' => {
+                path.close();
+                command = None;
+            }
+            other => return Err(format!("Unsupported path command '{other}'")),
+        }
+    }
+    Ok(path.finish())
+}
+
+/// Accumulates flattened subpaths while walking path commands
+struct PathBuilder<'a> {
+    context: &'a Context,
+    subpaths: Vec<Vec<Point2<f32>>>,
+    /// Flattened, transformed points of the current subpath
+    points: Vec<Point2<f32>>,
+    /// Current point and subpath start in untransformed SVG coordinates
+    current: Point2<f32>,
+    start: Point2<f32>,
+    /// Last control point and the kind of curve it belongs to, for `S` and `T`
+    last_control: Option<(char, Point2<f32>)>,
+}
+
+impl<'a> PathBuilder<'a> {
+    fn new(context: &'a Context) -> Self {
+        PathBuilder {
+            context,
+            subpaths: Vec::new(),
+            points: Vec::new(),
+            current: Point2::new(0.0, 0.0),
+            start: Point2::new(0.0, 0.0),
+            last_control: None,
+        }
+    }
+
+    fn move_to(&mut self, to: Point2<f32>) {
+        self.end_subpath();
+        self.current = to;
+        self.start = to;
+        self.points.push(self.context.transform.apply(to));
+        self.last_control = None;
+    }
+
+    fn line_to(&mut self, to: Point2<f32>) {
+        self.ensure_started();
+        self.current = to;
+        self.points.push(self.context.transform.apply(to));
+        self.last_control = None;
+    }
+
+    fn cubic_to(&mut self, c1: Point2<f32>, c2: Point2<f32>, to: Point2<f32>) {
+        self.ensure_started();
+        let t = &self.context.transform;
+        let (p0, p1, p2, p3) = (t.apply(self.current), t.apply(c1), t.apply(c2), t.apply(to));
+
+        let dd =
+            distance(second_difference(p0, p1, p2)).max(distance(second_difference(p1, p2, p3)));
+        let steps =
+            ((3.0 * dd / (4.0 * self.context.tolerance)).sqrt().ceil() as usize).clamp(1, 256);
+        for i in 1..=steps {
+            let s = i as f32 / steps as f32;
+            let u = 1.0 - s;
+            let (w0, w1, w2, w3) = (u * u * u, 3.0 * u * u * s, 3.0 * u * s * s, s * s * s);
+            self.points.push(Point2::new(
+                w0 * p0.x + w1 * p1.x + w2 * p2.x + w3 * p3.x,
+                w0 * p0.y + w1 * p1.y + w2 * p2.y + w3 * p3.y,
+            ));
+        }
+        self.current = to;
+        self.last_control = Some(('C', c2));
+    }
+
+    fn quadratic_to(&mut self, control: Point2<f32>, to: Point2<f32>) {
+        self.ensure_started();
+        let t = &self.context.transform;
+        let (p0, p1, p2) = (t.apply(self.current), t.apply(control), t.apply(to));
+
+        let dd = distance(second_difference(p0, p1, p2));
+        let steps = ((dd / (4.0 * self.context.tolerance)).sqrt().ceil() as usize).clamp(1, 256);
+        for i in 1..=steps {
+            let s = i as f32 / steps as f32;
+            let u = 1.0 - s;
+            let (w0, w1, w2) = (u * u, 2.0 * u * s, s * s);
+            self.points.push(Point2::new(
+                w0 * p0.x + w1 * p1.x + w2 * p2.x,
+                w0 * p0.y + w1 * p1.y + w2 * p2.y,
+            ));
+        }
+        self.current = to;
+        self.last_control = Some(('Q', control));
+    }
+
+    /// Elliptical arc, converted from endpoint to center parameterization as described in
+    /// the SVG implementation notes (appendix B.2.4)
+    fn arc_to(
+        &mut self,
+        rx: f32,
+        ry: f32,
+        rotation: f32,
+        large_arc: bool,
+        sweep: bool,
+        to: Point2<f32>,
+    ) {
+        let from = self.current;
+        let (mut rx, mut ry) = (rx.abs(), ry.abs());
+        if rx == 0.0 || ry == 0.0 || from == to {
+            self.line_to(to);
+            return;
+        }
+        self.ensure_started();
+
+        let (sin, cos) = rotation.to_radians().sin_cos();
+        let dx = (from.x - to.x) / 2.0;
+        let dy = (from.y - to.y) / 2.0;
+        let x1 = cos * dx + sin * dy;
+        let y1 = -sin * dx + cos * dy;
+
+        // scale up radii that are too small to reach the end point
+        let lambda = (x1 * x1) / (rx * rx) + (y1 * y1) / (ry * ry);
+        if lambda > 1.0 {
+            rx *= lambda.sqrt();
+            ry *= lambda.sqrt();
+        }
+
+        let numerator = rx * rx * ry * ry - rx * rx * y1 * y1 - ry * ry * x1 * x1;
+        let denominator = rx * rx * y1 * y1 + ry * ry * x1 * x1;
+        let mut factor = (numerator / denominator).max(0.0).sqrt();
+        if large_arc == sweep {
+            factor = -factor;
+        }
+        let cx1 = factor * rx * y1 / ry;
+        let cy1 = -factor * ry * x1 / rx;
+        let cx = cos * cx1 - sin * cy1 + (from.x + to.x) / 2.0;
+        let cy = sin * cx1 + cos * cy1 + (from.y + to.y) / 2.0;
+
+        let angle = |ux: f32, uy: f32| uy.atan2(ux);
+        let start = angle((x1 - cx1) / rx, (y1 - cy1) / ry);
+        let mut delta = angle((-x1 - cx1) / rx, (-y1 - cy1) / ry) - start;
+        if sweep && delta < 0.0 {
+            delta += TAU;
+        } else if !sweep && delta > 0.0 {
+            delta -= TAU;
+        }
+
+        let radius = rx.max(ry) * self.context.transform.scale();
+        let segments = circle_segments(radius, self.context.tolerance);
+        let steps = ((delta.abs() / TAU * segments as f32).ceil() as usize).max(1);
+        for i in 1..=steps {
+            let theta = start + delta * i as f32 / steps as f32;
+            let (ex, ey) = (rx * theta.cos(), ry * theta.sin());
+            let point = Point2::new(cos * ex - sin * ey + cx, sin * ex + cos * ey + cy);
+            self.points.push(self.context.transform.apply(point));
+        }
+        self.current = to;
+        self.last_control = None;
+    }
+
+    fn close(&mut self) {
+        self.end_subpath();
+        self.current = self.start;
+        self.last_control = None;
+    }
+
+    /// Control point for the smooth curve commands, the reflection of the previous control
+    /// point if the previous command was a curve of the same `kind`
+    fn reflected_control(&self, kind: char) -> Point2<f32> {
+        match self.last_control {
+            Some((last_kind, control)) if last_kind == kind => Point2::new(
+                2.0 * self.current.x - control.x,
+                2.0 * self.current.y - control.y,
+            ),
+            _ => self.current,
+        }
+    }
+
+    /// Drawing commands after a close path continue from the start of the closed subpath
+    fn ensure_started(&mut self) {
+        if self.points.is_empty() {
+            self.points.push(self.context.transform.apply(self.current));
+        }
+    }
+
+    fn end_subpath(&mut self) {
+        let mut points = std::mem::take(&mut self.points);
+        points.dedup();
+        if points.len() > 1 && points.first() == points.last() {
+            points.pop();
+        }
+        if points.len() >= 3 {
+            self.subpaths.push(points);
+        }
+    }
+
+    fn finish(mut self) -> Vec<Vec<Point2<f32>>> {
+        self.end_subpath();
+        self.subpaths
+    }
+}
+
+fn second_difference(a: Point2<f32>, b: Point2<f32>, c: Point2<f32>) -> (f32, f32) {
+    (a.x - 2.0 * b.x + c.x, a.y - 2.0 * b.y + c.y)
+}
+
+fn distance(v: (f32, f32)) -> f32 {
+    (v.0 * v.0 + v.1 * v.1).sqrt()
+}
+
+/// Splits path data and point lists into commands and numbers
+struct Tokenizer<'a> {
+    source: &'a str,
+    position: usize,
+}
+
+impl<'a> Tokenizer<'a> {
+    fn new(source: &'a str) -> Self {
+        Tokenizer {
+            source,
+            position: 0,
+        }
+    }
+
+    fn skip_separators(&mut self) {
+        let rest = &self.source[self.position..];
+        let trimmed = rest.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+        self.position += rest.len() - trimmed.len();
+    }
+
+    fn at_end(&mut self) -> bool {
+        self.skip_separators();
+        self.position >= self.source.len()
+    }
+
+    /// Consumes a command letter if the next token is one
+    fn command(&mut self) -> Option<char> {
+        self.skip_separators();
+        let c = self.source[self.position..].chars().next()?;
+        if c.is_ascii_alphabetic() && c != 'e' && c != 'E' {
+            self.position += 1;
+            Some(c)
+        } else {
+            None
+        }
+    }
+
+    /// Arc flags are single digits that don't need separators, like `a1 1 0 011 1`
+    fn flag(&mut self) -> Result<bool, String> {
+        self.skip_separators();
+        match self.source[self.position..].chars().next() {
+            Some('0') => {
+                self.position += 1;
+                Ok(false)
+            }
+            Some('1') => {
+                self.position += 1;
+                Ok(true)
+            }
+            _ => Err(format!(
+                "Expected arc flag at '{}'",
+                &self.source[self.position..]
+            )),
+        }
+    }
+
+    fn number(&mut self) -> Result<f32, String> {
+        self.skip_separators();
+        let bytes = self.source.as_bytes();
+        let start = self.position;
+        let mut end = start;
+        let mut seen_dot = false;
+        let mut seen_exponent = false;
+
+        if end < bytes.len() && matches!(bytes[end], b'+' | b'-') {
+            end += 1;
+        }
+        while end < bytes.len() {
+            match bytes[end] {
+                b'0'..=b'9' => {}
+                // a second dot starts the next number, like in "1.5.5"
+                b'.' if !seen_dot && !seen_exponent => seen_dot = true,
+                b'e' | b'E' if !seen_exponent && end > start => {
+                    seen_exponent = true;
+                    if end + 1 < bytes.len() && matches!(bytes[end + 1], b'+' | b'-') {
+                        end += 1;
+                    }
+                }
+                _ => break,
+            }
+            end += 1;
+        }
+
+        let text = &self.source[start..end];
+        let value = text
+            .parse()
+            .map_err(|_| format!("Expected number at '{}'", &self.source[start..]))?;
+        self.position = end;
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: Point2<f32>, x: f32, y: f32) -> bool {
+        (a.x - x).abs() < 1e-4 && (a.y - y).abs() < 1e-4
+    }
+
+    #[test]
+    fn test_tokenizer() {
+        let mut tokens = Tokenizer::new("M1.5.5-2e1,3 1e-1");
+        assert_eq!(tokens.command(), Some('M'));
+        assert_eq!(tokens.number(), Ok(1.5));
+        assert_eq!(tokens.number(), Ok(0.5));
+        assert_eq!(tokens.number(), Ok(-20.0));
+        assert_eq!(tokens.number(), Ok(3.0));
+        assert_eq!(tokens.number(), Ok(0.1));
+        assert!(tokens.at_end());
+    }
+
+    #[test]
+    fn test_transform() {
+        let t = parse_transform("translate(10, 5) scale(2)").unwrap();
+        assert!(close(t.apply(Point2::new(1.0, 1.0)), 12.0, 7.0));
+
+        let t = parse_transform("rotate(90 1 1)").unwrap();
+        assert!(close(t.apply(Point2::new(2.0, 1.0)), 1.0, 2.0));
+    }
+
+    #[test]
+    fn test_colors() {
+        let black = (0.0, 0.0, 0.0);
+        assert_eq!(parse_color("#f00", black), Ok(Some((1.0, 0.0, 0.0))));
+        assert_eq!(parse_color("#0000ff", black), Ok(Some((0.0, 0.0, 1.0))));
+        assert_eq!(parse_color("#0000ff80", black), Ok(Some((0.0, 0.0, 1.0))));
+        assert_eq!(parse_color("#f000", black), Ok(None));
+        assert_eq!(
+            parse_color("rgb(255, 0, 100%)", black),
+            Ok(Some((1.0, 0.0, 1.0)))
+        );
+        assert_eq!(
+            parse_color("rgba(0, 255, 0, 0.5)", black),
+            Ok(Some((0.0, 1.0, 0.0)))
+        );
+        assert_eq!(parse_color("rgba(0, 255, 0, 0)", black), Ok(None));
+        assert_eq!(
+            parse_color("currentColor", (0.5, 0.5, 0.5)),
+            Ok(Some((0.5, 0.5, 0.5)))
+        );
+        assert_eq!(parse_color("none", black), Ok(None));
+        assert!(parse_color("#12", black).is_err());
+        assert!(parse_color("#12345", black).is_err());
+
+        assert_eq!(
+            parse_fill("url(#grad) red", black),
+            Ok(Some((1.0, 0.0, 0.0)))
+        );
+        assert!(parse_fill("url(#grad)", black).is_err());
+    }
+
+    #[test]
+    fn test_parse_svg() {
+        let svg = r##"
+            <svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 100 100">
+                <g fill="#00ff00" transform="translate(10 0)">
+                    <rect x="0" y="0" width="10" height="20"/>
+                    <polygon points="0,0 10,0 5,10" style="fill: blue"/>
+                    <circle cx="50" cy="50" r="10" fill="none"/>
+                </g>
+                <path d="M0 0 L10 0 L10 10 Z M20 20 h5 v5 h-5 z" fill="red"/>
+                <path d="M0 0 C0 10 10 10 10 0 Q5 -5 0 0"/>
+                <path d="M0 0 A5 5 0 0 1 10 0 Z"/>
+            </svg>"##;
+        let shapes = parse_svg(svg, 0.01).unwrap();
+        assert_eq!(shapes.len(), 6);
+
+        let rect = &shapes[0];
+        let (_, g, _) = rect.fill.into();
+        assert_eq!(g, 1.0);
+        // translated and flipped upside down
+        assert!(close(rect.outline[2], 20.0, -20.0));
+
+        let (_, _, b) = shapes[1].fill.into();
+        assert_eq!(b, 1.0);
+
+        let (r, _, _) = shapes[2].fill.into();
+        assert_eq!(r, 1.0);
+        assert_eq!(shapes[2].outline.len(), 3);
+        assert_eq!(shapes[3].outline.len(), 4);
+        assert!(close(shapes[3].outline[0], 20.0, -20.0));
+
+        // curves are flattened into many points, default fill is black
+        assert!(shapes[4].outline.len() > 10);
+        let fill: (f32, f32, f32) = shapes[4].fill.into();
+        assert_eq!(fill, (0.0, 0.0, 0.0));
+
+        // positive sweep turns towards negative y in SVG space, so up once flipped
+        let arc = &shapes[5].outline;
+        assert!(arc.iter().all(|p| p.y >= -1e-4));
+        let top = arc.iter().map(|p| p.y).fold(f32::MIN, f32::max);
+        assert!((top - 5.0).abs() < 0.01);
+        assert_eq!(shapes[5].triangles().len(), (arc.len() - 2) * 3);
+    }
+
+    #[test]
+    fn test_parse_svg_errors() {
+        assert!(parse_svg("<html/>", 0.1).is_err());
+        assert!(parse_svg("<svg><path d=\"X 1 2\"/></svg>", 0.1).is_err());
+        assert!(parse_svg("<svg><rect width=\"a\"/></svg>", 0.1).is_err());
+        let negative = r#"<svg><rect width="-1" height="5" rx="2"/></svg>"#;
+        assert!(parse_svg(negative, 0.1).is_err());
+    }
+
+    #[test]
+    fn test_parse_svg_fallbacks() {
+        let svg = r##"
+            <svg>
+                <g fill="red" color="blue">
+                    <rect width="1" height="1" fill="url(#gradient)"/>
+                    <rect width="1" height="1" fill="currentColor"/>
+                    <rect width="1" height="1" transform="perspective(2) translate(1)"/>
+                    <rect width="1" height="1" fill="#00ff0080" style="fill: hsl(0, 0%, 0%)"/>
+                </g>
+            </svg>"##;
+        let shapes = parse_svg(svg, 0.1).unwrap();
+        let fills: Vec<(f32, f32, f32)> = shapes.iter().map(|s| s.fill.into()).collect();
+        let (red, blue) = ((1.0, 0.0, 0.0), (0.0, 0.0, 1.0));
+        assert_eq!(fills, [red, blue, red, red]);
+        // the transform is ignored
+        assert!(close(shapes[2].outline[2], 1.0, -1.0));
+    }
+}
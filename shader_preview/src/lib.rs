@@ -1,3 +1,10 @@
+pub mod check;
+pub mod cli;
+pub mod config;
+pub mod golden;
+pub mod import;
 pub mod run;
-pub use run::mesh::Polygon;
+pub mod shapes;
+pub mod stroke;
+pub use run::mesh::{Color, Mesh, Polygon};
 pub use run::run;
@@ -1,7 +1,16 @@
-use shader_preview::run;
+use clap::Parser;
+use shader_preview::cli::{Cli, Command};
+use shader_preview::{check, golden, run};
 
 fn main() {
-    if let Err(e) = run() {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    let cli = Cli::parse();
+    let result = match &cli.command {
+        Some(Command::Test(args)) => golden::run_tests(&cli, args),
+        Some(Command::Check(args)) => check::run_check(args),
+        None => run(&cli),
+    };
+    if let Err(e) = result {
         eprintln!("Error: {}", e);
         std::process::exit(1);
     }
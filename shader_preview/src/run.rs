@@ -1,145 +1,430 @@
 extern crate gl;
 extern crate glfw;
 
+use crate::cli::Cli;
+use crate::config::Config;
 pub use crate::polygon;
+use backend::{GlBackend, RenderBackend};
+use camera::{Camera2D, CameraMotion};
+use context::Capabilities;
 use gl::TRIANGLES;
-use gl::types::GLfloat;
-use glfw::{Action, Context, Key};
+use gl_debug::GlDebug;
+use glfw::Context;
+use grid::Grid;
+use input::{Action, Input};
+use isf_view::IsfView;
+use material::{Material, UniformValue, UniformValues};
 use mesh::Mesh;
-use shader_program::{ShaderProgram, Uniform};
+use model_view::ModelView;
+use mouse::Mouse;
+use overlay::Overlay;
+use post::PostChain;
+use render_state::RenderState;
+use scene::{Scene, SceneConfig};
+use shadertoy_view::ShadertoyView;
+use stats::{FrameSample, FrameStats, GpuTimer};
+use std::rc::Rc;
+use texture::{Texture, TextureBindings, TextureOptions};
 use vatnar_linalg::Vector2;
+use window::WindowedState;
 
+pub mod backend;
+pub mod camera;
+pub mod context;
+mod controls;
+mod fullscreen;
+mod gl_debug;
+mod grid;
+pub mod headless;
+pub mod input;
+pub(crate) mod isf_view;
+pub mod material;
 pub(crate) mod mesh;
-mod shader_program;
-pub fn run() -> Result<(), Box<dyn std::error::Error>> {
-    let mut glfw = glfw::init(glfw::fail_on_errors)?;
-    let window_size = Vector2::new(800, 600);
-
-    // create window and events receiver
-    let (mut window, window_event_receiver) = glfw
-        .create_window(
-            window_size.x,
-            window_size.y,
-            "ShaderPreview",
-            glfw::WindowMode::Windowed,
-        )
-        .ok_or("Failed to create GLFW window.")?;
-
-    // set window to current on thread, and turn on key polling
-    window.make_current();
+pub(crate) mod model_view;
+mod mouse;
+pub mod output;
+pub mod overlay;
+pub mod post;
+pub mod render_state;
+mod render_target;
+pub mod scene;
+mod screenshot;
+pub mod shader_program;
+pub(crate) mod shadertoy_view;
+pub mod stats;
+pub mod texture;
+pub mod window;
+
+/// Opens the preview window, pass a `.obj`, `.gltf` or `.glb` file as argument
+/// to preview a model in 3D instead of the 2D scene, a `.json` Shadertoy export to run
+/// its passes, or an ISF shader (`.fs` or `.isf`) to run it with a panel of its inputs
+///
+/// Arguments of the form `name=image.png` load the image and bind it to the
+/// `sampler2D` uniform `name` of the 2D shader, e.g. `iChannel0=noise.png`.
+///
+/// Key bindings and window options are read from [`crate::config::CONFIG_FILE`], or the file
+/// passed with `--config`, see [`input`] for the actions. Dragging with the left mouse button
+/// pans the 2D scene or orbits the model. The 2D shader receives the world position of the
+/// cursor as `u_mouse`, the framebuffer size in pixels as `u_resolution`, and Shadertoy style
+/// `iMouse` and `iResolution`.
+pub fn run(cli: &Cli) -> Result<(), Box<dyn std::error::Error>> {
+    let (texture_args, model_path) = cli.textures_and_model()?;
+    let extension = model_path
+        .as_deref()
+        .and_then(|path| path.extension())
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase);
+    let (model_path, shadertoy_path, isf_path) = match extension.as_deref() {
+        Some("json") => (None, model_path, None),
+        Some("fs" | "isf") => (None, None, model_path),
+        _ => (model_path, None, None),
+    };
+    let mut config = Config::load_from(cli.config.as_deref())?;
+    cli.apply(&mut config);
+    let window_config = config.window.clone();
+
+    // errors also surface as failed calls, e.g. window creation, which are reported with context
+    let mut glfw = glfw::init(|error, description| log::error!("GLFW {error:?}: {description}"))?;
+    config.gl.apply_hints(&mut glfw);
+    let (mut window, window_event_receiver) = window::create_window(&mut glfw, &window_config)
+        .map_err(|e| {
+            format!(
+                "{} ({} requested)",
+                e.trim_end_matches('.'),
+                config.gl.describe()
+            )
+        })?;
     window.set_key_polling(true);
     window.set_scroll_polling(true);
-    glfw.set_swap_interval(glfw::SwapInterval::Sync(1)); // v-sync
+    window.set_cursor_pos_polling(true);
+    window.set_mouse_button_polling(true);
+    window.set_framebuffer_size_polling(true);
+    window.set_content_scale_polling(true);
+    window.set_focus_polling(true);
+    let (x, y) = window.get_pos();
+    let mut windowed = WindowedState {
+        position: (x, y),
+        size: (window_config.width as i32, window_config.height as i32),
+    };
 
     // Initializes OpenGL function pointers by querying their addresses from the current context.
     // This must be called after creating the OpenGL context, or OpenGL functions won't work.
     gl::load_with(|symbol| window.get_proc_address(symbol) as *const _);
-    unsafe {
-        gl::Enable(gl::PROGRAM_POINT_SIZE);
+    let capabilities = Capabilities::query();
+    capabilities.log();
+    let gl_debug = GlDebug::install(config.gl.fatal_errors);
+    if config.gl.debug && !gl_debug.uses_callback() {
+        log::warn!("No GL debug context with KHR_debug, only glGetError is checked");
+    }
+    // OpenGL ES always takes the point size from the shader and multisamples if the
+    // framebuffer has samples
+    if !capabilities.es {
+        unsafe {
+            gl::Enable(gl::PROGRAM_POINT_SIZE);
+            if window_config.samples > 0 {
+                gl::Enable(gl::MULTISAMPLE);
+            }
+        }
     }
 
     // Compile shaders
     let vertex_src = include_str!("vertex_shader.glsl");
     let fragment_src = include_str!("fragment_shader.glsl");
-    let shader_program = ShaderProgram::from_source(vertex_src, fragment_src)?;
-    shader_program.use_program();
-
-    // Blending
-    unsafe {
-        gl::Enable(gl::BLEND);
-        gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
-    }
-    // Setup uniforms
-    let time_uniform = shader_program.get_uniform("u_time");
-    let color_uniform = shader_program.get_uniform("u_color");
+    let mut backend = GlBackend;
+    let shader_program = Rc::new(backend.create_program(vertex_src, fragment_src)?);
 
-    let offset_uniform = shader_program.get_uniform("u_offset");
-    let zoom_uniform = shader_program.get_uniform("u_zoom");
-
-    // The offset sort of acts like moving a camera
-    // TODO MARK figure out "zooming"
-    let mut offset: Vector2<f64> = Vector2::new(0.0, 0.0);
-    let mut zoom = 1.0;
-
-    let meshes = define_meshes();
+    // meshes without a material of their own use the 2D shader and the command line textures
+    let mut material = Material::new(shader_program.clone());
+    material.textures = load_textures(&texture_args, config.output.srgb)?;
 
+    // everything below works in framebuffer pixels, which differ from window
+    // coordinates on HiDPI screens
+    let (width, height) = window.get_framebuffer_size();
     unsafe {
-        gl::ClearColor(0.1, 0.1, 0.1, 1.0);
+        gl::Viewport(0, 0, width, height);
     }
+    let mut camera = Camera2D::new(width as f64, height as f64);
 
-    unsafe {
-        gl::Uniform3f(*color_uniform, 1.0, 0.2, 0.5);
-        gl::Uniform2f(*offset_uniform, 1.0, 0.0);
-    }
+    let mut input = Input::new(config.bindings);
+    let mut motion = CameraMotion::new(config.camera);
+    let mut mouse = Mouse::default();
+    let mut overlay = Overlay::new()?;
+    overlay.scale = window.get_content_scale().0.round().max(1.0);
+
+    let mut grid = Grid::new()?;
+    let mut scene = define_scene(&config.scene);
+    let mut model_view = model_path.as_deref().map(ModelView::load).transpose()?;
+    let mut shadertoy = shadertoy_path
+        .as_deref()
+        .map(ShadertoyView::load)
+        .transpose()?;
+    let mut isf = isf_path.as_deref().map(IsfView::load).transpose()?;
 
-    let mut offset_keys = Vector2::new(0, 0);
+    let mut time = 0.0;
+    let mut last_frame = glfw.get_time();
+    let mut paused = false;
+    let mut show_grid = true;
+    let mut show_stats = false;
+    let mut stats = FrameStats::default();
+    // timer queries are only an extension on OpenGL ES
+    let mut gpu_timer = (!capabilities.es).then(GpuTimer::new).flatten();
+    let mut post = PostChain::load(&config.post.passes, &config.output)?;
+    gl_debug.check("setup")?;
 
     // main loop
     while !window.should_close() {
+        let frame_start = glfw.get_time();
         glfw.poll_events();
 
-        // TODO change so it checks for releases and stuff
-        // instead so you can hold both left and down fir instance and it work
         for (_, event) in glfw::flush_messages(&window_event_receiver) {
-            // println!("Event gotten: {event:?}"); // DEBUG
+            input.handle_event(&event);
+            if input.is_held(Action::Drag) != mouse.pressed {
+                if mouse.pressed {
+                    mouse.release()
+                } else {
+                    mouse.press()
+                }
+            }
 
-            use glfw::WindowEvent::{Key as glfwKey, Scroll};
+            use glfw::WindowEvent::{ContentScale, CursorPos, FramebufferSize};
             match event {
-                glfwKey(Key::Escape, _, Action::Press, _) => window.set_should_close(true),
-
-                // Camera movement
-                glfwKey(Key::Left | Key::A, _, action, _) => match action {
-                    Action::Press => offset_keys.x += 1,
-                    Action::Release => offset_keys.x -= 1,
-                    _ => {}
-                },
-
-                glfwKey(Key::Right | Key::D, _, action, _) => match action {
-                    Action::Press => offset_keys.x -= 1,
-                    Action::Release => offset_keys.x += 1,
-                    _ => {}
-                },
-
-                glfwKey(Key::Up | Key::W, _, action, _) => match action {
-                    Action::Press => offset_keys.y -= 1,
-                    Action::Release => offset_keys.y += 1,
-                    _ => {}
-                },
-
-                glfwKey(Key::Down | Key::S, _, action, _) => match action {
-                    Action::Press => offset_keys.y += 1,
-                    Action::Release => offset_keys.y -= 1,
-                    _ => {}
-                },
-
-                // Zoom
-                Scroll(_, y_offset) => {
-                    zoom += y_offset;
-                    if zoom as i32 == 0 {
-                        zoom += y_offset
+                CursorPos(x, y) => {
+                    let previous = mouse.position;
+                    if let Some(delta) = mouse.move_to(to_framebuffer(&window, x, y)) {
+                        if let Some(model_view) = &mut model_view {
+                            model_view.orbit(-delta.x * 0.01, delta.y * 0.01);
+                        } else if shadertoy.is_none() && isf.is_none() {
+                            // keep the world point that was grabbed under the cursor
+                            let from = camera.screen_to_world(previous);
+                            let to = camera.screen_to_world(mouse.position);
+                            camera.position += Vector2::new(from.x - to.x, from.y - to.y);
+                        }
+                    }
+                }
+
+                // Resizing
+                FramebufferSize(width, height) => {
+                    unsafe {
+                        gl::Viewport(0, 0, width, height);
                     }
+                    camera.set_viewport(width as f64, height as f64);
                 }
+                ContentScale(x_scale, _) => overlay.scale = x_scale.round().max(1.0),
                 _ => {}
             }
         }
 
-        unsafe {
-            gl::Uniform1f(*time_uniform, glfw.get_time() as f32); // update u_time
-            gl::Uniform2f(*offset_uniform, offset.x as GLfloat, offset.y as GLfloat);
-            let scale = if zoom < 0.0 { -1.0 / zoom } else { zoom };
-            offset += offset_keys.normalized_i32() * 0.01 * (1.0 / scale);
+        if input.was_pressed(Action::Quit) {
+            window.set_should_close(true);
+        }
+        if input.was_pressed(Action::Pause) {
+            paused = !paused;
+        }
+        if input.was_pressed(Action::ToggleGrid) {
+            show_grid = !show_grid;
+        }
+        if input.was_pressed(Action::ToggleStats) {
+            show_stats = !show_stats;
+        }
+        if let Some(isf) = isf
+            .as_mut()
+            .filter(|_| input.was_pressed(Action::ToggleControls))
+        {
+            isf.controls.visible = !isf.controls.visible;
+        }
+        if input.was_pressed(Action::ToggleFullscreen) {
+            let monitor = window_config.monitor;
+            if let Err(e) =
+                window::toggle_fullscreen(&mut glfw, &mut window, monitor, &mut windowed)
+            {
+                log::error!("Couldn't toggle fullscreen: {e}");
+            }
+        }
+        if input.was_pressed(Action::Reload) {
+            let reloaded = Config::load_from(cli.config.as_deref()).and_then(|mut config| {
+                cli.apply(&mut config);
+                let textures = load_textures(&texture_args, config.output.srgb)?;
+                let model = model_path
+                    .as_deref()
+                    .map(ModelView::load)
+                    .transpose()
+                    .map_err(|e| e.to_string())?;
+                let shadertoy = shadertoy_path
+                    .as_deref()
+                    .map(ShadertoyView::load)
+                    .transpose()?;
+                let isf = isf_path.as_deref().map(IsfView::load).transpose()?;
+                let post = PostChain::load(&config.post.passes, &config.output)?;
+                Ok((config, textures, model, shadertoy, isf, post))
+            });
+            match reloaded {
+                Ok((
+                    config,
+                    reloaded_textures,
+                    reloaded_model,
+                    reloaded_shadertoy,
+                    mut reloaded_isf,
+                    reloaded_post,
+                )) => {
+                    input.map = config.bindings;
+                    motion.config = config.camera;
+                    scene = define_scene(&config.scene);
+                    material.textures = reloaded_textures;
+                    model_view = reloaded_model;
+                    shadertoy = reloaded_shadertoy;
+                    // sliders keep their values
+                    if let (Some(reloaded), Some(old)) = (&mut reloaded_isf, &isf) {
+                        reloaded.controls.restore(&old.controls);
+                    }
+                    isf = reloaded_isf;
+                    post = reloaded_post;
+                }
+                Err(e) => log::error!("Reload failed, keeping the old state: {e}"),
+            }
+        }
+
+        // clamped so a stalled frame doesn't send the camera flying
+        let now = glfw.get_time();
+        let frame_time = now - last_frame;
+        let dt = frame_time.min(0.1);
+        last_frame = now;
+        if !paused {
+            time += dt;
+        }
+
+        let pan = Vector2::new(
+            input.axis(Action::PanLeft, Action::PanRight),
+            input.axis(Action::PanDown, Action::PanUp),
+        );
+        let zoom = input.scroll(Action::ZoomIn) - input.scroll(Action::ZoomOut);
+        let (pan, held_zoom) = motion.update(pan, input.axis(Action::ZoomOut, Action::ZoomIn), dt);
+        if let Some(model_view) = &mut model_view {
+            // pan keys orbit around the model instead
+            let orbit = pan * (motion.config.orbit_speed / motion.config.pan_speed.max(1e-9));
+            model_view.orbit(-orbit.x, orbit.y);
+            model_view.zoom(zoom + held_zoom);
+        } else {
+            camera.position += pan * (1.0 / camera.zoom());
+            camera.zoom_at(mouse.position, zoom);
+            let center = camera.viewport() * 0.5;
+            camera.zoom_at(center, held_zoom);
+        }
+
+        let viewport = camera.viewport();
+        if let Some(isf) = &mut isf {
+            let size = [viewport.x as f32, viewport.y as f32];
+            isf.controls.update(&mouse, &overlay, size);
+        }
+        let timing = show_stats && gpu_timer.as_mut().is_some_and(|timer| timer.begin());
+        post.begin(viewport.x as u32, viewport.y as u32)?;
+        if let Some(model_view) = &mut model_view {
+            backend.clear(scene.clear_color);
+            model_view.render(&mut backend, camera.aspect(), time);
+        } else if let Some(shadertoy) = &mut shadertoy {
+            let size = (viewport.x as u32, viewport.y as u32);
+            let time_delta = if paused { 0.0 } else { dt };
+            shadertoy.render(size, time, time_delta, mouse.shadertoy(viewport.y))?;
+        } else if let Some(isf) = &mut isf {
+            let size = (viewport.x as u32, viewport.y as u32);
+            let time_delta = if paused { 0.0 } else { dt };
+            isf.render(size, time, time_delta, &material.textures)?;
+            isf.controls.end_frame();
+        } else {
+            let cursor = camera.screen_to_world(mouse.position);
 
-            gl::Uniform1f(*zoom_uniform, scale as GLfloat)
+            let view: [f32; 9] = camera.view_projection().to_column_major_f32()[..]
+                .try_into()
+                .expect("view projection is 3x3");
+            let (width, height) = (viewport.x as f32, viewport.y as f32);
+            let frame_uniforms = UniformValues::default()
+                .with("u_time", UniformValue::Float(time as f32))
+                .with("u_view", UniformValue::Mat3(view))
+                .with(
+                    "u_mouse",
+                    UniformValue::Vec2([cursor.x as f32, cursor.y as f32]),
+                )
+                .with("iMouse", UniformValue::Vec4(mouse.shadertoy(viewport.y)))
+                .with("u_resolution", UniformValue::Vec2([width, height]))
+                .with("iResolution", UniformValue::Vec3([width, height, 1.0]));
+            if show_grid {
+                grid.update(&camera);
+            }
+            backend.clear(scene.clear_color);
+            if show_grid {
+                grid.draw(&camera);
+            }
+            render(&mut backend, &scene, &material, &frame_uniforms);
+
+            if show_grid {
+                grid.labels(&camera, &mut overlay);
+            }
+            overlay.label(8.0, 8.0, &format!("x: {:.4}\ny: {:.4}", cursor.x, cursor.y));
+        }
+        post.finish(time);
+        if let Some(timer) = gpu_timer.as_mut().filter(|_| timing) {
+            timer.end();
         }
 
-        render(&meshes, color_uniform);
+        if let Some(isf) = &isf {
+            isf.controls
+                .draw(&mut overlay, [viewport.x as f32, viewport.y as f32]);
+        }
+        if show_stats {
+            stats.draw(&mut overlay, viewport.x as f32);
+        }
+        overlay.draw(viewport.x as f32, viewport.y as f32);
+
+        if input.was_pressed(Action::Screenshot) {
+            match screenshot::save_screenshot(viewport.x as u32, viewport.y as u32) {
+                Ok(path) => log::info!("Saved screenshot to '{}'", path.display()),
+                Err(e) => log::error!("{e}"),
+            }
+        }
+        input.end_frame();
+        gl_debug.check("rendering")?;
+
+        stats.push(FrameSample {
+            frame_ms: frame_time * 1000.0,
+            cpu_ms: (glfw.get_time() - frame_start) * 1000.0,
+            gpu_ms: None,
+        });
+        if let Some(timer) = &mut gpu_timer {
+            for (frames_ago, gpu_ms) in timer.poll() {
+                stats.set_gpu_ms(frames_ago, gpu_ms);
+            }
+            timer.end_frame();
+        }
         window.swap_buffers();
     }
 
     Ok(())
 }
 
-fn define_meshes() -> Vec<Mesh> {
+/// Loads the `name=path` texture arguments, decoding 8 bit images from sRGB if `srgb` is set
+fn load_textures(args: &[String], srgb: bool) -> Result<TextureBindings, String> {
+    let options = TextureOptions {
+        srgb,
+        ..Default::default()
+    };
+    let mut textures = TextureBindings::default();
+    for arg in args {
+        let (name, path) = arg.split_once('=').unwrap_or_default();
+        textures.insert(name, Texture::load(path, &options)?);
+    }
+    Ok(textures)
+}
+
+/// Converts a cursor position from window coordinates to framebuffer pixels
+fn to_framebuffer(window: &glfw::PWindow, x: f64, y: f64) -> Vector2<f64> {
+    let (window_width, window_height) = window.get_size();
+    let (width, height) = window.get_framebuffer_size();
+    Vector2::new(
+        x * width as f64 / window_width.max(1) as f64,
+        y * height as f64 / window_height.max(1) as f64,
+    )
+}
+
+fn define_scene(config: &SceneConfig) -> Scene {
     let triangle1 = polygon![-0.5, -0.5, 0.0, 0.5, 0.5, -0.5];
     let triangle2 = polygon![-0.8, 0.2, -0.3, 0.9, 0.2, 0.3];
     let triangle3 = polygon![5.0, 5.0, 6.0, 5.0, 5.5, 6.0];
@@ -147,26 +432,188 @@ fn define_meshes() -> Vec<Mesh> {
     let triangle5 = polygon![3.0, -5.0, 4.0, -5.0, 3.5, -6.0];
     let triangle6 = polygon![-4.0, -4.0, -3.0, -4.0, -3.5, -5.0];
 
-    vec![
+    let meshes = vec![
         Mesh::from_polygon(triangle1, TRIANGLES, (1.0, 0.0, 0.0).into()),
         Mesh::from_polygon(triangle2, TRIANGLES, (0.0, 1.0, 0.0).into()),
         Mesh::from_polygon(triangle3, TRIANGLES, (0.0, 0.0, 1.0).into()),
         Mesh::from_polygon(triangle4, TRIANGLES, (1.0, 1.0, 0.0).into()),
         Mesh::from_polygon(triangle5, TRIANGLES, (0.0, 1.0, 1.0).into()),
         Mesh::from_polygon(triangle6, TRIANGLES, (1.0, 1.0, 1.0).into()),
-    ]
+    ];
+    Scene::with_config(meshes, config)
 }
 
-fn render(meshes: &Vec<Mesh>, color: Uniform) {
-    unsafe {
-        gl::Clear(gl::COLOR_BUFFER_BIT);
-    }
-    // Draw meshes
-    for mesh in meshes {
-        unsafe {
-            let (r, g, b) = mesh.color.into();
-            gl::Uniform3f(color.into(), r, g, b);
+/// Draws the meshes of `scene`
+///
+/// Each mesh is drawn with its own render state and material, or `default_material`. Meshes
+/// are grouped by program where the draw order allows, see [`material::draw_order`].
+/// `frame_uniforms` are set whenever the material changes, so values and textures of one
+/// material don't carry over to the next, and `u_color` per mesh.
+fn render(
+    backend: &mut impl RenderBackend,
+    scene: &Scene,
+    default_material: &Material,
+    frame_uniforms: &UniformValues,
+) {
+    let materials: Vec<&Material> = scene
+        .meshes
+        .iter()
+        .map(|mesh| mesh.material.as_deref().unwrap_or(default_material))
+        .collect();
+    let draws: Vec<(u32, RenderState)> = scene
+        .meshes
+        .iter()
+        .zip(&materials)
+        .map(|(mesh, material)| (material.program.id, mesh.render_state))
+        .collect();
+
+    let mut current_material: Option<&Material> = None;
+    for i in material::draw_order(&draws) {
+        let (mesh, material) = (&scene.meshes[i], materials[i]);
+        if !current_material.is_some_and(|current| std::ptr::eq(current, material)) {
+            if let Some(previous) = current_material {
+                previous.textures.unbind(backend);
+            }
+            if current_material.is_none_or(|current| current.program.id != draws[i].0) {
+                backend.use_program(&material.program);
+            }
+            // material uniforms take precedence over the frame uniforms
+            frame_uniforms.apply(backend, &material.program);
+            material.apply(backend);
+            current_material = Some(material);
         }
-        mesh.draw();
+        let (r, g, b) = mesh.color.into();
+        backend.set_uniform(&material.program, "u_color", UniformValue::Vec3([r, g, b]));
+        backend.apply_render_state(&mesh.render_state);
+        mesh.draw_with(backend);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use backend::{Call, RecordingBackend};
+    use render_state::BlendMode;
+    use shader_program::ShaderProgram;
+
+    fn triangle(backend: &mut RecordingBackend, color: (f32, f32, f32)) -> Mesh {
+        let vertices = [0.0, 0.0, 1.0, 0.0, 0.0, 1.0];
+        let layout = [mesh::VertexAttribute::POSITION_2D];
+        Mesh::upload(backend, &vertices, &layout, None, TRIANGLES, color.into())
+    }
+
+    #[test]
+    fn test_render_groups_meshes_by_program() {
+        let mut backend = RecordingBackend::default();
+        let default_program = Rc::new(backend.create_program("", "").unwrap());
+        let other_program = Rc::new(backend.create_program("", "").unwrap());
+        let default_material = Material::new(default_program.clone());
+        let other_material = Rc::new(
+            Material::new(other_program.clone()).with_uniform("u_time", UniformValue::Float(7.0)),
+        );
+
+        let opaque = RenderState::OPAQUE_3D;
+        let scene = Scene::new(vec![
+            triangle(&mut backend, (1.0, 0.0, 0.0)).with_render_state(opaque),
+            triangle(&mut backend, (0.0, 1.0, 0.0))
+                .with_material(other_material.clone())
+                .with_render_state(opaque),
+            triangle(&mut backend, (0.0, 0.0, 1.0)).with_render_state(opaque),
+            triangle(&mut backend, (1.0, 1.0, 1.0))
+                .with_material(other_material)
+                .with_render_state(opaque.with_blend(BlendMode::Additive)),
+        ]);
+        let frame_uniforms = UniformValues::default().with("u_time", UniformValue::Float(1.5));
+        let vertex_arrays: Vec<u32> = backend
+            .take_calls()
+            .into_iter()
+            .filter_map(|call| match call {
+                Call::CreateMeshBuffers { vertex_array, .. } => Some(vertex_array),
+                _ => None,
+            })
+            .collect();
+
+        render(&mut backend, &scene, &default_material, &frame_uniforms);
+
+        // the opaque meshes of the default program are drawn first, the blended one stays last
+        assert_eq!(
+            backend.draws(),
+            [
+                (vertex_arrays[0], TRIANGLES, 3),
+                (vertex_arrays[2], TRIANGLES, 3),
+                (vertex_arrays[1], TRIANGLES, 3),
+                (vertex_arrays[3], TRIANGLES, 3),
+            ]
+        );
+
+        let uniform = |program: &ShaderProgram, name: &str, value| Call::SetUniform {
+            program: program.id,
+            name: name.to_string(),
+            value,
+        };
+        let calls = backend.take_calls();
+        let other_calls = &calls[calls
+            .iter()
+            .position(|call| *call == Call::UseProgram(other_program.id))
+            .unwrap()..];
+        assert_eq!(
+            other_calls[..3],
+            [
+                Call::UseProgram(other_program.id),
+                uniform(&other_program, "u_time", UniformValue::Float(1.5)),
+                // the material overrides the frame uniform
+                uniform(&other_program, "u_time", UniformValue::Float(7.0)),
+            ]
+        );
+        assert!(calls.contains(&uniform(
+            &default_program,
+            "u_color",
+            UniformValue::Vec3([0.0, 0.0, 1.0])
+        )));
+        assert!(calls.contains(&Call::ApplyRenderState(
+            opaque.with_blend(BlendMode::Additive)
+        )));
+        let program_switches = calls
+            .iter()
+            .filter(|call| matches!(call, Call::UseProgram(_)))
+            .count();
+        // each program once
+        assert_eq!(program_switches, 2);
+    }
+
+    #[test]
+    fn test_render_resets_uniforms_between_materials() {
+        let mut backend = RecordingBackend::default();
+        let program = Rc::new(backend.create_program("", "").unwrap());
+        let default_material = Material::new(program.clone());
+        let override_material = Rc::new(
+            Material::new(program.clone()).with_uniform("u_time", UniformValue::Float(7.0)),
+        );
+        let scene = Scene::new(vec![
+            triangle(&mut backend, (1.0, 0.0, 0.0)).with_material(override_material),
+            triangle(&mut backend, (0.0, 1.0, 0.0)),
+        ]);
+        let frame_uniforms = UniformValues::default().with("u_time", UniformValue::Float(1.5));
+        backend.take_calls();
+
+        render(&mut backend, &scene, &default_material, &frame_uniforms);
+
+        let u_time: Vec<UniformValue> = backend
+            .take_calls()
+            .into_iter()
+            .filter_map(|call| match call {
+                Call::SetUniform { name, value, .. } if name == "u_time" => Some(value),
+                _ => None,
+            })
+            .collect();
+        // the default material gets the frame value back
+        assert_eq!(
+            u_time,
+            [
+                UniformValue::Float(1.5),
+                UniformValue::Float(7.0),
+                UniformValue::Float(1.5)
+            ]
+        );
     }
 }
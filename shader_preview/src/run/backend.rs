@@ -0,0 +1,67 @@
+//! The graphics operations the renderer is built on
+//!
+//! [`GlBackend`] issues them to the current OpenGL context, [`SoftwareBackend`] rasterizes on
+//! the CPU and [`RecordingBackend`] only records them, so tests can check what a frame draws
+//! without a GPU.
+use crate::run::material::UniformValue;
+use crate::run::mesh::{Color, VertexAttribute};
+use crate::run::render_state::RenderState;
+use crate::run::shader_program::{ShaderError, ShaderProgram};
+use crate::run::texture::Texture;
+
+mod opengl;
+mod recording;
+mod software;
+
+pub use opengl::GlBackend;
+pub use recording::{Call, RecordingBackend};
+pub use software::{Fragment, FragmentFn, FragmentShader, SoftwareBackend};
+
+/// Vertex and index buffers of a mesh, created by a [`RenderBackend`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MeshBuffers {
+    /// The vertex array object, which also identifies the buffers to backends without one
+    pub vertex_array: u32,
+    pub vertex_buffer: u32,
+    pub index_buffer: Option<u32>,
+}
+
+pub trait RenderBackend {
+    /// Uploads interleaved `vertices` laid out as `layout`, and `indices` if the mesh has them
+    fn create_mesh_buffers(
+        &mut self,
+        vertices: &[f32],
+        layout: &[VertexAttribute],
+        indices: Option<&[u32]>,
+    ) -> MeshBuffers;
+
+    /// Replaces the vertices of `buffers`, keeping the layout
+    fn update_vertices(&mut self, buffers: &MeshBuffers, vertices: &[f32]);
+
+    fn create_program(
+        &mut self,
+        vertex_src: &str,
+        fragment_src: &str,
+    ) -> Result<ShaderProgram, ShaderError>;
+
+    fn use_program(&mut self, program: &ShaderProgram);
+
+    /// Sets a uniform of `program`, which has to be in use
+    ///
+    /// Uniforms the program doesn't have are skipped.
+    fn set_uniform(&mut self, program: &ShaderProgram, name: &str, value: UniformValue);
+
+    fn bind_texture(&mut self, unit: u32, texture: &Texture);
+
+    /// Unbinds the texture of `unit`, samplers reading it see black
+    fn unbind_texture(&mut self, unit: u32);
+
+    fn apply_render_state(&mut self, state: &RenderState);
+
+    /// Clears the color buffer to `color` and the depth buffer
+    fn clear(&mut self, color: Color);
+
+    /// Draws `count` vertices of `buffers`, or `count` indices for indexed buffers, as
+    /// primitives of `draw_mode` like `gl::TRIANGLES`
+    fn draw(&mut self, buffers: &MeshBuffers, draw_mode: u32, count: usize);
+}
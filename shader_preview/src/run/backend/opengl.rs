@@ -0,0 +1,200 @@
+//! The backend drawing with OpenGL
+use super::{MeshBuffers, RenderBackend};
+use crate::run::material::UniformValue;
+use crate::run::mesh::{Color, VertexAttribute};
+use crate::run::render_state::{CullMode, PolygonMode, RenderState};
+use crate::run::shader_program::{ShaderError, ShaderProgram};
+use crate::run::texture::Texture;
+
+/// Draws to the GL context current on this thread, every method requires one
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GlBackend;
+
+impl RenderBackend for GlBackend {
+    fn create_mesh_buffers(
+        &mut self,
+        vertices: &[f32],
+        layout: &[VertexAttribute],
+        indices: Option<&[u32]>,
+    ) -> MeshBuffers {
+        let floats_per_vertex: i32 = layout.iter().map(|a| a.components).sum();
+
+        // 1. Generate and bind a Vertex Array Object VAO
+        let mut vao = 0;
+        unsafe {
+            gl::GenVertexArrays(1, &mut vao); // Store ID to generated VAO in variable
+            gl::BindVertexArray(vao); // Bind the VAO so opengl knows we setting up
+        }
+
+        // 2. Generate and bind a vertex buffer object VBO
+        let mut vbo = 0;
+        unsafe {
+            gl::GenBuffers(1, &mut vbo); // Generate one vbo
+            gl::BindBuffer(gl::ARRAY_BUFFER, vbo); // Bind it as an array buffer
+            gl::BufferData(
+                // Upload the data to GPU
+                gl::ARRAY_BUFFER,
+                size_of_val(vertices) as isize, // Size of data in bytes
+                vertices.as_ptr() as *const _,  // Pointer to data
+                gl::DYNAMIC_DRAW,               // Tell gpu we  update often
+            );
+        }
+
+        // 3. Describe how the data in vbo is laid out
+        let stride = floats_per_vertex * size_of::<f32>() as i32;
+        let mut offset = 0;
+        for attribute in layout {
+            unsafe {
+                gl::VertexAttribPointer(
+                    attribute.location,   // Attribute index (location in shader)
+                    attribute.components, // Components of this attribute
+                    gl::FLOAT,            // Data type is float
+                    gl::FALSE,            // Dont normalize
+                    stride,               // Stride, size of one whole vertex
+                    offset as *const _,   // Byte offset of attribute in the vertex
+                );
+                gl::EnableVertexAttribArray(attribute.location); // enable specified attribute
+            }
+            offset += attribute.components as usize * size_of::<f32>();
+        }
+
+        // 4. Upload indices into an element buffer, the VAO remembers the binding
+        let ebo = indices.map(|indices| {
+            let mut ebo = 0;
+            unsafe {
+                gl::GenBuffers(1, &mut ebo);
+                gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, ebo);
+                gl::BufferData(
+                    gl::ELEMENT_ARRAY_BUFFER,
+                    size_of_val(indices) as isize,
+                    indices.as_ptr() as *const _,
+                    gl::STATIC_DRAW,
+                );
+            }
+            ebo
+        });
+
+        MeshBuffers {
+            vertex_array: vao,
+            vertex_buffer: vbo,
+            index_buffer: ebo,
+        }
+    }
+
+    fn update_vertices(&mut self, buffers: &MeshBuffers, vertices: &[f32]) {
+        unsafe {
+            gl::BindBuffer(gl::ARRAY_BUFFER, buffers.vertex_buffer);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                size_of_val(vertices) as isize,
+                vertices.as_ptr() as *const _,
+                gl::DYNAMIC_DRAW,
+            );
+        }
+    }
+
+    fn create_program(
+        &mut self,
+        vertex_src: &str,
+        fragment_src: &str,
+    ) -> Result<ShaderProgram, ShaderError> {
+        ShaderProgram::from_source(vertex_src, fragment_src)
+    }
+
+    fn use_program(&mut self, program: &ShaderProgram) {
+        unsafe {
+            gl::UseProgram(program.id);
+        }
+    }
+
+    fn set_uniform(&mut self, program: &ShaderProgram, name: &str, value: UniformValue) {
+        let location = *program.get_uniform(name);
+        unsafe {
+            match value {
+                UniformValue::Float(x) => gl::Uniform1f(location, x),
+                UniformValue::Int(x) => gl::Uniform1i(location, x),
+                UniformValue::Vec2([x, y]) => gl::Uniform2f(location, x, y),
+                UniformValue::Vec3([x, y, z]) => gl::Uniform3f(location, x, y, z),
+                UniformValue::Vec4([x, y, z, w]) => gl::Uniform4f(location, x, y, z, w),
+                UniformValue::Mat3(matrix) => {
+                    gl::UniformMatrix3fv(location, 1, gl::FALSE, matrix.as_ptr())
+                }
+                UniformValue::Mat4(matrix) => {
+                    gl::UniformMatrix4fv(location, 1, gl::FALSE, matrix.as_ptr())
+                }
+            }
+        }
+    }
+
+    fn bind_texture(&mut self, unit: u32, texture: &Texture) {
+        texture.bind(unit);
+    }
+
+    fn unbind_texture(&mut self, unit: u32) {
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0 + unit);
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+        }
+    }
+
+    fn apply_render_state(&mut self, state: &RenderState) {
+        unsafe {
+            match state.blend.factors() {
+                Some((src_rgb, dst_rgb, src_alpha, dst_alpha)) => {
+                    gl::Enable(gl::BLEND);
+                    gl::BlendFuncSeparate(src_rgb, dst_rgb, src_alpha, dst_alpha);
+                }
+                None => gl::Disable(gl::BLEND),
+            }
+
+            if state.depth_test {
+                gl::Enable(gl::DEPTH_TEST);
+            } else {
+                gl::Disable(gl::DEPTH_TEST);
+            }
+
+            match state.cull {
+                CullMode::None => gl::Disable(gl::CULL_FACE),
+                CullMode::Back | CullMode::Front => {
+                    gl::Enable(gl::CULL_FACE);
+                    gl::CullFace(if state.cull == CullMode::Back {
+                        gl::BACK
+                    } else {
+                        gl::FRONT
+                    });
+                }
+            }
+
+            // OpenGL ES only fills
+            if gl::PolygonMode::is_loaded() {
+                gl::PolygonMode(
+                    gl::FRONT_AND_BACK,
+                    match state.polygon_mode {
+                        PolygonMode::Fill => gl::FILL,
+                        PolygonMode::Wireframe => gl::LINE,
+                        PolygonMode::Points => gl::POINT,
+                    },
+                );
+            }
+        }
+    }
+
+    fn clear(&mut self, color: Color) {
+        let (r, g, b) = color.into();
+        unsafe {
+            gl::ClearColor(r, g, b, 1.0);
+            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+        }
+    }
+
+    fn draw(&mut self, buffers: &MeshBuffers, draw_mode: u32, count: usize) {
+        unsafe {
+            gl::BindVertexArray(buffers.vertex_array);
+            if buffers.index_buffer.is_some() {
+                gl::DrawElements(draw_mode, count as i32, gl::UNSIGNED_INT, std::ptr::null());
+            } else {
+                gl::DrawArrays(draw_mode, 0, count as i32);
+            }
+        }
+    }
+}
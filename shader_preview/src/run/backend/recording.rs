@@ -0,0 +1,150 @@
+//! A backend recording calls instead of drawing, for tests
+use super::{MeshBuffers, RenderBackend};
+use crate::run::material::UniformValue;
+use crate::run::mesh::{Color, VertexAttribute};
+use crate::run::render_state::RenderState;
+use crate::run::shader_program::{ShaderError, ShaderProgram};
+use crate::run::texture::Texture;
+
+/// A call made to a [`RecordingBackend`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Call {
+    CreateMeshBuffers {
+        vertex_array: u32,
+        vertices: Vec<f32>,
+        indices: Option<Vec<u32>>,
+    },
+    UpdateVertices {
+        vertex_array: u32,
+        vertices: Vec<f32>,
+    },
+    CreateProgram {
+        program: u32,
+    },
+    UseProgram(u32),
+    SetUniform {
+        program: u32,
+        name: String,
+        value: UniformValue,
+    },
+    BindTexture {
+        unit: u32,
+        texture: u32,
+    },
+    UnbindTexture(u32),
+    ApplyRenderState(RenderState),
+    Clear([f32; 3]),
+    Draw {
+        vertex_array: u32,
+        draw_mode: u32,
+        count: usize,
+    },
+}
+
+/// Records every call, creating buffers and programs with increasing ids starting at 1
+#[derive(Debug, Default)]
+pub struct RecordingBackend {
+    pub calls: Vec<Call>,
+    last_id: u32,
+}
+
+impl RecordingBackend {
+    /// Removes and returns the calls recorded so far
+    pub fn take_calls(&mut self) -> Vec<Call> {
+        std::mem::take(&mut self.calls)
+    }
+
+    /// The recorded draw calls as `(vertex_array, draw_mode, count)`
+    pub fn draws(&self) -> Vec<(u32, u32, usize)> {
+        self.calls
+            .iter()
+            .filter_map(|call| match *call {
+                Call::Draw {
+                    vertex_array,
+                    draw_mode,
+                    count,
+                } => Some((vertex_array, draw_mode, count)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn next_id(&mut self) -> u32 {
+        self.last_id += 1;
+        self.last_id
+    }
+}
+
+impl RenderBackend for RecordingBackend {
+    fn create_mesh_buffers(
+        &mut self,
+        vertices: &[f32],
+        _layout: &[VertexAttribute],
+        indices: Option<&[u32]>,
+    ) -> MeshBuffers {
+        let buffers = MeshBuffers {
+            vertex_array: self.next_id(),
+            vertex_buffer: self.next_id(),
+            index_buffer: indices.map(|_| self.next_id()),
+        };
+        self.calls.push(Call::CreateMeshBuffers {
+            vertex_array: buffers.vertex_array,
+            vertices: vertices.to_vec(),
+            indices: indices.map(<[u32]>::to_vec),
+        });
+        buffers
+    }
+
+    fn update_vertices(&mut self, buffers: &MeshBuffers, vertices: &[f32]) {
+        self.calls.push(Call::UpdateVertices {
+            vertex_array: buffers.vertex_array,
+            vertices: vertices.to_vec(),
+        });
+    }
+
+    fn create_program(&mut self, _: &str, _: &str) -> Result<ShaderProgram, ShaderError> {
+        let program = self.next_id();
+        self.calls.push(Call::CreateProgram { program });
+        Ok(ShaderProgram { id: program })
+    }
+
+    fn use_program(&mut self, program: &ShaderProgram) {
+        self.calls.push(Call::UseProgram(program.id));
+    }
+
+    fn set_uniform(&mut self, program: &ShaderProgram, name: &str, value: UniformValue) {
+        self.calls.push(Call::SetUniform {
+            program: program.id,
+            name: name.to_string(),
+            value,
+        });
+    }
+
+    fn bind_texture(&mut self, unit: u32, texture: &Texture) {
+        self.calls.push(Call::BindTexture {
+            unit,
+            texture: texture.id(),
+        });
+    }
+
+    fn unbind_texture(&mut self, unit: u32) {
+        self.calls.push(Call::UnbindTexture(unit));
+    }
+
+    fn apply_render_state(&mut self, state: &RenderState) {
+        self.calls.push(Call::ApplyRenderState(*state));
+    }
+
+    fn clear(&mut self, color: Color) {
+        let (r, g, b) = color.into();
+        self.calls.push(Call::Clear([r, g, b]));
+    }
+
+    fn draw(&mut self, buffers: &MeshBuffers, draw_mode: u32, count: usize) {
+        self.calls.push(Call::Draw {
+            vertex_array: buffers.vertex_array,
+            draw_mode,
+            count,
+        });
+    }
+}
@@ -0,0 +1,688 @@
+//! A backend rasterizing on the CPU, for reference images without a GL driver
+//!
+//! Vertices are transformed like `vertex_shader.glsl` does: the position at location 0 is
+//! multiplied by the `u_view` matrix if the program has one, and used as clip coordinates
+//! otherwise. Fragments get the flat `u_color`, or the color of a Rust closure set with
+//! [`SoftwareBackend::set_fragment_shader`]. GLSL sources and textures are ignored.
+use super::{MeshBuffers, RenderBackend};
+use crate::run::material::{UniformValue, UniformValues};
+use crate::run::mesh::{Color, VertexAttribute};
+use crate::run::render_state::{CullMode, PolygonMode, RenderState};
+use crate::run::shader_program::{ShaderError, ShaderProgram};
+use crate::run::texture::Texture;
+use image::{Rgba, RgbaImage};
+
+/// What a fragment shader closure gets for each pixel a primitive covers
+pub struct Fragment<'a> {
+    /// Window coordinates of the pixel center, origin in the bottom left like `gl_FragCoord`
+    pub position: [f32; 2],
+    /// 0 at the near plane, 1 at the far plane
+    pub depth: f32,
+    /// The floats of the vertices, interpolated across the primitive
+    pub attributes: &'a [f32],
+    /// Uniforms set on the program
+    pub uniforms: &'a UniformValues,
+}
+
+/// Rust function standing in for a GLSL fragment shader
+pub type FragmentFn = dyn Fn(&Fragment) -> [f32; 4];
+
+/// How the fragments of a program are colored
+pub enum FragmentShader {
+    /// The `u_color` uniform, or white without one
+    Flat,
+    /// Returns the RGBA color of a fragment
+    Closure(Box<FragmentFn>),
+}
+
+impl FragmentShader {
+    fn shade(&self, fragment: &Fragment) -> [f32; 4] {
+        match self {
+            FragmentShader::Flat => match fragment.uniforms.get("u_color") {
+                Some(UniformValue::Vec3([r, g, b])) => [r, g, b, 1.0],
+                Some(UniformValue::Vec4(color)) => color,
+                _ => [1.0; 4],
+            },
+            FragmentShader::Closure(shader) => shader(fragment),
+        }
+    }
+}
+
+struct VertexData {
+    vertices: Vec<f32>,
+    floats_per_vertex: usize,
+    /// Offset and length of the position attribute in a vertex
+    position: (usize, usize),
+    indices: Option<Vec<u32>>,
+}
+
+struct Program {
+    uniforms: UniformValues,
+    shader: FragmentShader,
+}
+
+/// A vertex after the vertex stage
+#[derive(Clone, Copy)]
+struct ScreenVertex<'a> {
+    /// Window coordinates in pixels, depth and clip space w
+    x: f32,
+    y: f32,
+    z: f32,
+    w: f32,
+    attributes: &'a [f32],
+}
+
+/// The color and depth buffers, rows bottom to top like in GL
+struct Framebuffer {
+    width: u32,
+    height: u32,
+    color: Vec<[f32; 4]>,
+    depth: Vec<f32>,
+}
+
+/// Rasterizes triangles, lines and points into an RGBA8 image
+pub struct SoftwareBackend {
+    framebuffer: Framebuffer,
+    meshes: Vec<VertexData>,
+    programs: Vec<Program>,
+    /// Index into `programs`
+    current_program: Option<usize>,
+    state: RenderState,
+    /// Width of points in pixels, `vertex_shader.glsl` sets 20
+    pub point_size: f32,
+}
+
+impl SoftwareBackend {
+    pub fn new(width: u32, height: u32) -> Self {
+        let pixels = (width * height) as usize;
+        SoftwareBackend {
+            framebuffer: Framebuffer {
+                width,
+                height,
+                color: vec![[0.0, 0.0, 0.0, 1.0]; pixels],
+                depth: vec![1.0; pixels],
+            },
+            meshes: Vec::new(),
+            programs: Vec::new(),
+            current_program: None,
+            state: RenderState::default(),
+            point_size: 1.0,
+        }
+    }
+
+    /// Colors the fragments of `program` with `shader` instead of the flat `u_color`
+    pub fn set_fragment_shader(&mut self, program: &ShaderProgram, shader: FragmentShader) {
+        self.programs[program.id as usize - 1].shader = shader;
+    }
+
+    /// The color buffer, top row first
+    pub fn image(&self) -> RgbaImage {
+        let Framebuffer {
+            width,
+            height,
+            color,
+            ..
+        } = &self.framebuffer;
+        RgbaImage::from_fn(*width, *height, |x, y| {
+            let pixel = color[((height - 1 - y) * width + x) as usize];
+            Rgba(pixel.map(|c| (c * 255.0).round() as u8))
+        })
+    }
+
+    /// Runs the vertex stage on vertex `index` of `mesh`
+    fn transform<'a>(
+        &self,
+        mesh: &'a VertexData,
+        uniforms: &UniformValues,
+        index: usize,
+    ) -> ScreenVertex<'a> {
+        let attributes =
+            &mesh.vertices[index * mesh.floats_per_vertex..(index + 1) * mesh.floats_per_vertex];
+        let (offset, components) = mesh.position;
+        let position = &attributes[offset..offset + components];
+        let (x, y) = (position[0], position.get(1).copied().unwrap_or(0.0));
+        let z = position.get(2).copied().unwrap_or(0.0);
+        let w = position.get(3).copied().unwrap_or(1.0);
+        let [x, y] = match uniforms.get("u_view") {
+            // column major, the translation is in the last column
+            Some(UniformValue::Mat3(m)) => [
+                m[0] * x + m[3] * y + m[6] * w,
+                m[1] * x + m[4] * y + m[7] * w,
+            ],
+            _ => [x, y],
+        };
+
+        let Framebuffer { width, height, .. } = self.framebuffer;
+        ScreenVertex {
+            x: (x / w + 1.0) / 2.0 * width as f32,
+            y: (y / w + 1.0) / 2.0 * height as f32,
+            z: (z / w + 1.0) / 2.0,
+            w,
+            attributes,
+        }
+    }
+}
+
+impl RenderBackend for SoftwareBackend {
+    fn create_mesh_buffers(
+        &mut self,
+        vertices: &[f32],
+        layout: &[VertexAttribute],
+        indices: Option<&[u32]>,
+    ) -> MeshBuffers {
+        let mut position = (0, 0);
+        let mut offset = 0;
+        for attribute in layout {
+            if attribute.location == 0 {
+                position = (offset, attribute.components as usize);
+            }
+            offset += attribute.components as usize;
+        }
+        self.meshes.push(VertexData {
+            vertices: vertices.to_vec(),
+            floats_per_vertex: offset,
+            position,
+            indices: indices.map(<[u32]>::to_vec),
+        });
+        let id = self.meshes.len() as u32;
+        MeshBuffers {
+            vertex_array: id,
+            vertex_buffer: id,
+            index_buffer: indices.map(|_| id),
+        }
+    }
+
+    fn update_vertices(&mut self, buffers: &MeshBuffers, vertices: &[f32]) {
+        self.meshes[buffers.vertex_array as usize - 1].vertices = vertices.to_vec();
+    }
+
+    fn create_program(&mut self, _: &str, _: &str) -> Result<ShaderProgram, ShaderError> {
+        self.programs.push(Program {
+            uniforms: UniformValues::default(),
+            shader: FragmentShader::Flat,
+        });
+        Ok(ShaderProgram {
+            id: self.programs.len() as u32,
+        })
+    }
+
+    fn use_program(&mut self, program: &ShaderProgram) {
+        self.current_program = Some(program.id as usize - 1);
+    }
+
+    fn set_uniform(&mut self, program: &ShaderProgram, name: &str, value: UniformValue) {
+        self.programs[program.id as usize - 1]
+            .uniforms
+            .set(name, value);
+    }
+
+    fn bind_texture(&mut self, _: u32, _: &Texture) {}
+
+    fn unbind_texture(&mut self, _: u32) {}
+
+    fn apply_render_state(&mut self, state: &RenderState) {
+        self.state = *state;
+    }
+
+    fn clear(&mut self, color: Color) {
+        let (r, g, b) = color.into();
+        self.framebuffer.color.fill(quantize([r, g, b, 1.0]));
+        self.framebuffer.depth.fill(1.0);
+    }
+
+    fn draw(&mut self, buffers: &MeshBuffers, draw_mode: u32, count: usize) {
+        let Some(program) = self.current_program else {
+            log::warn!("Software draw without a program");
+            return;
+        };
+        let mesh = &self.meshes[buffers.vertex_array as usize - 1];
+        let program = &self.programs[program];
+        let indices: Vec<usize> = match &mesh.indices {
+            Some(indices) => indices.iter().take(count).map(|&i| i as usize).collect(),
+            None => (0..count).collect(),
+        };
+        let vertices: Vec<ScreenVertex> = indices
+            .iter()
+            .map(|&i| self.transform(mesh, &program.uniforms, i))
+            .collect();
+
+        let mut raster = Rasterizer {
+            framebuffer: &mut self.framebuffer,
+            program,
+            state: self.state,
+            point_size: self.point_size,
+        };
+        match draw_mode {
+            gl::TRIANGLES => {
+                for triangle in vertices.chunks_exact(3) {
+                    raster.triangle([triangle[0], triangle[1], triangle[2]]);
+                }
+            }
+            gl::TRIANGLE_STRIP => {
+                for (i, triangle) in vertices.windows(3).enumerate() {
+                    // every other triangle is wound the other way around
+                    let [a, b, c] = [triangle[0], triangle[1], triangle[2]];
+                    raster.triangle(if i % 2 == 0 { [a, b, c] } else { [b, a, c] });
+                }
+            }
+            gl::TRIANGLE_FAN => {
+                for pair in vertices.get(1..).unwrap_or_default().windows(2) {
+                    raster.triangle([vertices[0], pair[0], pair[1]]);
+                }
+            }
+            gl::LINES => {
+                for line in vertices.chunks_exact(2) {
+                    raster.line(line[0], line[1]);
+                }
+            }
+            gl::LINE_STRIP | gl::LINE_LOOP => {
+                for line in vertices.windows(2) {
+                    raster.line(line[0], line[1]);
+                }
+                if draw_mode == gl::LINE_LOOP && vertices.len() > 2 {
+                    raster.line(vertices[vertices.len() - 1], vertices[0]);
+                }
+            }
+            gl::POINTS => vertices.iter().for_each(|&vertex| raster.point(vertex)),
+            _ => log::warn!("Software backend can't draw mode {draw_mode:#x}"),
+        }
+    }
+}
+
+/// Draws the primitives of one draw call
+struct Rasterizer<'a> {
+    framebuffer: &'a mut Framebuffer,
+    program: &'a Program,
+    state: RenderState,
+    point_size: f32,
+}
+
+impl Rasterizer<'_> {
+    fn triangle(&mut self, [a, b, c]: [ScreenVertex; 3]) {
+        match self.state.polygon_mode {
+            PolygonMode::Fill => {}
+            PolygonMode::Wireframe => {
+                self.line(a, b);
+                self.line(b, c);
+                self.line(c, a);
+                return;
+            }
+            PolygonMode::Points => {
+                [a, b, c].into_iter().for_each(|vertex| self.point(vertex));
+                return;
+            }
+        }
+        // primitives crossing the camera plane would need clipping
+        if [a, b, c].iter().any(|vertex| vertex.w <= 0.0) {
+            return;
+        }
+
+        // positive for counter-clockwise triangles, which face the camera
+        let area = edge(&a, &b, [c.x, c.y]);
+        let culled = match self.state.cull {
+            CullMode::None => false,
+            CullMode::Back => area < 0.0,
+            CullMode::Front => area > 0.0,
+        };
+        if area == 0.0 || culled {
+            return;
+        }
+        // wind counter-clockwise, so the inside is left of every edge
+        let [a, b, c] = if area > 0.0 { [a, b, c] } else { [a, c, b] };
+        let area = area.abs();
+
+        let Framebuffer { width, height, .. } = *self.framebuffer;
+        let min_x = a.x.min(b.x).min(c.x).floor().max(0.0) as u32;
+        let min_y = a.y.min(b.y).min(c.y).floor().max(0.0) as u32;
+        let max_x = (a.x.max(b.x).max(c.x).ceil() as u32).min(width);
+        let max_y = (a.y.max(b.y).max(c.y).ceil() as u32).min(height);
+        let edges = [(b, c), (c, a), (a, b)];
+        let top_left = edges.map(|(from, to)| is_top_left(&from, &to));
+        let mut attributes = vec![0.0; a.attributes.len()];
+
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                let center = [x as f32 + 0.5, y as f32 + 0.5];
+                let weights = edges.map(|(from, to)| edge(&from, &to, center));
+                // pixels on an edge belong to the triangle left or above it
+                let inside = (0..3).all(|i| weights[i] > 0.0 || (weights[i] == 0.0 && top_left[i]));
+                if !inside {
+                    continue;
+                }
+                let barycentric = weights.map(|weight| weight / area);
+                // interpolate attributes in clip space, not linearly across the screen
+                let perspective = [a.w, b.w, c.w]
+                    .iter()
+                    .zip(barycentric)
+                    .map(|(w, weight)| weight / w)
+                    .collect::<Vec<f32>>();
+                let total: f32 = perspective.iter().sum();
+                for (i, attribute) in attributes.iter_mut().enumerate() {
+                    *attribute = (perspective[0] * a.attributes[i]
+                        + perspective[1] * b.attributes[i]
+                        + perspective[2] * c.attributes[i])
+                        / total;
+                }
+                let depth = barycentric[0] * a.z + barycentric[1] * b.z + barycentric[2] * c.z;
+                self.fragment(x, y, depth, &attributes);
+            }
+        }
+    }
+
+    /// One pixel wide line, without the last pixel so strips don't draw shared ends twice
+    fn line(&mut self, from: ScreenVertex, to: ScreenVertex) {
+        if from.w <= 0.0 || to.w <= 0.0 {
+            return;
+        }
+        let (dx, dy) = (to.x - from.x, to.y - from.y);
+        let steps = dx.abs().max(dy.abs()).round() as usize;
+        let mut attributes = vec![0.0; from.attributes.len()];
+        for step in 0..steps {
+            let t = step as f32 / steps as f32;
+            let (x, y) = (from.x + dx * t, from.y + dy * t);
+            if x < 0.0 || y < 0.0 {
+                continue;
+            }
+            for (i, attribute) in attributes.iter_mut().enumerate() {
+                *attribute = from.attributes[i] + (to.attributes[i] - from.attributes[i]) * t;
+            }
+            let depth = from.z + (to.z - from.z) * t;
+            self.fragment(x as u32, y as u32, depth, &attributes);
+        }
+    }
+
+    /// Square of `point_size` pixels around the vertex
+    fn point(&mut self, vertex: ScreenVertex) {
+        if vertex.w <= 0.0 {
+            return;
+        }
+        let half = self.point_size / 2.0;
+        let first = |center: f32| (center - half).round().max(0.0) as u32;
+        for y in first(vertex.y)..(vertex.y + half).round().max(0.0) as u32 {
+            for x in first(vertex.x)..(vertex.x + half).round().max(0.0) as u32 {
+                self.fragment(x, y, vertex.z, vertex.attributes);
+            }
+        }
+    }
+
+    /// Shades the pixel at `x`, `y` and writes it if it passes the depth test
+    fn fragment(&mut self, x: u32, y: u32, depth: f32, attributes: &[f32]) {
+        let framebuffer = &mut *self.framebuffer;
+        if x >= framebuffer.width || y >= framebuffer.height {
+            return;
+        }
+        let index = (y * framebuffer.width + x) as usize;
+        if self.state.depth_test {
+            if depth >= framebuffer.depth[index] || !(0.0..=1.0).contains(&depth) {
+                return;
+            }
+            framebuffer.depth[index] = depth;
+        }
+
+        let source = self.program.shader.shade(&Fragment {
+            position: [x as f32 + 0.5, y as f32 + 0.5],
+            depth,
+            attributes,
+            uniforms: &self.program.uniforms,
+        });
+        let destination = framebuffer.color[index];
+        framebuffer.color[index] = match self.state.blend.factors() {
+            None => quantize(source),
+            Some((src_rgb, dst_rgb, src_alpha, dst_alpha)) => {
+                let mut blended = [0.0; 4];
+                for channel in 0..4 {
+                    let (src_factor, dst_factor) = if channel < 3 {
+                        (src_rgb, dst_rgb)
+                    } else {
+                        (src_alpha, dst_alpha)
+                    };
+                    blended[channel] = source[channel]
+                        * blend_factor(src_factor, source, destination, channel)
+                        + destination[channel]
+                            * blend_factor(dst_factor, source, destination, channel);
+                }
+                quantize(blended)
+            }
+        };
+    }
+}
+
+/// Twice the signed area of the triangle `from`, `to`, `point`, positive if `point` is left
+/// of the edge
+fn edge(from: &ScreenVertex, to: &ScreenVertex, [x, y]: [f32; 2]) -> f32 {
+    (to.x - from.x) * (y - from.y) - (to.y - from.y) * (x - from.x)
+}
+
+/// Top and left edges of a counter-clockwise triangle, with y pointing up
+fn is_top_left(from: &ScreenVertex, to: &ScreenVertex) -> bool {
+    let (dx, dy) = (to.x - from.x, to.y - from.y);
+    dy < 0.0 || (dy == 0.0 && dx < 0.0)
+}
+
+/// Value of a `glBlendFuncSeparate` factor for one channel
+fn blend_factor(factor: u32, source: [f32; 4], destination: [f32; 4], channel: usize) -> f32 {
+    match factor {
+        gl::ZERO => 0.0,
+        gl::ONE => 1.0,
+        gl::SRC_ALPHA => source[3],
+        gl::ONE_MINUS_SRC_ALPHA => 1.0 - source[3],
+        gl::DST_COLOR => destination[channel],
+        _ => unreachable!("blend modes only use the factors above"),
+    }
+}
+
+/// Clamps and rounds to what an RGBA8 framebuffer stores
+fn quantize(color: [f32; 4]) -> [f32; 4] {
+    color.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() / 255.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::run::camera::Camera2D;
+    use crate::run::mesh::Mesh;
+    use crate::run::render_state::BlendMode;
+    use vatnar_linalg::Vector2;
+
+    /// Draws 2D `vertices` with a flat color using a new program
+    fn draw(
+        backend: &mut SoftwareBackend,
+        vertices: &[f32],
+        draw_mode: u32,
+        color: [f32; 3],
+    ) -> ShaderProgram {
+        let program = backend.create_program("", "").unwrap();
+        let mesh = Mesh::upload(
+            backend,
+            vertices,
+            &[VertexAttribute::POSITION_2D],
+            None,
+            draw_mode,
+            (color[0], color[1], color[2]).into(),
+        );
+        backend.use_program(&program);
+        backend.set_uniform(&program, "u_color", UniformValue::Vec3(color));
+        mesh.draw_with(backend);
+        program
+    }
+
+    /// `#` for pixels that aren't black, top row first
+    fn coverage(image: &RgbaImage) -> Vec<String> {
+        image
+            .rows()
+            .map(|row| {
+                row.map(|pixel| if pixel.0[..3] == [0, 0, 0] { '.' } else { '#' })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_triangle_golden() {
+        let mut backend = SoftwareBackend::new(8, 8);
+        draw(
+            &mut backend,
+            &[-1.0, -1.0, 1.0, -1.0, -1.0, 1.0],
+            gl::TRIANGLES,
+            [1.0, 0.0, 0.0],
+        );
+        // pixel centers on the diagonal lie on the right edge and aren't drawn
+        assert_eq!(
+            coverage(&backend.image()),
+            [
+                "........", "#.......", "##......", "###.....", "####....", "#####...", "######..",
+                "#######.",
+            ]
+        );
+        assert_eq!(backend.image().get_pixel(0, 7).0, [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_shared_edges_draw_once() {
+        let mut backend = SoftwareBackend::new(16, 16);
+        backend.apply_render_state(&RenderState::default().with_blend(BlendMode::Additive));
+        // a quad of two triangles at half intensity, overlapping pixels would be brighter
+        draw(
+            &mut backend,
+            &[
+                -1.0, -1.0, 1.0, -1.0, 1.0, 1.0, -1.0, -1.0, 1.0, 1.0, -1.0, 1.0,
+            ],
+            gl::TRIANGLES,
+            [0.5, 0.5, 0.5],
+        );
+        let image = backend.image();
+        assert!(image.pixels().all(|pixel| pixel.0 == [128, 128, 128, 255]));
+    }
+
+    #[test]
+    fn test_camera_golden() {
+        let (width, height) = (40, 20);
+        let mut camera = Camera2D::new(width as f64, height as f64);
+        camera.position = Vector2::new(0.5, 0.0);
+        camera.set_zoom(2.0);
+        let view: [f32; 9] = camera.view_projection().to_column_major_f32()[..]
+            .try_into()
+            .unwrap();
+
+        let mut backend = SoftwareBackend::new(width, height);
+        let program = backend.create_program("", "").unwrap();
+        backend.use_program(&program);
+        backend.set_uniform(&program, "u_view", UniformValue::Mat3(view));
+        backend.set_uniform(&program, "u_color", UniformValue::Vec3([0.0, 1.0, 0.0]));
+        // the world square from (0, 0) to (0.5, 0.5)
+        let square = [0.0, 0.0, 0.5, 0.0, 0.5, 0.5, 0.0, 0.5];
+        let mesh = Mesh::upload(
+            &mut backend,
+            &square,
+            &[VertexAttribute::POSITION_2D],
+            None,
+            gl::TRIANGLE_FAN,
+            (0.0, 1.0, 0.0).into(),
+        );
+        mesh.draw_with(&mut backend);
+
+        // where the camera puts the corners on screen
+        let top_left = camera.world_to_screen(Vector2::new(0.0, 0.5));
+        let bottom_right = camera.world_to_screen(Vector2::new(0.5, 0.0));
+        assert_eq!((top_left.x, top_left.y), (10.0, 0.0));
+        assert_eq!((bottom_right.x, bottom_right.y), (20.0, 10.0));
+        let image = backend.image();
+        for (x, y, pixel) in image.enumerate_pixels() {
+            let inside = (10..20).contains(&x) && (0..10).contains(&y);
+            assert_eq!(pixel.0[1] == 255, inside, "pixel {x}, {y}");
+        }
+    }
+
+    #[test]
+    fn test_depth_test() {
+        let mut backend = SoftwareBackend::new(4, 4);
+        backend.apply_render_state(&RenderState::OPAQUE_3D);
+        let program = backend.create_program("", "").unwrap();
+        backend.use_program(&program);
+        let layout = [VertexAttribute::POSITION_3D];
+        let quad = |z: f32| {
+            [
+                -1.0, -1.0, z, 1.0, -1.0, z, 1.0, 1.0, z, -1.0, -1.0, z, 1.0, 1.0, z, -1.0, 1.0, z,
+            ]
+        };
+        let near = Mesh::upload(
+            &mut backend,
+            &quad(-0.5),
+            &layout,
+            None,
+            gl::TRIANGLES,
+            (0.0, 0.0, 1.0).into(),
+        );
+        let far = Mesh::upload(
+            &mut backend,
+            &quad(0.5),
+            &layout,
+            None,
+            gl::TRIANGLES,
+            (1.0, 0.0, 0.0).into(),
+        );
+
+        backend.set_uniform(&program, "u_color", UniformValue::Vec3([0.0, 0.0, 1.0]));
+        near.draw_with(&mut backend);
+        backend.set_uniform(&program, "u_color", UniformValue::Vec3([1.0, 0.0, 0.0]));
+        far.draw_with(&mut backend);
+        assert!(
+            backend
+                .image()
+                .pixels()
+                .all(|pixel| pixel.0 == [0, 0, 255, 255])
+        );
+    }
+
+    #[test]
+    fn test_closure_shader_interpolates_attributes() {
+        let mut backend = SoftwareBackend::new(4, 1);
+        let program = backend.create_program("", "").unwrap();
+        // u from 0 on the left to 1 on the right
+        backend.set_fragment_shader(
+            &program,
+            FragmentShader::Closure(Box::new(|fragment| [fragment.attributes[2], 0.0, 0.0, 1.0])),
+        );
+        backend.use_program(&program);
+        let vertices = [-1.0, -1.0, 0.0, 3.0, -1.0, 2.0, -1.0, 3.0, 0.0];
+        let layout = [
+            VertexAttribute::POSITION_2D,
+            VertexAttribute {
+                location: 2,
+                components: 1,
+            },
+        ];
+        Mesh::upload(
+            &mut backend,
+            &vertices,
+            &layout,
+            None,
+            gl::TRIANGLES,
+            (1.0, 1.0, 1.0).into(),
+        )
+        .draw_with(&mut backend);
+        let reds: Vec<u8> = backend.image().pixels().map(|pixel| pixel.0[0]).collect();
+        // pixel centers at u = 1/8, 3/8, 5/8, 7/8
+        assert_eq!(reds, [32, 96, 159, 223]);
+    }
+
+    #[test]
+    fn test_lines_and_points() {
+        let mut backend = SoftwareBackend::new(8, 8);
+        draw(
+            &mut backend,
+            &[-1.0, -0.875, 1.0, -0.875],
+            gl::LINES,
+            [1.0, 1.0, 1.0],
+        );
+        backend.point_size = 2.0;
+        draw(&mut backend, &[0.0, 0.5], gl::POINTS, [1.0, 1.0, 1.0]);
+        assert_eq!(
+            coverage(&backend.image()),
+            [
+                "........", "...##...", "...##...", "........", "........", "........", "........",
+                "########",
+            ]
+        );
+    }
+}
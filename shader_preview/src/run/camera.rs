@@ -0,0 +1,301 @@
+//! 2D camera for the scene preview
+use serde::Deserialize;
+use vatnar_linalg::{Matrix, Vector2};
+
+/// Zoom multiplier for one unit of scrolling
+const ZOOM_STEP: f64 = 1.1;
+const MIN_ZOOM: f64 = 1e-4;
+const MAX_ZOOM: f64 = 1e6;
+
+/// Camera looking at the xy-plane
+///
+/// At zoom 1 the view spans from -1 to 1 world units vertically, horizontally it is
+/// widened or narrowed by the aspect ratio so the scene is never stretched.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Camera2D {
+    /// World position in the center of the view
+    pub position: Vector2<f64>,
+    zoom: f64,
+    /// Size of the viewport in pixels
+    viewport: Vector2<f64>,
+}
+
+impl Camera2D {
+    pub fn new(viewport_width: f64, viewport_height: f64) -> Self {
+        let mut camera = Camera2D {
+            position: Vector2::new(0.0, 0.0),
+            zoom: 1.0,
+            viewport: Vector2::new(1.0, 1.0),
+        };
+        camera.set_viewport(viewport_width, viewport_height);
+        camera
+    }
+
+    pub fn zoom(&self) -> f64 {
+        self.zoom
+    }
+
+    pub fn set_zoom(&mut self, zoom: f64) {
+        self.zoom = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+    }
+
+    pub fn viewport(&self) -> Vector2<f64> {
+        self.viewport
+    }
+
+    /// Changes the viewport size, sizes below one pixel are ignored
+    pub fn set_viewport(&mut self, width: f64, height: f64) {
+        self.viewport = Vector2::new(width.max(1.0), height.max(1.0));
+    }
+
+    pub fn aspect(&self) -> f64 {
+        self.viewport.x / self.viewport.y
+    }
+
+    /// Matrix from world coordinates to clip space, upload it as a `mat3`
+    pub fn view_projection(&self) -> Matrix {
+        Matrix::scaling_2d(Vector2::new(self.zoom / self.aspect(), self.zoom))
+            * Matrix::translation_2d(Vector2::new(-self.position.x, -self.position.y))
+    }
+
+    /// Inverse of [`Camera2D::view_projection`], from clip space to world coordinates
+    pub fn inverse_view_projection(&self) -> Matrix {
+        Matrix::translation_2d(self.position)
+            * Matrix::scaling_2d(Vector2::new(self.aspect() / self.zoom, 1.0 / self.zoom))
+    }
+
+    /// Converts a pixel position in the viewport, with the origin in the top left corner,
+    /// to world coordinates
+    pub fn screen_to_world(&self, screen: Vector2<f64>) -> Vector2<f64> {
+        let clip = Vector2::new(
+            screen.x / self.viewport.x * 2.0 - 1.0,
+            1.0 - screen.y / self.viewport.y * 2.0,
+        );
+        self.inverse_view_projection().transform_point2(clip)
+    }
+
+    /// Converts world coordinates to a pixel position in the viewport, the inverse of
+    /// [`Camera2D::screen_to_world`]
+    pub fn world_to_screen(&self, world: Vector2<f64>) -> Vector2<f64> {
+        let clip = self.view_projection().transform_point2(world);
+        Vector2::new(
+            (clip.x + 1.0) / 2.0 * self.viewport.x,
+            (1.0 - clip.y) / 2.0 * self.viewport.y,
+        )
+    }
+
+    /// Lower left and upper right corner of the visible area in world coordinates
+    pub fn visible_bounds(&self) -> (Vector2<f64>, Vector2<f64>) {
+        let min = self.screen_to_world(Vector2::new(0.0, self.viewport.y));
+        let max = self.screen_to_world(Vector2::new(self.viewport.x, 0.0));
+        (min, max)
+    }
+
+    /// Length of one world unit in pixels
+    pub fn pixels_per_unit(&self) -> f64 {
+        self.viewport.y / 2.0 * self.zoom
+    }
+
+    /// Zooms exponentially so the world point under `screen` stays in place
+    ///
+    /// # Arguments
+    /// * `screen` - pixel position to zoom about, usually the cursor
+    /// * `amount` - positive zooms in, negative zooms out, like scroll wheel offsets
+    pub fn zoom_at(&mut self, screen: Vector2<f64>, amount: f64) {
+        let before = self.screen_to_world(screen);
+        self.set_zoom(self.zoom * ZOOM_STEP.powf(amount));
+        let after = self.screen_to_world(screen);
+        self.position += Vector2::new(before.x - after.x, before.y - after.y);
+    }
+}
+
+/// Speeds of keyboard camera movement, the `[camera]` section of the config file
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MotionConfig {
+    /// World units per second at zoom 1, slower when zoomed in so the screen speed stays the same
+    pub pan_speed: f64,
+    /// Scroll steps per second while a zoom key is held
+    pub zoom_speed: f64,
+    /// Radians per second when orbiting a model
+    pub orbit_speed: f64,
+    /// How fast the velocity approaches full speed while a key is held, per second,
+    /// 0 reaches it instantly
+    pub acceleration: f64,
+    /// How fast the velocity decays after the keys are released, per second, 0 stops instantly
+    pub damping: f64,
+}
+
+impl Default for MotionConfig {
+    fn default() -> Self {
+        MotionConfig {
+            pan_speed: 1.0,
+            zoom_speed: 6.0,
+            orbit_speed: 1.5,
+            acceleration: 0.0,
+            damping: 0.0,
+        }
+    }
+}
+
+/// Frame rate independent velocities for keyboard panning and zooming
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CameraMotion {
+    pub config: MotionConfig,
+    pan_velocity: Vector2<f64>,
+    zoom_velocity: f64,
+}
+
+impl CameraMotion {
+    pub fn new(config: MotionConfig) -> Self {
+        CameraMotion {
+            config,
+            ..Default::default()
+        }
+    }
+
+    /// Advances the velocities by `dt` seconds and returns how far to move
+    ///
+    /// # Arguments
+    /// * `pan` - direction the pan keys point in, each axis -1 to 1
+    /// * `zoom` - -1 to 1 from the zoom keys
+    /// * `dt` - seconds since the last frame
+    ///
+    /// Returns the pan distance in world units at zoom 1 and the amount of zoom steps.
+    pub fn update(&mut self, pan: Vector2<f64>, zoom: f64, dt: f64) -> (Vector2<f64>, f64) {
+        let pan_target = pan.normalized() * self.config.pan_speed;
+        let rate = self.rate(pan_target.mag() > 0.0);
+        self.pan_velocity = Vector2::new(
+            approach(self.pan_velocity.x, pan_target.x, rate, dt),
+            approach(self.pan_velocity.y, pan_target.y, rate, dt),
+        );
+
+        let zoom_target = zoom * self.config.zoom_speed;
+        let rate = self.rate(zoom_target != 0.0);
+        self.zoom_velocity = approach(self.zoom_velocity, zoom_target, rate, dt);
+
+        (self.pan_velocity * dt, self.zoom_velocity * dt)
+    }
+
+    fn rate(&self, accelerating: bool) -> f64 {
+        if accelerating {
+            self.config.acceleration
+        } else {
+            self.config.damping
+        }
+    }
+}
+
+/// Exponentially moves `current` towards `target`, jumping there if `rate` isn't positive
+fn approach(current: f64, target: f64, rate: f64, dt: f64) -> f64 {
+    if rate <= 0.0 {
+        target
+    } else {
+        current + (target - current) * (1.0 - (-rate * dt).exp())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: Vector2<f64>, b: Vector2<f64>) -> bool {
+        (a.x - b.x).abs() < 1e-9 && (a.y - b.y).abs() < 1e-9
+    }
+
+    #[test]
+    fn test_aspect_ratio() {
+        let camera = Camera2D::new(800.0, 400.0);
+        let view = camera.view_projection();
+        // a square stays square on screen, the right edge of the view is 2 units away
+        let corner = view.transform_point2(Vector2::new(2.0, 1.0));
+        assert!(close(corner, Vector2::new(1.0, 1.0)));
+        assert!(close(
+            camera.screen_to_world(Vector2::new(800.0, 0.0)),
+            Vector2::new(2.0, 1.0)
+        ));
+    }
+
+    #[test]
+    fn test_world_to_screen() {
+        let mut camera = Camera2D::new(800.0, 600.0);
+        camera.position = Vector2::new(1.0, 2.0);
+        camera.set_zoom(0.5);
+        let screen = Vector2::new(123.0, 456.0);
+        assert!(close(
+            camera.world_to_screen(camera.screen_to_world(screen)),
+            screen
+        ));
+
+        let (min, max) = camera.visible_bounds();
+        assert!(close(max - min, Vector2::new(16.0 / 3.0, 4.0)));
+        assert_eq!(camera.pixels_per_unit(), 150.0);
+    }
+
+    #[test]
+    fn test_inverse() {
+        let mut camera = Camera2D::new(640.0, 480.0);
+        camera.position = Vector2::new(3.0, -2.0);
+        camera.set_zoom(7.5);
+        let product = &camera.view_projection() * &camera.inverse_view_projection();
+        for row in 0..3 {
+            for col in 0..3 {
+                let expected = if row == col { 1.0 } else { 0.0 };
+                assert!((product[(row, col)] - expected).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn test_zoom_about_cursor() {
+        let mut camera = Camera2D::new(800.0, 600.0);
+        let cursor = Vector2::new(650.0, 120.0);
+        let before = camera.screen_to_world(cursor);
+
+        camera.zoom_at(cursor, 3.0);
+        assert!((camera.zoom() - ZOOM_STEP.powi(3)).abs() < 1e-9);
+        assert!(close(camera.screen_to_world(cursor), before));
+
+        // zooming back out by the same amount returns to the start
+        camera.zoom_at(cursor, -3.0);
+        assert!(close(camera.position, Vector2::new(0.0, 0.0)));
+    }
+
+    #[test]
+    fn test_motion_frame_rate_independent() {
+        let right = Vector2::new(1.0, 0.0);
+        let distance = |frames: usize, config: MotionConfig| {
+            let mut motion = CameraMotion::new(config);
+            let dt = 1.0 / frames as f64;
+            (0..frames)
+                .map(|_| motion.update(right, 0.0, dt).0.x)
+                .sum::<f64>()
+        };
+
+        assert!((distance(30, MotionConfig::default()) - 1.0).abs() < 1e-9);
+        assert!((distance(144, MotionConfig::default()) - 1.0).abs() < 1e-9);
+
+        // accelerating takes time, but converges to the same result at any frame rate
+        let smooth = MotionConfig {
+            acceleration: 5.0,
+            ..Default::default()
+        };
+        let slow = distance(60, smooth.clone());
+        assert!(slow < 1.0);
+        assert!((slow - distance(240, smooth)).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_motion_damping() {
+        let mut motion = CameraMotion::new(MotionConfig {
+            damping: 4.0,
+            ..Default::default()
+        });
+        motion.update(Vector2::new(0.0, 1.0), 1.0, 0.1);
+
+        // keeps gliding after the keys are released
+        let (pan, zoom) = motion.update(Vector2::new(0.0, 0.0), 0.0, 0.1);
+        assert!(pan.y > 0.0 && pan.y < 0.1);
+        assert!(zoom > 0.0);
+    }
+}
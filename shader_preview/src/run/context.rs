@@ -0,0 +1,256 @@
+//! Settings for the OpenGL context the window is created with, and what the context supports
+use glfw::{ClientApiHint, Glfw, OpenGlProfileHint, WindowHint};
+use serde::{Deserialize, Deserializer};
+use std::ffi::CStr;
+use std::fmt;
+use std::str::FromStr;
+
+/// An OpenGL or OpenGL ES version, written as `"3.3"`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct GlVersion {
+    pub major: u32,
+    pub minor: u32,
+}
+
+impl FromStr for GlVersion {
+    type Err = String;
+
+    fn from_str(version: &str) -> Result<Self, Self::Err> {
+        let error = || format!("Invalid GL version '{version}', expected e.g. '3.3'");
+        let (major, minor) = version.split_once('.').ok_or_else(error)?;
+        Ok(GlVersion {
+            major: major.parse().map_err(|_| error())?,
+            minor: minor.parse().map_err(|_| error())?,
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for GlVersion {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let version = String::deserialize(deserializer)?;
+        version.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl fmt::Display for GlVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// Which flavor of OpenGL to request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GlProfile {
+    /// Whatever the driver gives by default
+    #[default]
+    Any,
+    /// Desktop core profile, forward compatible
+    Core,
+    /// Desktop compatibility profile
+    Compat,
+    /// OpenGL ES, shaders written for `#version 330` are translated to `#version 300 es`
+    Es,
+}
+
+impl FromStr for GlProfile {
+    type Err = String;
+
+    fn from_str(profile: &str) -> Result<Self, Self::Err> {
+        match profile {
+            "any" => Ok(GlProfile::Any),
+            "core" => Ok(GlProfile::Core),
+            "compat" => Ok(GlProfile::Compat),
+            "es" => Ok(GlProfile::Es),
+            _ => Err(format!(
+                "Unknown GL profile '{profile}', expected any, core, compat or es"
+            )),
+        }
+    }
+}
+
+/// The `[gl]` section of the config file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GlConfig {
+    /// Context version to request, 3.3 for the core and compatibility profiles and 3.0 for
+    /// OpenGL ES if not set, otherwise the driver default
+    pub version: Option<GlVersion>,
+    pub profile: GlProfile,
+    /// Requests a debug context, so the driver reports errors and warnings through KHR_debug
+    pub debug: bool,
+    /// Stops with an error on the first high severity GL message or `glGetError` error
+    pub fatal_errors: bool,
+}
+
+impl GlConfig {
+    /// Sets the window hints for the context, call before creating the window
+    pub fn apply_hints(&self, glfw: &mut Glfw) {
+        if let Some(GlVersion { major, minor }) = self.requested_version() {
+            glfw.window_hint(WindowHint::ContextVersion(major, minor));
+        }
+        match self.profile {
+            GlProfile::Any => {}
+            GlProfile::Core => {
+                glfw.window_hint(WindowHint::OpenGlProfile(OpenGlProfileHint::Core));
+                glfw.window_hint(WindowHint::OpenGlForwardCompat(true));
+            }
+            GlProfile::Compat => {
+                glfw.window_hint(WindowHint::OpenGlProfile(OpenGlProfileHint::Compat));
+            }
+            GlProfile::Es => glfw.window_hint(WindowHint::ClientApi(ClientApiHint::OpenGlEs)),
+        }
+        glfw.window_hint(WindowHint::OpenGlDebugContext(self.debug));
+    }
+
+    /// The configured version, defaulting to what the shaders need: GLSL 3.30 or GLSL ES
+    /// 3.00. Profiles only exist from OpenGL 3.2, so GLFW rejects them without a version.
+    fn requested_version(&self) -> Option<GlVersion> {
+        match (self.version, self.profile) {
+            (None, GlProfile::Es) => Some(GlVersion { major: 3, minor: 0 }),
+            (None, GlProfile::Core | GlProfile::Compat) => Some(GlVersion { major: 3, minor: 3 }),
+            (version, _) => version,
+        }
+    }
+
+    /// Describes the requested context for error messages
+    pub fn describe(&self) -> String {
+        let version = self
+            .requested_version()
+            .map(|v| format!(" {v}"))
+            .unwrap_or_default();
+        match self.profile {
+            GlProfile::Any => format!("OpenGL{version}"),
+            GlProfile::Core => format!("OpenGL{version} core profile"),
+            GlProfile::Compat => format!("OpenGL{version} compatibility profile"),
+            GlProfile::Es => format!("OpenGL ES{version}"),
+        }
+    }
+}
+
+/// What the current context is and supports, queried once after creating it
+#[derive(Debug, Clone)]
+pub struct Capabilities {
+    pub vendor: String,
+    pub renderer: String,
+    pub version: String,
+    pub glsl_version: String,
+    /// An OpenGL ES context
+    pub es: bool,
+    pub extensions: Vec<String>,
+    pub max_texture_size: i32,
+    pub max_samples: i32,
+    pub max_texture_image_units: i32,
+    pub max_renderbuffer_size: i32,
+    pub max_vertex_attribs: i32,
+}
+
+impl Capabilities {
+    /// Queries the current context, requires loaded GL functions
+    pub fn query() -> Self {
+        let version = gl_string(gl::VERSION);
+        let mut extension_count = 0;
+        unsafe {
+            gl::GetIntegerv(gl::NUM_EXTENSIONS, &mut extension_count);
+        }
+        let extensions = (0..extension_count.max(0) as u32)
+            .map(|i| unsafe { c_string(gl::GetStringi(gl::EXTENSIONS, i)) })
+            .collect();
+        Capabilities {
+            vendor: gl_string(gl::VENDOR),
+            renderer: gl_string(gl::RENDERER),
+            es: version.starts_with("OpenGL ES"),
+            version,
+            glsl_version: gl_string(gl::SHADING_LANGUAGE_VERSION),
+            extensions,
+            max_texture_size: get_integer(gl::MAX_TEXTURE_SIZE),
+            max_samples: get_integer(gl::MAX_SAMPLES),
+            max_texture_image_units: get_integer(gl::MAX_TEXTURE_IMAGE_UNITS),
+            max_renderbuffer_size: get_integer(gl::MAX_RENDERBUFFER_SIZE),
+            max_vertex_attribs: get_integer(gl::MAX_VERTEX_ATTRIBS),
+        }
+    }
+
+    pub fn has_extension(&self, name: &str) -> bool {
+        self.extensions.iter().any(|extension| extension == name)
+    }
+
+    /// Logs the context at info level and the extensions at debug level
+    pub fn log(&self) {
+        log::info!(
+            "{} on {} ({}), GLSL {}",
+            self.version,
+            self.renderer,
+            self.vendor,
+            self.glsl_version
+        );
+        log::info!(
+            "Max texture size {}, max samples {}, {} texture units, max renderbuffer size {}, {} vertex attributes",
+            self.max_texture_size,
+            self.max_samples,
+            self.max_texture_image_units,
+            self.max_renderbuffer_size,
+            self.max_vertex_attribs
+        );
+        log::debug!(
+            "{} extensions: {}",
+            self.extensions.len(),
+            self.extensions.join(" ")
+        );
+    }
+}
+
+fn gl_string(name: u32) -> String {
+    unsafe { c_string(gl::GetString(name)) }
+}
+
+/// Copies a string returned by GL, empty for null
+unsafe fn c_string(string: *const u8) -> String {
+    if string.is_null() {
+        return String::new();
+    }
+    unsafe { CStr::from_ptr(string.cast()) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn get_integer(name: u32) -> i32 {
+    let mut value = 0;
+    unsafe {
+        gl::GetIntegerv(name, &mut value);
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_gl_config() {
+        let config: GlConfig = toml::from_str("version = \"3.0\"\nprofile = \"es\"").unwrap();
+        assert_eq!(config.version, Some(GlVersion { major: 3, minor: 0 }));
+        assert_eq!(config.profile, GlProfile::Es);
+        assert_eq!(config.describe(), "OpenGL ES 3.0");
+
+        assert!("3".parse::<GlVersion>().is_err());
+        assert!("es3.0".parse::<GlVersion>().is_err());
+        assert!(toml::from_str::<GlConfig>("profile = \"legacy\"").is_err());
+
+        let core = GlConfig {
+            version: Some(GlVersion { major: 4, minor: 1 }),
+            profile: GlProfile::Core,
+            ..Default::default()
+        };
+        assert_eq!(core.describe(), "OpenGL 4.1 core profile");
+
+        // profiles need a version
+        let compat: GlConfig = toml::from_str("profile = \"compat\"").unwrap();
+        assert_eq!(
+            compat.requested_version(),
+            Some(GlVersion { major: 3, minor: 3 })
+        );
+        assert_eq!(compat.describe(), "OpenGL 3.3 compatibility profile");
+        assert_eq!(GlConfig::default().requested_version(), None);
+    }
+}
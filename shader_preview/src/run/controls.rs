@@ -0,0 +1,494 @@
+//! Panel of widgets setting uniforms, drawn with the overlay in the top left corner
+//!
+//! Clicking a checkbox toggles it, a choice steps to its next value and a button is true
+//! for the frame it was clicked in. Sliders follow the cursor while the mouse button is
+//! held, colors have one slider per channel and points one per axis.
+use crate::run::material::{UniformValue, UniformValues};
+use crate::run::mouse::Mouse;
+use crate::run::overlay::{BACKGROUND_COLOR, Overlay, Rgba, TEXT_COLOR};
+
+const WIDTH: f32 = 180.0;
+const MARGIN: f32 = 8.0;
+const PADDING: f32 = 4.0;
+const BAR_HEIGHT: f32 = 6.0;
+
+const TRACK_COLOR: Rgba = [1.0, 1.0, 1.0, 0.15];
+const FILL_COLOR: Rgba = [0.3, 0.7, 1.0, 0.9];
+const ACTIVE_COLOR: Rgba = [1.0, 0.6, 0.2, 0.9];
+const CHANNEL_COLORS: [Rgba; 4] = [
+    [1.0, 0.3, 0.3, 0.9],
+    [0.3, 1.0, 0.3, 0.9],
+    [0.4, 0.5, 1.0, 0.9],
+    [0.8, 0.8, 0.8, 0.9],
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Widget {
+    /// A `float` between `min` and `max`
+    Slider { value: f32, min: f32, max: f32 },
+    /// An `int` of 0 or 1
+    Checkbox(bool),
+    /// An `int` out of `values`, shown as `labels`
+    Choice {
+        index: usize,
+        values: Vec<i32>,
+        labels: Vec<String>,
+    },
+    /// An `int` that is 1 in the frame the button was clicked
+    Button { clicked: bool },
+    /// A `vec4` RGBA color
+    Color([f32; 4]),
+    /// A `vec2` within `range`, or within the viewport in pixels
+    Point {
+        value: [f32; 2],
+        range: Option<([f32; 2], [f32; 2])>,
+    },
+}
+
+impl Widget {
+    fn bars(&self) -> usize {
+        match self {
+            Widget::Slider { .. } => 1,
+            Widget::Point { .. } => 2,
+            Widget::Color(_) => 4,
+            _ => 0,
+        }
+    }
+
+    /// Range of a slider bar
+    fn range(&self, bar: usize, viewport: [f32; 2]) -> (f32, f32) {
+        match self {
+            Widget::Slider { min, max, .. } => (*min, *max),
+            Widget::Point {
+                range: Some((min, max)),
+                ..
+            } => (min[bar], max[bar]),
+            Widget::Point { range: None, .. } => (0.0, viewport[bar]),
+            _ => (0.0, 1.0),
+        }
+    }
+
+    fn bar_value(&self, bar: usize) -> Option<f32> {
+        match self {
+            Widget::Slider { value, .. } => Some(*value),
+            Widget::Point { value, .. } => value.get(bar).copied(),
+            Widget::Color(color) => color.get(bar).copied(),
+            _ => None,
+        }
+    }
+
+    fn bar_value_mut(&mut self, bar: usize) -> Option<&mut f32> {
+        match self {
+            Widget::Slider { value, .. } => Some(value),
+            Widget::Point { value, .. } => value.get_mut(bar),
+            Widget::Color(color) => color.get_mut(bar),
+            _ => None,
+        }
+    }
+
+    /// How far along its range a slider bar is, from 0 to 1
+    fn fraction(&self, bar: usize, viewport: [f32; 2]) -> f32 {
+        let (min, max) = self.range(bar, viewport);
+        let value = self.bar_value(bar).unwrap_or(min);
+        if max > min {
+            ((value - min) / (max - min)).clamp(0.0, 1.0)
+        } else {
+            0.0
+        }
+    }
+
+    fn set_fraction(&mut self, bar: usize, fraction: f32, viewport: [f32; 2]) {
+        let (min, max) = self.range(bar, viewport);
+        if let Some(value) = self.bar_value_mut(bar) {
+            *value = min + fraction.clamp(0.0, 1.0) * (max - min);
+        }
+    }
+
+    fn click(&mut self) {
+        match self {
+            Widget::Checkbox(checked) => *checked = !*checked,
+            Widget::Choice { index, values, .. } => *index = (*index + 1) % values.len().max(1),
+            Widget::Button { clicked } => *clicked = true,
+            _ => {}
+        }
+    }
+
+    pub fn uniform(&self) -> UniformValue {
+        match self {
+            Widget::Slider { value, .. } => UniformValue::Float(*value),
+            Widget::Checkbox(checked) => UniformValue::Int(*checked as i32),
+            Widget::Choice { index, values, .. } => {
+                UniformValue::Int(values.get(*index).copied().unwrap_or(0))
+            }
+            Widget::Button { clicked } => UniformValue::Int(*clicked as i32),
+            Widget::Color(color) => UniformValue::Vec4(*color),
+            Widget::Point { value, .. } => UniformValue::Vec2(*value),
+        }
+    }
+
+    fn text(&self, label: &str) -> String {
+        match self {
+            Widget::Slider { value, .. } => format!("{label} {value:.2}"),
+            Widget::Checkbox(true) => format!("[x] {label}"),
+            Widget::Checkbox(false) => format!("[ ] {label}"),
+            Widget::Choice { index, labels, .. } => {
+                format!("{label}: {}", labels.get(*index).map_or("", String::as_str))
+            }
+            Widget::Button { .. } => format!("> {label}"),
+            Widget::Color([r, g, b, a]) => format!("{label} {r:.2} {g:.2} {b:.2} {a:.2}"),
+            Widget::Point { value: [x, y], .. } => format!("{label} {x:.2}, {y:.2}"),
+        }
+    }
+}
+
+/// A named uniform and the widget setting it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Control {
+    pub name: String,
+    pub label: String,
+    pub widget: Widget,
+}
+
+/// Rectangle as `[x, y, width, height]` in pixels
+type Rect = [f32; 4];
+
+fn contains([x, y, width, height]: Rect, point: [f32; 2]) -> bool {
+    (x..x + width).contains(&point[0]) && (y..y + height).contains(&point[1])
+}
+
+/// Where a control is drawn
+struct Placement {
+    text: [f32; 2],
+    /// The slider bars, or the line of the text for widgets that are clicked
+    targets: Vec<Rect>,
+}
+
+/// Sizes of the overlay text, in pixels
+#[derive(Debug, Clone, Copy)]
+struct Metrics {
+    line_height: f32,
+    scale: f32,
+}
+
+impl Metrics {
+    fn of(overlay: &Overlay) -> Self {
+        Metrics {
+            line_height: overlay.line_height(),
+            scale: overlay.scale,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ControlPanel {
+    pub controls: Vec<Control>,
+    pub visible: bool,
+    /// Control and bar of the slider being dragged
+    active: Option<(usize, usize)>,
+    /// Mouse button state of the last update, to find presses
+    was_pressed: bool,
+}
+
+impl ControlPanel {
+    pub fn new(controls: Vec<Control>) -> Self {
+        ControlPanel {
+            controls,
+            visible: true,
+            ..Default::default()
+        }
+    }
+
+    /// Values of the controls by uniform name
+    pub fn uniforms(&self) -> UniformValues {
+        let mut uniforms = UniformValues::default();
+        for control in &self.controls {
+            uniforms.set(&control.name, control.widget.uniform());
+        }
+        uniforms
+    }
+
+    /// Takes over the values of controls with the same name and kind from `previous`, e.g.
+    /// after reloading the shader
+    pub fn restore(&mut self, previous: &ControlPanel) {
+        for control in &mut self.controls {
+            let Some(old) = previous.controls.iter().find(|c| c.name == control.name) else {
+                continue;
+            };
+            match (&mut control.widget, &old.widget) {
+                (Widget::Slider { value, min, max }, Widget::Slider { value: old, .. }) => {
+                    *value = old.max(*min).min(*max)
+                }
+                (Widget::Checkbox(checked), Widget::Checkbox(old)) => *checked = *old,
+                (
+                    Widget::Choice { index, values, .. },
+                    Widget::Choice {
+                        index: old_index,
+                        values: old_values,
+                        ..
+                    },
+                ) => {
+                    let old = old_values.get(*old_index);
+                    if let Some(i) = values.iter().position(|value| Some(value) == old) {
+                        *index = i;
+                    }
+                }
+                (Widget::Color(color), Widget::Color(old)) => *color = *old,
+                (Widget::Point { value, .. }, Widget::Point { value: old, .. }) => *value = *old,
+                _ => {}
+            }
+        }
+        self.visible = previous.visible;
+    }
+
+    /// Handles clicks and drags on the widgets, `viewport` is the size of the framebuffer
+    pub fn update(&mut self, mouse: &Mouse, overlay: &Overlay, viewport: [f32; 2]) {
+        self.update_with(mouse, Metrics::of(overlay), viewport)
+    }
+
+    fn update_with(&mut self, mouse: &Mouse, metrics: Metrics, viewport: [f32; 2]) {
+        let pressed = mouse.pressed && !self.was_pressed;
+        self.was_pressed = mouse.pressed;
+        if !mouse.pressed || !self.visible {
+            self.active = None;
+            return;
+        }
+        let cursor = [mouse.position.x as f32, mouse.position.y as f32];
+        let placements = self.layout(metrics);
+        if pressed {
+            let hit = placements
+                .iter()
+                .enumerate()
+                .find_map(|(index, placement)| {
+                    let target = placement
+                        .targets
+                        .iter()
+                        .position(|&rect| contains(rect, cursor))?;
+                    Some((index, target))
+                });
+            match hit {
+                Some((index, _)) if self.controls[index].widget.bars() == 0 => {
+                    self.controls[index].widget.click();
+                    return;
+                }
+                hit => self.active = hit,
+            }
+        }
+        let Some((index, bar)) = self.active else {
+            return;
+        };
+        let [x, _, width, _] = placements[index].targets[bar];
+        let fraction = (cursor[0] - x) / width;
+        self.controls[index]
+            .widget
+            .set_fraction(bar, fraction, viewport);
+    }
+
+    /// Resets the buttons clicked this frame
+    pub fn end_frame(&mut self) {
+        for control in &mut self.controls {
+            if let Widget::Button { clicked } = &mut control.widget {
+                *clicked = false;
+            }
+        }
+    }
+
+    fn layout(&self, metrics: Metrics) -> Vec<Placement> {
+        let Metrics { line_height, scale } = metrics;
+        let (left, width, padding) = (MARGIN * scale, WIDTH * scale, PADDING * scale);
+        let bar_height = BAR_HEIGHT * scale;
+        let mut y = MARGIN * scale + padding;
+        let mut placements = Vec::new();
+        for control in &self.controls {
+            let text = [left + padding, y];
+            let line = [left, y, width, line_height];
+            y += line_height + padding;
+            let targets = match control.widget.bars() {
+                0 => vec![line],
+                bars => (0..bars)
+                    .map(|_| {
+                        let bar = [left + padding, y, width - 2.0 * padding, bar_height];
+                        y += bar_height + padding;
+                        bar
+                    })
+                    .collect(),
+            };
+            placements.push(Placement { text, targets });
+        }
+        placements
+    }
+
+    /// Adds the panel to `overlay`
+    pub fn draw(&self, overlay: &mut Overlay, viewport: [f32; 2]) {
+        if !self.visible || self.controls.is_empty() {
+            return;
+        }
+        let metrics = Metrics::of(overlay);
+        let placements = self.layout(metrics);
+        let margin = MARGIN * metrics.scale;
+        let bottom = placements
+            .iter()
+            .flat_map(|placement| &placement.targets)
+            .map(|[_, y, _, height]| y + height)
+            .fold(0.0, f32::max);
+        let height = bottom + PADDING * metrics.scale - margin;
+        overlay.rect(
+            margin,
+            margin,
+            WIDTH * metrics.scale,
+            height,
+            BACKGROUND_COLOR,
+        );
+
+        for (index, (control, placement)) in self.controls.iter().zip(&placements).enumerate() {
+            let [x, y] = placement.text;
+            overlay.text(x, y, &control.widget.text(&control.label), TEXT_COLOR);
+            if control.widget.bars() == 0 {
+                continue;
+            }
+            for (bar, &[x, y, width, height]) in placement.targets.iter().enumerate() {
+                let fill = match control.widget {
+                    _ if self.active == Some((index, bar)) => ACTIVE_COLOR,
+                    Widget::Color(_) => CHANNEL_COLORS[bar],
+                    _ => FILL_COLOR,
+                };
+                let fraction = control.widget.fraction(bar, viewport);
+                overlay.rect(x, y, width, height, TRACK_COLOR);
+                overlay.rect(x, y, width * fraction, height, fill);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vatnar_linalg::Vector2;
+
+    const METRICS: Metrics = Metrics {
+        line_height: 10.0,
+        scale: 1.0,
+    };
+    const VIEWPORT: [f32; 2] = [400.0, 300.0];
+
+    fn panel() -> ControlPanel {
+        let control = |name: &str, widget| Control {
+            name: name.to_string(),
+            label: name.to_string(),
+            widget,
+        };
+        ControlPanel::new(vec![
+            control(
+                "amount",
+                Widget::Slider {
+                    value: 0.5,
+                    min: 0.0,
+                    max: 2.0,
+                },
+            ),
+            control("invert", Widget::Checkbox(false)),
+            control("reset", Widget::Button { clicked: false }),
+            control(
+                "center",
+                Widget::Point {
+                    value: [0.0, 0.0],
+                    range: None,
+                },
+            ),
+        ])
+    }
+
+    fn click(panel: &mut ControlPanel, x: f64, y: f64) {
+        let mut mouse = Mouse::default();
+        mouse.move_to(Vector2::new(x, y));
+        mouse.press();
+        panel.update_with(&mouse, METRICS, VIEWPORT);
+        mouse.release();
+        panel.update_with(&mouse, METRICS, VIEWPORT);
+    }
+
+    #[test]
+    fn test_click_widgets() {
+        let mut panel = panel();
+        let layout = panel.layout(METRICS);
+        let [x, y, width, height] = layout[0].targets[0];
+
+        // the slider jumps to the click and follows the drag
+        click(
+            &mut panel,
+            (x + width * 0.25) as f64,
+            (y + height / 2.0) as f64,
+        );
+        assert_eq!(
+            panel.uniforms().get("amount"),
+            Some(UniformValue::Float(0.5))
+        );
+        let mut mouse = Mouse::default();
+        mouse.move_to(Vector2::new(x as f64, (y + 1.0) as f64));
+        mouse.press();
+        panel.update_with(&mouse, METRICS, VIEWPORT);
+        mouse.move_to(Vector2::new((x + width * 2.0) as f64, 0.0));
+        panel.update_with(&mouse, METRICS, VIEWPORT);
+        assert_eq!(
+            panel.uniforms().get("amount"),
+            Some(UniformValue::Float(2.0))
+        );
+        mouse.release();
+        panel.update_with(&mouse, METRICS, VIEWPORT);
+
+        let [x, y, ..] = layout[1].targets[0];
+        click(&mut panel, (x + 1.0) as f64, (y + 1.0) as f64);
+        assert_eq!(panel.uniforms().get("invert"), Some(UniformValue::Int(1)));
+
+        // buttons are only clicked for one frame
+        let [x, y, ..] = layout[2].targets[0];
+        click(&mut panel, (x + 1.0) as f64, (y + 1.0) as f64);
+        assert_eq!(panel.uniforms().get("reset"), Some(UniformValue::Int(1)));
+        panel.end_frame();
+        assert_eq!(panel.uniforms().get("reset"), Some(UniformValue::Int(0)));
+
+        // points without a range are in viewport pixels
+        let [x, y, width, _] = layout[3].targets[1];
+        click(&mut panel, (x + width) as f64 - 0.01, (y + 1.0) as f64);
+        let Some(UniformValue::Vec2([_, center_y])) = panel.uniforms().get("center") else {
+            panic!("center is a vec2");
+        };
+        assert!((center_y - 300.0).abs() < 0.1);
+
+        // clicks outside don't hit anything and hidden panels ignore the mouse
+        let before = panel.uniforms();
+        click(&mut panel, 390.0, 290.0);
+        panel.visible = false;
+        let [x, y, ..] = layout[1].targets[0];
+        click(&mut panel, (x + 1.0) as f64, (y + 1.0) as f64);
+        assert_eq!(panel.uniforms(), before);
+    }
+
+    #[test]
+    fn test_restore() {
+        let mut previous = panel();
+        previous.controls[0].widget = Widget::Slider {
+            value: 1.5,
+            min: 0.0,
+            max: 2.0,
+        };
+        previous.controls[1].widget = Widget::Checkbox(true);
+
+        let mut reloaded = panel();
+        reloaded.controls[0].widget = Widget::Slider {
+            value: 0.0,
+            min: 0.0,
+            max: 1.0,
+        };
+        // a control that changed its kind keeps its default
+        reloaded.controls[1].widget = Widget::Color([1.0; 4]);
+        reloaded.restore(&previous);
+        assert_eq!(
+            reloaded.uniforms().get("amount"),
+            Some(UniformValue::Float(1.0))
+        );
+        assert_eq!(
+            reloaded.uniforms().get("invert"),
+            Some(UniformValue::Vec4([1.0; 4]))
+        );
+    }
+}
@@ -0,0 +1,133 @@
+//! Pieces shared by everything drawing fullscreen shader passes, like post-processing,
+//! headless rendering and the Shadertoy and ISF views
+use crate::run::render_target::RenderTarget;
+use crate::run::texture::TextureFormat;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Draws a triangle covering the viewport with `vertex_shader_post.glsl`
+pub struct FullscreenTriangle {
+    /// Empty vertex array, core profiles refuse to draw without one bound
+    vao: u32,
+}
+
+impl FullscreenTriangle {
+    /// Requires a current GL context
+    pub fn new() -> Self {
+        let mut vao = 0;
+        unsafe {
+            gl::GenVertexArrays(1, &mut vao);
+        }
+        FullscreenTriangle { vao }
+    }
+
+    /// Draws with the program in use
+    pub fn draw(&self) {
+        unsafe {
+            gl::BindVertexArray(self.vao);
+            gl::DrawArrays(gl::TRIANGLES, 0, 3);
+        }
+    }
+}
+
+impl Drop for FullscreenTriangle {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteVertexArrays(1, &self.vao);
+        }
+    }
+}
+
+/// Two targets drawn into in turns, so a pass can read what it drew the frame before
+pub struct DoubleTarget {
+    targets: [RenderTarget; 2],
+    /// Index of the front target
+    front: usize,
+}
+
+impl DoubleTarget {
+    /// Creates both targets cleared to transparent black
+    pub fn new(width: u32, height: u32, format: TextureFormat) -> Result<Self, String> {
+        let targets = [
+            RenderTarget::new(width, height, format)?,
+            RenderTarget::new(width, height, format)?,
+        ];
+        let target = DoubleTarget { targets, front: 0 };
+        target.clear();
+        Ok(target)
+    }
+
+    /// Clears both targets to transparent black
+    pub fn clear(&self) {
+        for target in &self.targets {
+            target.bind();
+            unsafe {
+                gl::ClearColor(0.0, 0.0, 0.0, 0.0);
+                gl::Clear(gl::COLOR_BUFFER_BIT);
+            }
+        }
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        self.targets[0].size()
+    }
+
+    /// The target with the last finished frame
+    pub fn front(&self) -> &RenderTarget {
+        &self.targets[self.front]
+    }
+
+    /// The target to draw the next frame into
+    pub fn back(&self) -> &RenderTarget {
+        &self.targets[1 - self.front]
+    }
+
+    /// Makes the back target the front one, after drawing into it
+    pub fn swap(&mut self) {
+        self.front = 1 - self.front;
+    }
+}
+
+/// The date in UTC as year, month from 1, day of the month and seconds since midnight
+pub fn utc_date(now: SystemTime) -> (i64, u32, u32, f64) {
+    let seconds = now
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |since| since.as_secs_f64());
+    let days = (seconds / 86400.0).floor() as i64;
+    // days since 1970 to the civil calendar, after Howard Hinnant's `civil_from_days`
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (
+        year,
+        month as u32,
+        day as u32,
+        seconds - days as f64 * 86400.0,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_utc_date() {
+        let at = |seconds| utc_date(UNIX_EPOCH + Duration::from_secs(seconds));
+        assert_eq!(at(0), (1970, 1, 1, 0.0));
+        // 2024-02-29 12:00:00
+        assert_eq!(at(1_709_208_000), (2024, 2, 29, 43200.0));
+        // 2023-12-31 23:59:59
+        assert_eq!(at(1_704_067_199), (2023, 12, 31, 86399.0));
+    }
+}
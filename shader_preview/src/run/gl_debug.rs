@@ -0,0 +1,201 @@
+//! Reports GL errors through the `log` crate
+//!
+//! With KHR_debug and a debug context the driver calls back with every message, tagged with
+//! its source, type and severity. Otherwise `glGetError` is polled once per frame, which only
+//! tells that something failed, not where.
+use std::ffi::{CStr, c_char, c_void};
+use std::sync::Mutex;
+
+/// What the callback shares with [`GlDebug`]
+struct DebugState {
+    fatal_errors: bool,
+    /// First error that should stop the preview, until it's reported by a check
+    fatal: Mutex<Option<String>>,
+}
+
+impl DebugState {
+    fn report_fatal(&self, message: String) {
+        if self.fatal_errors {
+            let mut fatal = self.fatal.lock().unwrap_or_else(|e| e.into_inner());
+            fatal.get_or_insert(message);
+        }
+    }
+
+    /// The first fatal error since the last call
+    fn take_fatal(&self) -> Option<String> {
+        let mut fatal = self.fatal.lock().unwrap_or_else(|e| e.into_inner());
+        fatal.take()
+    }
+}
+
+pub struct GlDebug {
+    /// Boxed so the callback's pointer to it stays valid when `GlDebug` moves
+    state: Box<DebugState>,
+    callback: bool,
+}
+
+impl GlDebug {
+    /// Installs the debug callback if the context supports it, requires a current GL context
+    ///
+    /// # Arguments
+    /// * `fatal_errors` - makes [`GlDebug::check`] fail after a high severity message
+    pub fn install(fatal_errors: bool) -> Self {
+        let state = Box::new(DebugState {
+            fatal_errors,
+            fatal: Mutex::new(None),
+        });
+
+        let mut flags = 0;
+        unsafe {
+            gl::GetIntegerv(gl::CONTEXT_FLAGS, &mut flags);
+        }
+        let is_debug_context = flags as u32 & gl::CONTEXT_FLAG_DEBUG_BIT != 0;
+        let callback = is_debug_context && gl::DebugMessageCallback::is_loaded();
+        if callback {
+            unsafe {
+                gl::Enable(gl::DEBUG_OUTPUT);
+                // report on the thread and inside the call that caused the message
+                gl::Enable(gl::DEBUG_OUTPUT_SYNCHRONOUS);
+                gl::DebugMessageCallback(
+                    Some(debug_callback),
+                    &*state as *const DebugState as *const c_void,
+                );
+            }
+            log::debug!("GL debug output enabled");
+        } else {
+            log::debug!("No KHR_debug debug context, polling glGetError instead");
+        }
+        GlDebug { state, callback }
+    }
+
+    /// Whether messages come from the KHR_debug callback instead of `glGetError`
+    pub fn uses_callback(&self) -> bool {
+        self.callback
+    }
+
+    /// Polls `glGetError` without the callback, and returns the first fatal error since the
+    /// last check
+    ///
+    /// # Arguments
+    /// * `context` - what was done since the last check, for the log message
+    pub fn check(&self, context: &str) -> Result<(), String> {
+        if !self.callback {
+            loop {
+                let error = unsafe { gl::GetError() };
+                if error == gl::NO_ERROR {
+                    break;
+                }
+                let message = format!("GL error {} during {context}", error_name(error));
+                log::error!("{message}");
+                self.state.report_fatal(message);
+            }
+        }
+        match self.state.take_fatal() {
+            Some(message) => Err(message),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for GlDebug {
+    fn drop(&mut self) {
+        if self.callback {
+            unsafe {
+                gl::DebugMessageCallback(None, std::ptr::null());
+            }
+        }
+    }
+}
+
+extern "system" fn debug_callback(
+    source: u32,
+    kind: u32,
+    id: u32,
+    severity: u32,
+    _length: i32,
+    message: *const c_char,
+    user_param: *mut c_void,
+) {
+    // SAFETY: the driver passes a null terminated string, and `user_param` is the boxed state
+    // that outlives the callback, see `GlDebug::drop`
+    let (message, state) = unsafe {
+        (
+            CStr::from_ptr(message).to_string_lossy(),
+            &*(user_param as *const DebugState),
+        )
+    };
+    let text = format!(
+        "GL {} {} {id}: {message}",
+        source_name(source),
+        type_name(kind)
+    );
+    let level = match severity {
+        gl::DEBUG_SEVERITY_HIGH => log::Level::Error,
+        gl::DEBUG_SEVERITY_MEDIUM => log::Level::Warn,
+        gl::DEBUG_SEVERITY_LOW => log::Level::Info,
+        _ => log::Level::Debug,
+    };
+    log::log!(level, "{text}");
+    if severity == gl::DEBUG_SEVERITY_HIGH {
+        state.report_fatal(text);
+    }
+}
+
+fn source_name(source: u32) -> &'static str {
+    match source {
+        gl::DEBUG_SOURCE_API => "api",
+        gl::DEBUG_SOURCE_WINDOW_SYSTEM => "window-system",
+        gl::DEBUG_SOURCE_SHADER_COMPILER => "shader-compiler",
+        gl::DEBUG_SOURCE_THIRD_PARTY => "third-party",
+        gl::DEBUG_SOURCE_APPLICATION => "application",
+        _ => "other",
+    }
+}
+
+fn type_name(kind: u32) -> &'static str {
+    match kind {
+        gl::DEBUG_TYPE_ERROR => "error",
+        gl::DEBUG_TYPE_DEPRECATED_BEHAVIOR => "deprecated",
+        gl::DEBUG_TYPE_UNDEFINED_BEHAVIOR => "undefined-behavior",
+        gl::DEBUG_TYPE_PORTABILITY => "portability",
+        gl::DEBUG_TYPE_PERFORMANCE => "performance",
+        gl::DEBUG_TYPE_MARKER => "marker",
+        _ => "other",
+    }
+}
+
+fn error_name(error: u32) -> String {
+    match error {
+        gl::INVALID_ENUM => "INVALID_ENUM".to_string(),
+        gl::INVALID_VALUE => "INVALID_VALUE".to_string(),
+        gl::INVALID_OPERATION => "INVALID_OPERATION".to_string(),
+        gl::INVALID_FRAMEBUFFER_OPERATION => "INVALID_FRAMEBUFFER_OPERATION".to_string(),
+        gl::OUT_OF_MEMORY => "OUT_OF_MEMORY".to_string(),
+        _ => format!("{error:#x}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_fatal_when_enabled() {
+        let state = DebugState {
+            fatal_errors: false,
+            fatal: Mutex::new(None),
+        };
+        state.report_fatal("ignored".to_string());
+        assert_eq!(*state.fatal.lock().unwrap(), None);
+
+        let state = DebugState {
+            fatal_errors: true,
+            ..state
+        };
+        state.report_fatal("first".to_string());
+        state.report_fatal("second".to_string());
+        assert_eq!(state.take_fatal().as_deref(), Some("first"));
+        assert_eq!(state.take_fatal(), None);
+        assert_eq!(error_name(gl::INVALID_ENUM), "INVALID_ENUM");
+    }
+}
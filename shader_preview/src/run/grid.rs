@@ -0,0 +1,186 @@
+//! Background grid with axes and coordinate labels for the 2D scene
+//!
+//! Only the visible part of the grid is generated, every frame, so it never ends. The line
+//! spacing is a power of ten times 1, 2 or 5, picked so lines stay at least
+//! [`MIN_LINE_PIXELS`] apart at any zoom.
+use crate::run::camera::Camera2D;
+use crate::run::mesh::{Color, Mesh, VertexAttribute};
+use crate::run::overlay::{Overlay, Rgba};
+use crate::run::shader_program::{ShaderProgram, Uniform};
+use crate::shapes::grid_multiples;
+use vatnar_linalg::Vector2;
+
+pub const MIN_LINE_PIXELS: f64 = 60.0;
+/// Every n-th line is drawn brighter
+const MAJOR_EVERY: i64 = 5;
+
+const MINOR_COLOR: Rgba = [1.0, 1.0, 1.0, 0.06];
+const MAJOR_COLOR: Rgba = [1.0, 1.0, 1.0, 0.15];
+const X_AXIS_COLOR: Rgba = [0.9, 0.3, 0.3, 0.8];
+const Y_AXIS_COLOR: Rgba = [0.3, 0.9, 0.3, 0.8];
+const LABEL_COLOR: Rgba = [0.7, 0.7, 0.7, 1.0];
+
+pub struct Grid {
+    program: ShaderProgram,
+    view_uniform: Uniform,
+    mesh: Mesh,
+    vertices: Vec<f32>,
+}
+
+impl Grid {
+    /// Creates the grid, requires a current GL context
+    pub fn new() -> Result<Self, String> {
+        let program = ShaderProgram::from_source(
+            include_str!("../vertex_shader_grid.glsl"),
+            include_str!("../fragment_shader_grid.glsl"),
+        )?;
+        let mesh = Mesh::from_vertices(
+            &[],
+            &[VertexAttribute::POSITION_2D, VertexAttribute::COLOR],
+            None,
+            gl::LINES,
+            Color::from((1.0, 1.0, 1.0)),
+        );
+        Ok(Grid {
+            view_uniform: program.get_uniform("u_view"),
+            program,
+            mesh,
+            vertices: Vec::new(),
+        })
+    }
+
+    /// Regenerates the lines for the area `camera` sees
+    pub fn update(&mut self, camera: &Camera2D) {
+        let (min, max) = camera.visible_bounds();
+        let spacing = line_spacing(camera.pixels_per_unit(), MIN_LINE_PIXELS);
+
+        self.vertices.clear();
+        for i in grid_multiples(min.x, max.x, spacing) {
+            let x = (i as f64 * spacing) as f32;
+            let color = line_color(i, Y_AXIS_COLOR);
+            self.line([x, min.y as f32], [x, max.y as f32], color);
+        }
+        for i in grid_multiples(min.y, max.y, spacing) {
+            let y = (i as f64 * spacing) as f32;
+            let color = line_color(i, X_AXIS_COLOR);
+            self.line([min.x as f32, y], [max.x as f32, y], color);
+        }
+        self.mesh.set_vertices(&self.vertices);
+    }
+
+    fn line(&mut self, from: [f32; 2], to: [f32; 2], color: Rgba) {
+        for point in [from, to] {
+            self.vertices.extend_from_slice(&point);
+            self.vertices.extend_from_slice(&color);
+        }
+    }
+
+    /// Draws the lines from the last [`Grid::update`], switching to the grid shader
+    pub fn draw(&self, camera: &Camera2D) {
+        self.program.use_program();
+        unsafe {
+            let view = camera.view_projection().to_column_major_f32();
+            gl::UniformMatrix3fv(*self.view_uniform, 1, gl::FALSE, view.as_ptr());
+        }
+        self.mesh.render_state.apply();
+        self.mesh.draw();
+    }
+
+    /// Adds tick labels along the axes, or along the screen edges when an axis is off screen
+    pub fn labels(&self, camera: &Camera2D, overlay: &mut Overlay) {
+        let (min, max) = camera.visible_bounds();
+        let spacing = line_spacing(camera.pixels_per_unit(), MIN_LINE_PIXELS);
+        let viewport = camera.viewport();
+        let origin = camera.world_to_screen(Vector2::new(0.0, 0.0));
+        let margin = 4.0 * overlay.scale as f64;
+        let line_height = overlay.line_height() as f64;
+
+        let label_y = origin
+            .y
+            .clamp(margin, viewport.y - line_height - margin * 2.0);
+        for i in grid_multiples(min.x, max.x, spacing).filter(|&i| i != 0) {
+            let x = camera
+                .world_to_screen(Vector2::new(i as f64 * spacing, 0.0))
+                .x;
+            let text = format_tick(i as f64 * spacing, spacing);
+            overlay.text(
+                (x + margin) as f32,
+                (label_y + margin) as f32,
+                &text,
+                LABEL_COLOR,
+            );
+        }
+
+        for i in grid_multiples(min.y, max.y, spacing) {
+            let y = camera
+                .world_to_screen(Vector2::new(0.0, i as f64 * spacing))
+                .y;
+            let text = format_tick(i as f64 * spacing, spacing);
+            let width = overlay.text_size(&text).0 as f64;
+            let label_x = origin.x.clamp(0.0, viewport.x - width - margin * 2.0);
+            overlay.text(
+                (label_x + margin) as f32,
+                (y + margin) as f32,
+                &text,
+                LABEL_COLOR,
+            );
+        }
+    }
+}
+
+fn line_color(index: i64, axis_color: Rgba) -> Rgba {
+    if index == 0 {
+        axis_color
+    } else if index % MAJOR_EVERY == 0 {
+        MAJOR_COLOR
+    } else {
+        MINOR_COLOR
+    }
+}
+
+/// Smallest spacing of the form `{1, 2, 5} * 10^n` that keeps lines `min_pixels` apart
+pub fn line_spacing(pixels_per_unit: f64, min_pixels: f64) -> f64 {
+    let min_spacing = min_pixels / pixels_per_unit;
+    let power = 10f64.powf(min_spacing.log10().floor());
+    [1.0, 2.0, 5.0, 10.0]
+        .into_iter()
+        .map(|step| step * power)
+        .find(|&spacing| spacing >= min_spacing * (1.0 - 1e-9))
+        .unwrap_or(10.0 * power)
+}
+
+/// Formats a tick value with as many decimals as the spacing needs
+pub fn format_tick(value: f64, spacing: f64) -> String {
+    let decimals = (-spacing.log10().floor()).max(0.0) as usize;
+    // avoid printing "-0"
+    let value = if value.abs() < spacing / 2.0 {
+        0.0
+    } else {
+        value
+    };
+    format!("{value:.decimals$}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_spacing() {
+        assert_eq!(line_spacing(60.0, 60.0), 1.0);
+        assert_eq!(line_spacing(50.0, 60.0), 2.0);
+        assert_eq!(line_spacing(20.0, 60.0), 5.0);
+        assert_eq!(line_spacing(10.0, 60.0), 10.0);
+        assert!((line_spacing(1000.0, 60.0) - 0.1).abs() < 1e-12);
+        assert!((line_spacing(1e6, 60.0) - 1e-4).abs() < 1e-15);
+    }
+
+    #[test]
+    fn test_format_tick() {
+        assert_eq!(format_tick(20.0, 10.0), "20");
+        assert_eq!(format_tick(0.5, 0.5), "0.5");
+        assert_eq!(format_tick(0.3, 0.1), "0.3");
+        assert_eq!(format_tick(-0.0025, 0.0005), "-0.0025");
+        assert_eq!(format_tick(-1e-17, 0.1), "0.0");
+    }
+}
@@ -0,0 +1,129 @@
+//! Rendering into images without showing a window, for golden image tests
+use crate::run::backend::{GlBackend, RenderBackend};
+use crate::run::context::{Capabilities, GlConfig};
+use crate::run::fullscreen::FullscreenTriangle;
+use crate::run::gl_debug::GlDebug;
+use crate::run::material::{UniformValue, UniformValues};
+use crate::run::mesh::Color;
+use crate::run::model_view::ModelView;
+use crate::run::render_state::{BlendMode, RenderState};
+use crate::run::render_target::RenderTarget;
+use crate::run::screenshot;
+use crate::run::shader_program::ShaderProgram;
+use crate::run::texture::{Texture, TextureFormat};
+use glfw::{Context, Glfw, PWindow, WindowHint, WindowMode};
+use image::RgbaImage;
+use std::path::Path;
+
+/// A GL context of an invisible window, drawing into offscreen targets
+pub struct Headless {
+    // dropped before the window, which owns the context they were created in
+    gl_debug: GlDebug,
+    triangle: FullscreenTriangle,
+    _window: PWindow,
+    _glfw: Glfw,
+}
+
+impl Headless {
+    pub fn new(config: &GlConfig) -> Result<Self, String> {
+        let mut glfw =
+            glfw::init(|error, description| log::error!("GLFW {error:?}: {description}"))
+                .map_err(|e| format!("Couldn't initialize GLFW: {e}"))?;
+        config.apply_hints(&mut glfw);
+        glfw.window_hint(WindowHint::Visible(false));
+        let (mut window, _) = glfw
+            .create_window(1, 1, "ShaderPreview", WindowMode::Windowed)
+            .ok_or_else(|| {
+                format!(
+                    "Failed to create a hidden GLFW window ({} requested)",
+                    config.describe()
+                )
+            })?;
+        window.make_current();
+        gl::load_with(|symbol| window.get_proc_address(symbol) as *const _);
+        let capabilities = Capabilities::query();
+        log::debug!(
+            "Rendering headless with {} on {}",
+            capabilities.version,
+            capabilities.renderer
+        );
+
+        Ok(Headless {
+            gl_debug: GlDebug::install(true),
+            triangle: FullscreenTriangle::new(),
+            _window: window,
+            _glfw: glfw,
+        })
+    }
+
+    /// Draws with `draw` into a `width` x `height` RGBA8 target cleared to `clear_color`, and
+    /// reads it back top row first
+    pub fn render(
+        &self,
+        width: u32,
+        height: u32,
+        clear_color: Color,
+        draw: impl FnOnce(&mut GlBackend),
+    ) -> Result<RgbaImage, String> {
+        let target = RenderTarget::new(width, height, TextureFormat::Rgba8)?;
+        target.bind();
+        let mut backend = GlBackend;
+        unsafe {
+            gl::Viewport(0, 0, width as i32, height as i32);
+        }
+        backend.clear(clear_color);
+        draw(&mut backend);
+        let image = screenshot::read_framebuffer(width, height);
+        RenderTarget::bind_window();
+        self.gl_debug.check("headless rendering")?;
+        Ok(image)
+    }
+
+    /// Compiles a fragment shader for [`Headless::render_shader`], it gets the `v_uv`
+    /// coordinates of post-processing passes
+    pub fn load_shader(&self, path: &Path) -> Result<ShaderProgram, String> {
+        let fragment_source = std::fs::read_to_string(path)
+            .map_err(|e| format!("Couldn't read shader '{}': {e}", path.display()))?;
+        Ok(GlBackend
+            .create_program(include_str!("../vertex_shader_post.glsl"), &fragment_source)?)
+    }
+
+    /// Draws `program` over the whole image at `time` seconds, with `input` bound to
+    /// `u_texture`
+    ///
+    /// The program also gets `u_resolution` and `u_time`, and their Shadertoy names.
+    pub fn render_shader(
+        &self,
+        program: &ShaderProgram,
+        input: &Texture,
+        (width, height): (u32, u32),
+        time: f64,
+    ) -> Result<RgbaImage, String> {
+        let (w, h, t) = (width as f32, height as f32, time as f32);
+        let uniforms = UniformValues::default()
+            .with("u_texture", UniformValue::Int(0))
+            .with("u_resolution", UniformValue::Vec2([w, h]))
+            .with("iResolution", UniformValue::Vec3([w, h, 1.0]))
+            .with("u_time", UniformValue::Float(t))
+            .with("iTime", UniformValue::Float(t));
+        self.render(width, height, (0.0, 0.0, 0.0).into(), |backend| {
+            backend.apply_render_state(&RenderState::default().with_blend(BlendMode::Opaque));
+            backend.use_program(program);
+            backend.bind_texture(0, input);
+            uniforms.apply(backend, program);
+            self.triangle.draw();
+        })
+    }
+
+    /// Draws a model as the 3D preview shows it before moving the camera
+    pub(crate) fn render_model(
+        &self,
+        model: &ModelView,
+        (width, height): (u32, u32),
+        time: f64,
+    ) -> Result<RgbaImage, String> {
+        self.render(width, height, (0.1, 0.1, 0.1).into(), |backend| {
+            model.render(backend, width as f64 / height as f64, time)
+        })
+    }
+}
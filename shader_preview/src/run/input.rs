@@ -0,0 +1,303 @@
+//! Maps keys, mouse buttons and scrolling to named actions
+//!
+//! [`Input`] tracks which bindings are held down, so several keys can be held at once and a
+//! lost release event can't leave the camera drifting. Bindings are written as strings in the
+//! config file: GLFW key names like `"A"`, `"Left"` or `"F5"`, mouse buttons `"MouseLeft"`,
+//! `"MouseRight"`, `"MouseMiddle"` or `"Mouse1"` to `"Mouse8"`, and `"ScrollUp"`/`"ScrollDown"`.
+use glfw::{Action as KeyAction, Key, MouseButton, WindowEvent};
+use serde::de::IntoDeserializer;
+use serde::{Deserialize, Deserializer};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+/// Something the user can do, written in `snake_case` in the config file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    PanLeft,
+    PanRight,
+    PanUp,
+    PanDown,
+    ZoomIn,
+    ZoomOut,
+    /// Pans the 2D scene or orbits the model while held and the cursor moves
+    Drag,
+    /// Freezes `u_time`
+    Pause,
+    /// Shows or hides the background grid of the 2D scene
+    ToggleGrid,
+    /// Shows or hides the frame time HUD
+    ToggleStats,
+    /// Shows or hides the panel of ISF inputs
+    ToggleControls,
+    Screenshot,
+    /// Reloads the config file, textures and model from disk
+    Reload,
+    ToggleFullscreen,
+    Quit,
+}
+
+/// A physical input that can trigger an [`Action`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Binding {
+    Key(Key),
+    Mouse(MouseButton),
+    ScrollUp,
+    ScrollDown,
+}
+
+impl FromStr for Binding {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let mouse = |button: i32| {
+            MouseButton::from_i32(button - 1)
+                .map(Binding::Mouse)
+                .ok_or_else(|| format!("Unknown mouse button '{name}'"))
+        };
+        match name {
+            "ScrollUp" => Ok(Binding::ScrollUp),
+            "ScrollDown" => Ok(Binding::ScrollDown),
+            "MouseLeft" => mouse(1),
+            "MouseRight" => mouse(2),
+            "MouseMiddle" => mouse(3),
+            _ => match name.strip_prefix("Mouse").map(str::parse::<i32>) {
+                Some(Ok(button)) => mouse(button),
+                _ => Key::deserialize(name.into_deserializer())
+                    .map(Binding::Key)
+                    .map_err(|_: serde::de::value::Error| format!("Unknown key '{name}'")),
+            },
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Binding {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Binding::Key(key) => write!(f, "{key:?}"),
+            Binding::Mouse(button) => write!(f, "Mouse{}", *button as i32 + 1),
+            Binding::ScrollUp => write!(f, "ScrollUp"),
+            Binding::ScrollDown => write!(f, "ScrollDown"),
+        }
+    }
+}
+
+/// Bindings of every action
+///
+/// In the config file this is a table of action names to lists of bindings, actions that are
+/// left out keep their default bindings.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(from = "BTreeMap<Action, Vec<Binding>>")]
+pub struct InputMap {
+    bindings: BTreeMap<Action, Vec<Binding>>,
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        use Binding::{Key as K, Mouse as M};
+        let bindings = [
+            (Action::PanLeft, vec![K(Key::A), K(Key::Left)]),
+            (Action::PanRight, vec![K(Key::D), K(Key::Right)]),
+            (Action::PanUp, vec![K(Key::W), K(Key::Up)]),
+            (Action::PanDown, vec![K(Key::S), K(Key::Down)]),
+            (Action::ZoomIn, vec![Binding::ScrollUp, K(Key::Equal)]),
+            (Action::ZoomOut, vec![Binding::ScrollDown, K(Key::Minus)]),
+            (Action::Drag, vec![M(MouseButton::Button1)]),
+            (Action::Pause, vec![K(Key::Space)]),
+            (Action::ToggleGrid, vec![K(Key::G)]),
+            (Action::ToggleStats, vec![K(Key::F3)]),
+            (Action::ToggleControls, vec![K(Key::Tab)]),
+            (Action::Screenshot, vec![K(Key::F12)]),
+            (Action::Reload, vec![K(Key::F5)]),
+            (Action::ToggleFullscreen, vec![K(Key::F11)]),
+            (Action::Quit, vec![K(Key::Escape)]),
+        ];
+        InputMap {
+            bindings: bindings.into_iter().collect(),
+        }
+    }
+}
+
+impl From<BTreeMap<Action, Vec<Binding>>> for InputMap {
+    fn from(bindings: BTreeMap<Action, Vec<Binding>>) -> Self {
+        let mut map = InputMap::default();
+        map.bindings.extend(bindings);
+        map
+    }
+}
+
+impl InputMap {
+    pub fn bindings(&self, action: Action) -> &[Binding] {
+        self.bindings.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Actions triggered by `binding`
+    pub fn actions(&self, binding: Binding) -> impl Iterator<Item = Action> + '_ {
+        self.bindings
+            .iter()
+            .filter(move |(_, bindings)| bindings.contains(&binding))
+            .map(|(action, _)| *action)
+    }
+}
+
+/// Input state of the current frame
+///
+/// Feed it every window event with [`Input::handle_event`] and call [`Input::end_frame`]
+/// once the frame has been processed.
+#[derive(Debug, Default)]
+pub struct Input {
+    pub map: InputMap,
+    held: HashSet<Binding>,
+    pressed: HashSet<Action>,
+    scroll: BTreeMap<Action, f64>,
+}
+
+impl Input {
+    pub fn new(map: InputMap) -> Self {
+        Input {
+            map,
+            ..Default::default()
+        }
+    }
+
+    pub fn handle_event(&mut self, event: &WindowEvent) {
+        match *event {
+            WindowEvent::Key(key, _, action, _) => self.set_held(Binding::Key(key), action),
+            WindowEvent::MouseButton(button, action, _) => {
+                self.set_held(Binding::Mouse(button), action)
+            }
+            // horizontal scrolling isn't bound to anything
+            WindowEvent::Scroll(_, 0.0) => {}
+            WindowEvent::Scroll(_, y_offset) => {
+                let binding = if y_offset > 0.0 {
+                    Binding::ScrollUp
+                } else {
+                    Binding::ScrollDown
+                };
+                for action in self.map.actions(binding) {
+                    *self.scroll.entry(action).or_default() += y_offset.abs();
+                    self.pressed.insert(action);
+                }
+            }
+            // releases are lost while the window is unfocused
+            WindowEvent::Focus(false) => self.held.clear(),
+            _ => {}
+        }
+    }
+
+    fn set_held(&mut self, binding: Binding, action: KeyAction) {
+        match action {
+            KeyAction::Press => {
+                self.held.insert(binding);
+                self.pressed.extend(self.map.actions(binding));
+            }
+            KeyAction::Release => {
+                self.held.remove(&binding);
+            }
+            KeyAction::Repeat => {}
+        }
+    }
+
+    /// Whether any binding of `action` is held down
+    pub fn is_held(&self, action: Action) -> bool {
+        self.map
+            .bindings(action)
+            .iter()
+            .any(|binding| self.held.contains(binding))
+    }
+
+    /// Whether `action` was triggered this frame, by a press or by scrolling
+    pub fn was_pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    /// How far the scroll wheel was turned towards `action` this frame
+    pub fn scroll(&self, action: Action) -> f64 {
+        self.scroll.get(&action).copied().unwrap_or(0.0)
+    }
+
+    /// -1, 0 or 1 depending on which of the two actions is held
+    pub fn axis(&self, negative: Action, positive: Action) -> f64 {
+        self.is_held(positive) as i32 as f64 - self.is_held(negative) as i32 as f64
+    }
+
+    /// Clears the presses and scrolling of the frame, held bindings stay
+    pub fn end_frame(&mut self) {
+        self.pressed.clear();
+        self.scroll.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glfw::Modifiers;
+
+    fn key(key: Key, action: KeyAction) -> WindowEvent {
+        WindowEvent::Key(key, 0, action, Modifiers::empty())
+    }
+
+    #[test]
+    fn test_parse_binding() {
+        assert_eq!("A".parse(), Ok(Binding::Key(Key::A)));
+        assert_eq!("F5".parse(), Ok(Binding::Key(Key::F5)));
+        assert_eq!(
+            "MouseLeft".parse(),
+            Ok(Binding::Mouse(MouseButton::Button1))
+        );
+        assert_eq!("Mouse3".parse(), Ok(Binding::Mouse(MouseButton::Button3)));
+        assert_eq!("ScrollDown".parse(), Ok(Binding::ScrollDown));
+        assert!("Mouse9".parse::<Binding>().is_err());
+        assert!("NotAKey".parse::<Binding>().is_err());
+
+        for binding in ["Left", "Mouse2", "ScrollUp"] {
+            assert_eq!(binding.parse::<Binding>().unwrap().to_string(), binding);
+        }
+    }
+
+    #[test]
+    fn test_held_keys() {
+        let mut input = Input::default();
+        input.handle_event(&key(Key::A, KeyAction::Press));
+        input.handle_event(&key(Key::Left, KeyAction::Press));
+        input.handle_event(&key(Key::W, KeyAction::Press));
+        assert!(input.was_pressed(Action::PanLeft));
+        assert_eq!(input.axis(Action::PanLeft, Action::PanRight), -1.0);
+        assert_eq!(input.axis(Action::PanDown, Action::PanUp), 1.0);
+
+        // still held through the other binding, and presses only last one frame
+        input.end_frame();
+        input.handle_event(&key(Key::A, KeyAction::Release));
+        assert!(input.is_held(Action::PanLeft));
+        assert!(!input.was_pressed(Action::PanLeft));
+
+        input.handle_event(&key(Key::Left, KeyAction::Release));
+        assert_eq!(input.axis(Action::PanLeft, Action::PanRight), 0.0);
+
+        input.handle_event(&WindowEvent::Focus(false));
+        assert!(!input.is_held(Action::PanUp));
+    }
+
+    #[test]
+    fn test_scroll() {
+        let mut input = Input::default();
+        input.handle_event(&WindowEvent::Scroll(0.0, 1.0));
+        input.handle_event(&WindowEvent::Scroll(0.0, 0.5));
+        assert_eq!(input.scroll(Action::ZoomIn), 1.5);
+        assert_eq!(input.scroll(Action::ZoomOut), 0.0);
+        input.end_frame();
+        assert_eq!(input.scroll(Action::ZoomIn), 0.0);
+
+        input.handle_event(&WindowEvent::Scroll(2.0, 0.0));
+        assert_eq!(input.scroll(Action::ZoomOut), 0.0);
+        assert!(!input.was_pressed(Action::ZoomOut));
+    }
+}
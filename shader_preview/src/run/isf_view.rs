@@ -0,0 +1,288 @@
+//! Preview mode for ISF shaders, see [`crate::import::isf`]
+//!
+//! Inputs become widgets of a [`ControlPanel`], except images, which are bound to the
+//! textures of the same name on the command line or a placeholder. Pass targets are kept
+//! between frames, a pass reading its own target or a later one sees the previous frame.
+//! Targets that aren't `PERSISTENT` are cleared at the start of each frame.
+use crate::import::isf::{InputKind, Isf, Pass};
+use crate::run::backend::{GlBackend, RenderBackend};
+use crate::run::controls::{Control, ControlPanel, Widget};
+use crate::run::fullscreen::{DoubleTarget, FullscreenTriangle, utc_date};
+use crate::run::material::{UniformValue, UniformValues};
+use crate::run::render_state::{BlendMode, RenderState};
+use crate::run::shader_program::ShaderProgram;
+use crate::run::texture::{Texture, TextureBindings, TextureData, TextureFormat, TextureOptions};
+use std::path::Path;
+use std::time::SystemTime;
+
+/// A named target passes draw into
+struct Target {
+    name: String,
+    persistent: bool,
+    format: TextureFormat,
+    /// Created on the first frame and recreated when its size changes
+    buffer: Option<DoubleTarget>,
+}
+
+/// What a sampler uniform reads
+enum Sampler {
+    /// An image input, by the name of its command line texture
+    Input(String),
+    /// Index into [`IsfView::imported`]
+    Imported(usize),
+    /// Index into [`IsfView::targets`]
+    Target(usize),
+}
+
+pub(crate) struct IsfView {
+    program: ShaderProgram,
+    passes: Vec<Pass>,
+    /// Target of each pass, by index into `targets`
+    pass_targets: Vec<Option<usize>>,
+    targets: Vec<Target>,
+    /// Sampler uniforms, each bound to the texture unit of its index
+    samplers: Vec<(String, Sampler)>,
+    imported: Vec<Texture>,
+    /// Bound to image inputs without a texture
+    placeholder: Texture,
+    pub controls: ControlPanel,
+    frame: i32,
+    triangle: FullscreenTriangle,
+    /// `GL_MAX_TEXTURE_SIZE`, pass sizes are clamped to it
+    max_size: u32,
+}
+
+impl IsfView {
+    /// Loads an ISF shader and compiles it, requires a current GL context
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let isf = Isf::load(path)?;
+        let program = GlBackend
+            .create_program(
+                include_str!("../vertex_shader_post.glsl"),
+                &isf.fragment_source(),
+            )
+            .map_err(|e| format!("'{}': {e}", path.display()))?;
+
+        let targets: Vec<Target> = isf
+            .targets()
+            .into_iter()
+            .map(|name| {
+                let passes = || {
+                    isf.passes
+                        .iter()
+                        .filter(|p| p.target.as_deref() == Some(name))
+                };
+                Target {
+                    name: name.to_string(),
+                    persistent: passes().any(|pass| pass.persistent),
+                    format: match passes().any(|pass| pass.float) {
+                        true => TextureFormat::Rgba32F,
+                        false => TextureFormat::Rgba8,
+                    },
+                    buffer: None,
+                }
+            })
+            .collect();
+        let pass_targets = isf
+            .passes
+            .iter()
+            .map(|pass| {
+                let name = pass.target.as_deref()?;
+                targets.iter().position(|target| target.name == name)
+            })
+            .collect();
+
+        let image_dir = path.parent().unwrap_or(Path::new("."));
+        let options = TextureOptions::default();
+        let mut imported = Vec::new();
+        let mut samplers = Vec::new();
+        for image in &isf.imported {
+            let image_path = image_dir.join(&image.path);
+            let data = if image_path.is_file() {
+                TextureData::load(&image_path)?
+            } else {
+                log::warn!(
+                    "Imported image '{}' isn't in '{}', using a placeholder",
+                    image.path,
+                    image_dir.display()
+                );
+                TextureData::placeholder()
+            };
+            imported.push(Texture::upload(&data, &options));
+            samplers.push((image.name.clone(), Sampler::Imported(imported.len() - 1)));
+        }
+        let mut controls = Vec::new();
+        for input in &isf.inputs {
+            match control(&input.kind) {
+                Some(widget) => controls.push(Control {
+                    name: input.name.clone(),
+                    label: input.label.clone(),
+                    widget,
+                }),
+                None => samplers.push((input.name.clone(), Sampler::Input(input.name.clone()))),
+            }
+        }
+        for (index, target) in targets.iter().enumerate() {
+            samplers.push((target.name.clone(), Sampler::Target(index)));
+        }
+        log::info!(
+            "Loaded ISF shader '{}' with {} passes and {} inputs",
+            path.display(),
+            isf.passes.len(),
+            isf.inputs.len()
+        );
+
+        let mut max_size = 0;
+        unsafe {
+            gl::GetIntegerv(gl::MAX_TEXTURE_SIZE, &mut max_size);
+        }
+
+        Ok(IsfView {
+            program,
+            passes: isf.passes,
+            pass_targets,
+            targets,
+            samplers,
+            imported,
+            placeholder: Texture::upload(&TextureData::placeholder(), &options),
+            controls: ControlPanel::new(controls),
+            frame: 0,
+            triangle: FullscreenTriangle::new(),
+            max_size: max_size.max(1) as u32,
+        })
+    }
+
+    /// Runs the passes, the ones without a target draw into the bound framebuffer
+    ///
+    /// `images` are bound to the image inputs of the same name, `time_delta` is 0 while
+    /// paused.
+    pub fn render(
+        &mut self,
+        (width, height): (u32, u32),
+        time: f64,
+        time_delta: f64,
+        images: &TextureBindings,
+    ) -> Result<(), String> {
+        let (width, height) = (width.max(1), height.max(1));
+        let (mut framebuffer, mut viewport) = (0, [0; 4]);
+        unsafe {
+            gl::GetIntegerv(gl::DRAW_FRAMEBUFFER_BINDING, &mut framebuffer);
+            gl::GetIntegerv(gl::VIEWPORT, viewport.as_mut_ptr());
+        }
+
+        let mut uniforms = self.controls.uniforms();
+        let variable = |name: &str| match uniforms.get(name)? {
+            UniformValue::Float(value) => Some(value as f64),
+            UniformValue::Int(value) => Some(value as f64),
+            _ => None,
+        };
+        let mut sizes = Vec::new();
+        for pass in &self.passes {
+            let (pass_width, pass_height) = pass
+                .size((width, height), variable)
+                .map_err(|e| format!("Size of the pass into {:?}: {e}", pass.target))?;
+            sizes.push((
+                pass_width.min(self.max_size),
+                pass_height.min(self.max_size),
+            ));
+        }
+        for (pass, target) in self.pass_targets.iter().enumerate() {
+            let Some(target) = target.map(|index| &mut self.targets[index]) else {
+                continue;
+            };
+            let size = sizes[pass];
+            match &target.buffer {
+                Some(buffer) if buffer.size() == size => {
+                    if !target.persistent {
+                        buffer.clear();
+                    }
+                }
+                _ => target.buffer = Some(DoubleTarget::new(size.0, size.1, target.format)?),
+            }
+        }
+
+        let (year, month, day, seconds) = utc_date(SystemTime::now());
+        let date = [year as f32, month as f32, day as f32, seconds as f32];
+        uniforms.set("TIME", UniformValue::Float(time as f32));
+        uniforms.set("TIMEDELTA", UniformValue::Float(time_delta as f32));
+        uniforms.set("DATE", UniformValue::Vec4(date));
+        uniforms.set("FRAMEINDEX", UniformValue::Int(self.frame));
+
+        let mut backend = GlBackend;
+        backend.apply_render_state(&RenderState::default().with_blend(BlendMode::Opaque));
+        backend.use_program(&self.program);
+        uniforms.apply(&mut backend, &self.program);
+        for (pass, target) in self.pass_targets.iter().enumerate() {
+            let (pass_width, pass_height) = sizes[pass];
+            match target {
+                Some(index) => {
+                    let buffer = self.targets[*index].buffer.as_ref();
+                    buffer.expect("targets are created").back().bind();
+                }
+                None => unsafe {
+                    gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer as u32);
+                },
+            }
+            unsafe {
+                gl::Viewport(0, 0, pass_width as i32, pass_height as i32);
+            }
+            let size = [pass_width as f32, pass_height as f32];
+            let pass_uniforms = UniformValues::default()
+                .with("PASSINDEX", UniformValue::Int(pass as i32))
+                .with("RENDERSIZE", UniformValue::Vec2(size));
+            pass_uniforms.apply(&mut backend, &self.program);
+            for (unit, (name, sampler)) in self.samplers.iter().enumerate() {
+                let texture = match sampler {
+                    Sampler::Input(name) => images.get(name).unwrap_or(&self.placeholder),
+                    Sampler::Imported(index) => &self.imported[*index],
+                    Sampler::Target(index) => {
+                        let buffer = self.targets[*index].buffer.as_ref();
+                        &buffer.expect("targets are created").front().color
+                    }
+                };
+                backend.bind_texture(unit as u32, texture);
+                backend.set_uniform(&self.program, name, UniformValue::Int(unit as i32));
+            }
+            self.triangle.draw();
+            if let Some(index) = target {
+                let buffer = self.targets[*index].buffer.as_mut();
+                buffer.expect("targets are created").swap();
+            }
+        }
+
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer as u32);
+            gl::Viewport(viewport[0], viewport[1], viewport[2], viewport[3]);
+        }
+        self.frame += 1;
+        Ok(())
+    }
+}
+
+/// The widget of an input, `None` for images
+fn control(kind: &InputKind) -> Option<Widget> {
+    Some(match kind {
+        InputKind::Event => Widget::Button { clicked: false },
+        InputKind::Bool(checked) => Widget::Checkbox(*checked),
+        InputKind::Long {
+            default,
+            values,
+            labels,
+        } => Widget::Choice {
+            index: values.iter().position(|v| v == default).unwrap_or(0),
+            values: values.clone(),
+            labels: labels.clone(),
+        },
+        InputKind::Float { default, min, max } => Widget::Slider {
+            value: *default,
+            min: *min,
+            max: *max,
+        },
+        InputKind::Point2D { default, range } => Widget::Point {
+            value: *default,
+            range: *range,
+        },
+        InputKind::Color(color) => Widget::Color(*color),
+        InputKind::Image => return None,
+    })
+}
@@ -0,0 +1,153 @@
+//! Materials pair a shader program with the uniform values and textures it's drawn with
+use crate::run::backend::RenderBackend;
+use crate::run::render_state::RenderState;
+use crate::run::shader_program::ShaderProgram;
+use crate::run::texture::TextureBindings;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Value of a uniform, uploaded with the matching `glUniform*` call
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UniformValue {
+    Float(f32),
+    Int(i32),
+    Vec2([f32; 2]),
+    Vec3([f32; 3]),
+    Vec4([f32; 4]),
+    /// Column major 3x3 matrix
+    Mat3([f32; 9]),
+    /// Column major 4x4 matrix
+    Mat4([f32; 16]),
+}
+
+/// Named uniform values, set on whichever program is in use
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UniformValues {
+    values: Vec<(String, UniformValue)>,
+}
+
+impl UniformValues {
+    /// Sets `name` to `value`, replacing an earlier value
+    pub fn set(&mut self, name: &str, value: UniformValue) {
+        match self
+            .values
+            .iter_mut()
+            .find(|(existing, _)| existing == name)
+        {
+            Some((_, existing)) => *existing = value,
+            None => self.values.push((name.to_string(), value)),
+        }
+    }
+
+    pub fn with(mut self, name: &str, value: UniformValue) -> Self {
+        self.set(name, value);
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<UniformValue> {
+        self.values
+            .iter()
+            .find(|(existing, _)| existing == name)
+            .map(|(_, value)| *value)
+    }
+
+    /// Uploads the values to `program`, which has to be in use
+    ///
+    /// Uniforms the program doesn't have are skipped.
+    pub fn apply(&self, backend: &mut impl RenderBackend, program: &ShaderProgram) {
+        for (name, value) in &self.values {
+            backend.set_uniform(program, name, *value);
+        }
+    }
+}
+
+pub struct Material {
+    /// Shared between materials so meshes can be grouped by program when drawing
+    pub program: Rc<ShaderProgram>,
+    pub uniforms: UniformValues,
+    /// Textures bound to the sampler uniforms of the program
+    pub textures: TextureBindings,
+}
+
+impl Material {
+    pub fn new(program: Rc<ShaderProgram>) -> Self {
+        Material {
+            program,
+            uniforms: UniformValues::default(),
+            textures: TextureBindings::default(),
+        }
+    }
+
+    pub fn with_uniform(mut self, name: &str, value: UniformValue) -> Self {
+        self.uniforms.set(name, value);
+        self
+    }
+
+    /// Sets the uniforms and textures of the material, its program has to be in use
+    pub fn apply(&self, backend: &mut impl RenderBackend) {
+        self.uniforms.apply(backend, &self.program);
+        self.textures.bind(backend, &self.program);
+    }
+}
+
+/// Indices of draws, given by program id and render state, in the order to issue them so
+/// programs switch less often
+///
+/// Only runs of [order independent](RenderState::is_order_independent) draws are grouped by
+/// program, in the order the programs first appear in the run. Other draws keep their place
+/// in the scene, which decides what ends up on top and how blended draws combine.
+pub fn draw_order(draws: &[(u32, RenderState)]) -> Vec<usize> {
+    let indices: Vec<usize> = (0..draws.len()).collect();
+    let reorderable = |i: usize| draws[i].1.is_order_independent();
+    let mut order = Vec::with_capacity(draws.len());
+    for run in indices.chunk_by(|&a, &b| reorderable(a) && reorderable(b)) {
+        let mut first_use = HashMap::new();
+        for &i in run {
+            first_use.entry(draws[i].0).or_insert(i);
+        }
+        let mut run = run.to_vec();
+        run.sort_by_key(|&i| first_use[&draws[i].0]);
+        order.extend(run);
+    }
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uniform_values_replace() {
+        let mut values = UniformValues::default()
+            .with("u_color", UniformValue::Vec3([1.0, 0.0, 0.0]))
+            .with("u_scale", UniformValue::Float(2.0));
+        values.set("u_color", UniformValue::Vec3([0.0, 1.0, 0.0]));
+        assert_eq!(
+            values.get("u_color"),
+            Some(UniformValue::Vec3([0.0, 1.0, 0.0]))
+        );
+        assert_eq!(values.get("u_scale"), Some(UniformValue::Float(2.0)));
+        assert_eq!(values.get("u_missing"), None);
+    }
+
+    #[test]
+    fn test_draw_order_groups_programs() {
+        let opaque = |programs: &[u32]| -> Vec<(u32, RenderState)> {
+            programs
+                .iter()
+                .map(|&program| (program, RenderState::OPAQUE_3D))
+                .collect()
+        };
+        assert_eq!(draw_order(&opaque(&[3, 5, 3, 7, 5])), [0, 2, 1, 4, 3]);
+        assert_eq!(draw_order(&opaque(&[1, 1, 1])), [0, 1, 2]);
+        assert!(draw_order(&[]).is_empty());
+
+        // blended and 2D draws stay in place and split the runs that get grouped
+        let mut draws = opaque(&[3, 5, 3, 5, 3, 5]);
+        draws[2].1 = RenderState::default();
+        draws[3].1.depth_test = false;
+        assert_eq!(draw_order(&draws), [0, 1, 2, 3, 4, 5]);
+        draws[3].1.depth_test = true;
+        assert_eq!(draw_order(&draws), [0, 1, 2, 3, 5, 4]);
+    }
+}
@@ -1,9 +1,14 @@
 extern crate gl;
 extern crate glfw;
 
+use crate::run::backend::{GlBackend, MeshBuffers, RenderBackend};
+use crate::run::material::Material;
+use crate::run::render_state::RenderState;
 use std::ops::Deref;
+use std::rc::Rc;
 use vatnar_linalg::Point2;
 
+#[derive(Debug, Clone)]
 pub struct Polygon(pub Vec<Point2<f32>>);
 impl Deref for Polygon {
     type Target = Vec<Point2<f32>>;
@@ -44,7 +49,7 @@ macro_rules! polygon_from {
     };
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Color {
     r: f32,
     g: f32,
@@ -66,63 +71,314 @@ impl From<(f32, f32, f32)> for Color {
         }
     }
 }
+
+impl From<[f32; 3]> for Color {
+    fn from([r, g, b]: [f32; 3]) -> Self {
+        Color { r, g, b }
+    }
+}
+/// A vertex attribute in an interleaved vertex buffer
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VertexAttribute {
+    /// Attribute location in the vertex shader, `layout (location = n)`
+    pub location: u32,
+    /// Amount of floats in the attribute, 1 to 4
+    pub components: i32,
+}
+
+impl VertexAttribute {
+    /// 2D position at location 0, the layout used by [`Mesh::from_polygon`]
+    pub const POSITION_2D: VertexAttribute = VertexAttribute {
+        location: 0,
+        components: 2,
+    };
+    /// 3D position at location 0
+    pub const POSITION_3D: VertexAttribute = VertexAttribute {
+        location: 0,
+        components: 3,
+    };
+    /// Normal at location 1
+    pub const NORMAL: VertexAttribute = VertexAttribute {
+        location: 1,
+        components: 3,
+    };
+    /// Texture coordinate at location 2
+    pub const UV: VertexAttribute = VertexAttribute {
+        location: 2,
+        components: 2,
+    };
+    /// RGBA vertex color at location 3
+    pub const COLOR: VertexAttribute = VertexAttribute {
+        location: 3,
+        components: 4,
+    };
+}
+
+/// CPU side geometry of a 3D mesh, as produced by the model loaders
+///
+/// `normals` and `uvs` are either empty or have one entry per position.
+#[derive(Debug, Clone)]
+pub struct MeshData {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+    pub uvs: Vec<[f32; 2]>,
+    /// Triangle list indices into the vertex arrays, empty for non-indexed meshes
+    pub indices: Vec<u32>,
+    pub color: Color,
+}
+
+impl MeshData {
+    /// Checks that the indices point at vertices and that the normals and UVs, when present,
+    /// match the positions, which [`MeshData::interleaved`] relies on
+    pub fn validate(&self) -> Result<(), String> {
+        let vertex_count = self.positions.len();
+        if let Some(index) = self.indices.iter().find(|&&i| i as usize >= vertex_count) {
+            return Err(format!(
+                "Index {index} is out of range for {vertex_count} vertices"
+            ));
+        }
+        for (name, count) in [("normals", self.normals.len()), ("UVs", self.uvs.len())] {
+            if count != 0 && count != vertex_count {
+                return Err(format!(
+                    "{count} {name} don't match the {vertex_count} positions"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Interleaves the vertex arrays into one buffer and returns it along with its layout
+    pub fn interleaved(&self) -> (Vec<f32>, Vec<VertexAttribute>) {
+        let mut layout = vec![VertexAttribute::POSITION_3D];
+        if !self.normals.is_empty() {
+            layout.push(VertexAttribute::NORMAL);
+        }
+        if !self.uvs.is_empty() {
+            layout.push(VertexAttribute::UV);
+        }
+
+        let mut vertices = Vec::new();
+        for (i, position) in self.positions.iter().enumerate() {
+            vertices.extend_from_slice(position);
+            if !self.normals.is_empty() {
+                vertices.extend_from_slice(&self.normals[i]);
+            }
+            if !self.uvs.is_empty() {
+                vertices.extend_from_slice(&self.uvs[i]);
+            }
+        }
+        (vertices, layout)
+    }
+
+    /// Fills in smooth vertex normals by averaging the normals of the adjacent triangles
+    ///
+    /// Triangles with indices out of range are skipped.
+    pub fn compute_normals(&mut self) {
+        let mut normals = vec![[0.0f32; 3]; self.positions.len()];
+        let triangles: Vec<[usize; 3]> = if self.indices.is_empty() {
+            (0..self.positions.len() / 3)
+                .map(|t| [3 * t, 3 * t + 1, 3 * t + 2])
+                .collect()
+        } else {
+            self.indices
+                .chunks_exact(3)
+                .map(|t| [t[0] as usize, t[1] as usize, t[2] as usize])
+                .filter(|t| t.iter().all(|&i| i < self.positions.len()))
+                .collect()
+        };
+
+        for [a, b, c] in triangles {
+            let (pa, pb, pc) = (self.positions[a], self.positions[b], self.positions[c]);
+            let u = [pb[0] - pa[0], pb[1] - pa[1], pb[2] - pa[2]];
+            let v = [pc[0] - pa[0], pc[1] - pa[1], pc[2] - pa[2]];
+            // not normalized, so bigger triangles weigh more
+            let face = [
+                u[1] * v[2] - u[2] * v[1],
+                u[2] * v[0] - u[0] * v[2],
+                u[0] * v[1] - u[1] * v[0],
+            ];
+            for vertex in [a, b, c] {
+                for axis in 0..3 {
+                    normals[vertex][axis] += face[axis];
+                }
+            }
+        }
+
+        for normal in &mut normals {
+            let length = normal.iter().map(|n| n * n).sum::<f32>().sqrt();
+            if length > 0.0 {
+                normal.iter_mut().for_each(|n| *n /= length);
+            }
+        }
+        self.normals = normals;
+    }
+
+    /// Axis aligned bounding box as `(min, max)`
+    pub fn bounds(&self) -> ([f32; 3], [f32; 3]) {
+        let mut min = [f32::MAX; 3];
+        let mut max = [f32::MIN; 3];
+        for position in &self.positions {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(position[axis]);
+                max[axis] = max[axis].max(position[axis]);
+            }
+        }
+        (min, max)
+    }
+
+    /// Uploads the mesh to the GPU as an indexed triangle list, requires a current GL context
+    pub fn to_mesh(&self) -> Mesh {
+        self.upload(&mut GlBackend)
+    }
+
+    /// Like [`MeshData::to_mesh`], creating the buffers with `backend`
+    pub fn upload(&self, backend: &mut impl RenderBackend) -> Mesh {
+        let (vertices, layout) = self.interleaved();
+        let indices = (!self.indices.is_empty()).then_some(self.indices.as_slice());
+        Mesh::upload(
+            backend,
+            &vertices,
+            &layout,
+            indices,
+            gl::TRIANGLES,
+            self.color,
+        )
+        .with_render_state(RenderState::OPAQUE_3D)
+    }
+}
+
 pub struct Mesh {
-    vao: u32,
-    _vbo: u32,
+    buffers: MeshBuffers,
+    floats_per_vertex: usize,
+    /// Vertex count, or index count for indexed meshes
     vertex_count: usize,
     draw_mode: u32,
     pub color: Color,
+    /// Blending, depth, culling and polygon mode, applied by the renderer before drawing
+    pub render_state: RenderState,
+    /// Program, uniforms and textures to draw with, the renderer's default when `None`
+    pub material: Option<Rc<Material>>,
 }
 
 impl Mesh {
     pub fn from_polygon(polygon: Polygon, draw_mode: gl::types::GLenum, color: Color) -> Self {
         let vertices: Vec<f32> = polygon.iter().flat_map(|p| [p.x, p.y]).collect();
+        Mesh::from_vertices(
+            &vertices,
+            &[VertexAttribute::POSITION_2D],
+            None,
+            draw_mode,
+            color,
+        )
+    }
 
-        // 1. Generate and bind a Vertex Array Object VAO
-        let mut vao = 0;
-        unsafe {
-            gl::GenVertexArrays(1, &mut vao); // Store ID to generated VAO in variable
-            gl::BindVertexArray(vao); // Bind the VAO so opengl knows we setting up
-        }
+    /// Creates a mesh from interleaved vertex data, requires a current GL context
+    ///
+    /// # Arguments
+    /// * `vertices` - interleaved attributes of every vertex, in the order of `layout`
+    /// * `layout` - attributes making up one vertex
+    /// * `indices` - optional element indices, drawn with `glDrawElements` when present
+    /// * `draw_mode` - primitive type like `gl::TRIANGLES`
+    /// * `color` - value for the `u_color` uniform
+    pub fn from_vertices(
+        vertices: &[f32],
+        layout: &[VertexAttribute],
+        indices: Option<&[u32]>,
+        draw_mode: gl::types::GLenum,
+        color: Color,
+    ) -> Self {
+        Mesh::upload(&mut GlBackend, vertices, layout, indices, draw_mode, color)
+    }
 
-        // 2. Generate and bind a vertex buffer object VBO
-        let mut vbo = 0;
-        unsafe {
-            gl::GenBuffers(1, &mut vbo); // Generate one vbo
-            gl::BindBuffer(gl::ARRAY_BUFFER, vbo); // Bind it as an array buffer
-            gl::BufferData(
-                // Upload the data to GPU
-                gl::ARRAY_BUFFER,
-                size_of_val(&vertices) as isize, // Size of data in bytes
-                vertices.as_ptr() as *const _,   // Pointer to data
-                gl::DYNAMIC_DRAW,                // Tell gpu we  update often
-            );
+    /// Like [`Mesh::from_vertices`], creating the buffers with `backend`
+    pub fn upload(
+        backend: &mut impl RenderBackend,
+        vertices: &[f32],
+        layout: &[VertexAttribute],
+        indices: Option<&[u32]>,
+        draw_mode: gl::types::GLenum,
+        color: Color,
+    ) -> Self {
+        let floats_per_vertex = layout.iter().map(|a| a.components as usize).sum();
+        Mesh {
+            buffers: backend.create_mesh_buffers(vertices, layout, indices),
+            floats_per_vertex,
+            vertex_count: match indices {
+                Some(indices) => indices.len(),
+                None => vertices.len() / floats_per_vertex,
+            },
+            draw_mode,
+            color,
+            render_state: RenderState::default(),
+            material: None,
         }
+    }
 
-        // 3. Describe how the data in vbo is laid out
-        unsafe {
-            gl::VertexAttribPointer(
-                0,                           // Attribute index (loc 0 in shader)
-                2,                           // 2 Components per vertex (x, y)
-                gl::FLOAT,                   // Data type is float
-                gl::FALSE,                   // Dont normalize
-                2 * size_of::<f32>() as i32, // Stride, 2 floats per vertex
-                std::ptr::null(),            // offset: start at the beginning
-            );
-            gl::EnableVertexAttribArray(0); // enable specified attribute
+    pub fn with_render_state(self, render_state: RenderState) -> Self {
+        Mesh {
+            render_state,
+            ..self
         }
+    }
+
+    pub fn with_material(self, material: Rc<Material>) -> Self {
         Mesh {
-            vao,
-            _vbo: vbo,
-            vertex_count: vertices.len() / 2,
-            draw_mode,
-            color,
+            material: Some(material),
+            ..self
         }
     }
+
+    /// Replaces the vertex data of a non-indexed mesh, keeping its layout
+    pub fn set_vertices(&mut self, vertices: &[f32]) {
+        self.set_vertices_with(&mut GlBackend, vertices);
+    }
+
+    pub fn set_vertices_with(&mut self, backend: &mut impl RenderBackend, vertices: &[f32]) {
+        assert!(
+            self.buffers.index_buffer.is_none(),
+            "indexed meshes can't change their vertices"
+        );
+        backend.update_vertices(&self.buffers, vertices);
+        self.vertex_count = vertices.len() / self.floats_per_vertex;
+    }
+
     pub fn draw(&self) {
-        unsafe {
-            gl::BindVertexArray(self.vao);
-            gl::DrawArrays(self.draw_mode, 0, self.vertex_count as i32);
+        self.draw_with(&mut GlBackend);
+    }
+
+    pub fn draw_with(&self, backend: &mut impl RenderBackend) {
+        backend.draw(&self.buffers, self.draw_mode, self.vertex_count);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn triangle(indices: Vec<u32>) -> MeshData {
+        MeshData {
+            positions: vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+            normals: Vec::new(),
+            uvs: Vec::new(),
+            indices,
+            color: (1.0, 1.0, 1.0).into(),
         }
     }
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(triangle(vec![0, 1, 2]).validate(), Ok(()));
+        assert!(triangle(vec![0, 1, 3]).validate().is_err());
+
+        let mut short_uvs = triangle(Vec::new());
+        short_uvs.uvs = vec![[0.0, 0.0]];
+        assert!(short_uvs.validate().is_err());
+    }
+
+    #[test]
+    fn test_compute_normals_skips_invalid_triangles() {
+        let mut mesh = triangle(vec![0, 1, 2, 0, 1, 7]);
+        mesh.compute_normals();
+        assert_eq!(mesh.normals, vec![[0.0, 0.0, 1.0]; 3]);
+    }
 }
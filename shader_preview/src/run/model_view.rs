@@ -0,0 +1,191 @@
+//! 3D preview mode for models loaded from OBJ or glTF files
+//!
+//! The model is centered and viewed by an orbiting camera, the vertex shader receives
+//! `u_model`, `u_view` and `u_projection` matrices.
+use crate::import::{gltf::load_gltf, obj::load_obj};
+use crate::run::backend::{GlBackend, RenderBackend};
+use crate::run::material::UniformValue;
+use crate::run::mesh::{Mesh, MeshData};
+use crate::run::shader_program::ShaderProgram;
+use std::f64::consts::FRAC_PI_2;
+use std::path::Path;
+use vatnar_linalg::{Matrix, Vector3};
+
+pub(crate) struct ModelView {
+    program: ShaderProgram,
+    meshes: Vec<Mesh>,
+    /// Center and radius of the bounding sphere of the model
+    center: Vector3<f64>,
+    radius: f64,
+    /// Orbit camera angles in radians, and distance in model radii
+    yaw: f64,
+    pitch: f64,
+    distance: f64,
+}
+
+impl ModelView {
+    /// Loads a `.obj`, `.gltf` or `.glb` file and uploads it, requires a current GL context
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase);
+        let data = match extension.as_deref() {
+            Some("obj") => load_obj(path)?,
+            Some("gltf" | "glb") => load_gltf(path)?,
+            _ => return Err(format!("Unsupported model format '{}'", path.display()).into()),
+        };
+        if data.is_empty() {
+            return Err(format!("'{}' contains no triangle meshes", path.display()).into());
+        }
+        Ok(ModelView::new(&mut GlBackend, &data)?)
+    }
+
+    /// Uploads the meshes of a model with `backend`, `data` must not be empty
+    pub fn new(backend: &mut impl RenderBackend, data: &[MeshData]) -> Result<Self, String> {
+        let mut min = [f32::MAX; 3];
+        let mut max = [f32::MIN; 3];
+        for mesh in data {
+            let (mesh_min, mesh_max) = mesh.bounds();
+            for axis in 0..3 {
+                min[axis] = min[axis].min(mesh_min[axis]);
+                max[axis] = max[axis].max(mesh_max[axis]);
+            }
+        }
+        let center = Vector3::new(
+            (min[0] + max[0]) as f64 / 2.0,
+            (min[1] + max[1]) as f64 / 2.0,
+            (min[2] + max[2]) as f64 / 2.0,
+        );
+        let extent = Vector3::new(
+            (max[0] - min[0]) as f64,
+            (max[1] - min[1]) as f64,
+            (max[2] - min[2]) as f64,
+        );
+        let radius = (extent.mag() / 2.0).max(1e-3);
+
+        let program = backend
+            .create_program(
+                include_str!("../vertex_shader_3d.glsl"),
+                include_str!("../fragment_shader_3d.glsl"),
+            )
+            .map_err(|e| e.to_string())?;
+
+        Ok(ModelView {
+            program,
+            meshes: data.iter().map(|mesh| mesh.upload(backend)).collect(),
+            center,
+            radius,
+            yaw: 0.0,
+            pitch: 0.3,
+            distance: 2.5,
+        })
+    }
+
+    /// Rotates the camera around the model, `pitch` is clamped so it can't flip over the poles
+    pub fn orbit(&mut self, yaw: f64, pitch: f64) {
+        self.yaw += yaw;
+        self.pitch = (self.pitch + pitch).clamp(-FRAC_PI_2 + 0.01, FRAC_PI_2 - 0.01);
+    }
+
+    /// Moves the camera closer for positive `amount`, further away for negative
+    pub fn zoom(&mut self, amount: f64) {
+        self.distance = (self.distance * 0.9f64.powf(amount)).clamp(0.1, 100.0);
+    }
+
+    pub fn render(&self, backend: &mut impl RenderBackend, aspect: f64, time: f64) {
+        let distance = self.distance * self.radius;
+        let eye = self.center
+            + Vector3::new(
+                self.pitch.cos() * self.yaw.sin(),
+                self.pitch.sin(),
+                self.pitch.cos() * self.yaw.cos(),
+            ) * distance;
+
+        let model = Matrix::identity(4);
+        let view = Matrix::look_at(eye, self.center, Vector3::new(0.0, 1.0, 0.0));
+        let projection = Matrix::perspective(
+            45f64.to_radians(),
+            aspect,
+            (distance - self.radius).max(distance * 0.01),
+            distance + self.radius * 2.0,
+        );
+
+        let program = &self.program;
+        backend.use_program(program);
+        backend.set_uniform(program, "u_time", UniformValue::Float(time as f32));
+        for (name, matrix) in [
+            ("u_model", &model),
+            ("u_view", &view),
+            ("u_projection", &projection),
+        ] {
+            let data = matrix.to_column_major_f32().try_into().expect("4x4 matrix");
+            backend.set_uniform(program, name, UniformValue::Mat4(data));
+        }
+
+        for mesh in &self.meshes {
+            let (r, g, b) = mesh.color.into();
+            backend.set_uniform(program, "u_color", UniformValue::Vec3([r, g, b]));
+            backend.apply_render_state(&mesh.render_state);
+            mesh.draw_with(backend);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::run::backend::{Call, RecordingBackend};
+    use crate::run::render_state::RenderState;
+
+    #[test]
+    fn test_render_uniforms() {
+        let mut backend = RecordingBackend::default();
+        let data = MeshData {
+            positions: vec![[-1.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 2.0, 0.0]],
+            normals: Vec::new(),
+            uvs: Vec::new(),
+            indices: Vec::new(),
+            color: (1.0, 0.5, 0.0).into(),
+        };
+        let view = ModelView::new(&mut backend, &[data]).unwrap();
+        assert_eq!(view.center, Vector3::new(0.0, 1.0, 0.0));
+        backend.take_calls();
+
+        view.render(&mut backend, 2.0, 1.5);
+        let calls = backend.take_calls();
+        assert_eq!(calls[0], Call::UseProgram(view.program.id));
+        let uniform = |name: &str| {
+            calls.iter().find_map(|call| match call {
+                Call::SetUniform {
+                    name: uniform,
+                    value,
+                    ..
+                } if uniform == name => Some(*value),
+                _ => None,
+            })
+        };
+        assert_eq!(uniform("u_time"), Some(UniformValue::Float(1.5)));
+        assert_eq!(
+            uniform("u_color"),
+            Some(UniformValue::Vec3([1.0, 0.5, 0.0]))
+        );
+        let Some(UniformValue::Mat4(model)) = uniform("u_model") else {
+            panic!("u_model isn't a 4x4 matrix");
+        };
+        assert_eq!(model, Matrix::identity(4).to_column_major_f32()[..]);
+        // the camera looks at the center from the front and above, so it's in front of it
+        let Some(UniformValue::Mat4(view_matrix)) = uniform("u_view") else {
+            panic!("u_view isn't a 4x4 matrix");
+        };
+        let center_z = view_matrix[2] * 0.0 + view_matrix[6] * 1.0 + view_matrix[14];
+        assert!(center_z < 0.0);
+        assert!(matches!(
+            uniform("u_projection"),
+            Some(UniformValue::Mat4(_))
+        ));
+
+        assert!(calls.contains(&Call::ApplyRenderState(RenderState::OPAQUE_3D)));
+        assert!(matches!(calls.last(), Some(Call::Draw { count: 3, .. })));
+    }
+}
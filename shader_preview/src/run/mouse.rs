@@ -0,0 +1,75 @@
+//! Cursor tracking for panning and the mouse uniforms
+use vatnar_linalg::Vector2;
+
+/// Cursor position and left button state, positions are framebuffer pixels with the origin
+/// in the top left corner
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Mouse {
+    pub position: Vector2<f64>,
+    /// Where the left button was last pressed
+    pub click: Vector2<f64>,
+    /// Last cursor position while the button was held
+    pub drag: Vector2<f64>,
+    pub pressed: bool,
+}
+
+impl Mouse {
+    /// Moves the cursor, returning how far it moved if the button is held
+    pub fn move_to(&mut self, position: Vector2<f64>) -> Option<Vector2<f64>> {
+        let previous = std::mem::replace(&mut self.position, position);
+        if self.pressed {
+            self.drag = position;
+        }
+        self.pressed
+            .then(|| Vector2::new(self.position.x - previous.x, self.position.y - previous.y))
+    }
+
+    pub fn press(&mut self) {
+        self.pressed = true;
+        self.click = self.position;
+        self.drag = self.position;
+    }
+
+    pub fn release(&mut self) {
+        self.pressed = false;
+    }
+
+    /// Value of the Shadertoy `iMouse` uniform
+    ///
+    /// `xy` is the cursor as of the last drag, `zw` the last click, both with the origin
+    /// in the bottom left corner. `z` is negative while the button is up, `w` is negative
+    /// except in the frame the button was pressed, approximated here as while it is held.
+    pub fn shadertoy(&self, height: f64) -> [f32; 4] {
+        let flip = |p: Vector2<f64>| [p.x as f32, (height - p.y) as f32];
+        let [x, y] = flip(self.drag);
+        let [click_x, click_y] = flip(self.click);
+        if self.pressed {
+            [x, y, click_x, click_y]
+        } else {
+            [x, y, -click_x, -click_y]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drag() {
+        let mut mouse = Mouse::default();
+        assert_eq!(mouse.move_to(Vector2::new(10.0, 10.0)), None);
+
+        mouse.press();
+        assert_eq!(
+            mouse.move_to(Vector2::new(15.0, 8.0)),
+            Some(Vector2::new(5.0, -2.0))
+        );
+        assert_eq!(mouse.shadertoy(100.0), [15.0, 92.0, 10.0, 90.0]);
+
+        // after releasing xy stays at the end of the drag
+        mouse.release();
+        mouse.move_to(Vector2::new(50.0, 50.0));
+        assert_eq!(mouse.shadertoy(100.0), [15.0, 92.0, -10.0, -90.0]);
+    }
+}
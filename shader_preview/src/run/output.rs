@@ -0,0 +1,120 @@
+//! How the rendered frame becomes the colors on screen
+//!
+//! By default shaders draw straight into the 8 bit window. With an HDR format, tonemapping or
+//! sRGB output the frame is rendered offscreen first, and a final pass scales it by the
+//! exposure, maps it into displayable range and encodes it for the screen.
+use crate::run::material::{UniformValue, UniformValues};
+use crate::run::texture::TextureFormat;
+use serde::Deserialize;
+use serde::de::IntoDeserializer;
+use std::str::FromStr;
+
+/// Operator compressing HDR colors into `0..1`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Tonemap {
+    /// Colors are clamped
+    #[default]
+    None,
+    /// `c / (1 + c)`, keeps hues but washes out highlights
+    Reinhard,
+    /// Narkowicz's fit of the ACES reference rendering transform
+    Aces,
+    /// John Hable's Uncharted 2 curve
+    Filmic,
+}
+
+impl Tonemap {
+    /// Value of `u_tonemap` in the output shader
+    fn index(self) -> i32 {
+        match self {
+            Tonemap::None => 0,
+            Tonemap::Reinhard => 1,
+            Tonemap::Aces => 2,
+            Tonemap::Filmic => 3,
+        }
+    }
+}
+
+impl FromStr for Tonemap {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Tonemap::deserialize(name.into_deserializer())
+            .map_err(|_: serde::de::value::Error| format!("Unknown tonemapping operator '{name}'"))
+    }
+}
+
+impl FromStr for TextureFormat {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        TextureFormat::deserialize(name.into_deserializer())
+            .map_err(|_: serde::de::value::Error| format!("Unknown texture format '{name}'"))
+    }
+}
+
+/// The `[output]` section of the config file
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutputConfig {
+    /// Format the frame and the post-processing passes render into
+    pub format: TextureFormat,
+    pub tonemap: Tonemap,
+    /// Colors are multiplied by this before tonemapping
+    pub exposure: f32,
+    /// Shaders work in linear light: images are decoded from sRGB when sampled and the frame
+    /// is encoded to sRGB for display
+    pub srgb: bool,
+}
+
+impl Default for OutputConfig {
+    fn default() -> Self {
+        OutputConfig {
+            format: TextureFormat::Rgba8,
+            tonemap: Tonemap::None,
+            exposure: 1.0,
+            srgb: false,
+        }
+    }
+}
+
+impl OutputConfig {
+    /// Whether the frame needs the final output pass, the default draws straight to the window
+    pub fn needs_output_pass(&self) -> bool {
+        *self != OutputConfig::default()
+    }
+
+    /// Uniforms of the output shader
+    pub(crate) fn uniforms(&self) -> UniformValues {
+        UniformValues::default()
+            .with("u_exposure", UniformValue::Float(self.exposure))
+            .with("u_tonemap", UniformValue::Int(self.tonemap.index()))
+            .with("u_srgb", UniformValue::Int(self.srgb as i32))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_output_config() {
+        let config: OutputConfig =
+            toml::from_str("format = \"rgba16f\"\ntonemap = \"aces\"\nsrgb = true").unwrap();
+        assert_eq!(config.format, TextureFormat::Rgba16F);
+        assert_eq!(config.tonemap, Tonemap::Aces);
+        assert_eq!(config.exposure, 1.0);
+        assert!(config.needs_output_pass());
+        assert!(!OutputConfig::default().needs_output_pass());
+
+        assert!(toml::from_str::<OutputConfig>("format = \"rgb565\"").is_err());
+    }
+
+    #[test]
+    fn test_parse_names() {
+        assert_eq!("filmic".parse(), Ok(Tonemap::Filmic));
+        assert_eq!("rgba32f".parse(), Ok(TextureFormat::Rgba32F));
+        assert!("hable".parse::<Tonemap>().is_err());
+    }
+}
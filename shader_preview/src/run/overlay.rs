@@ -0,0 +1,161 @@
+//! Screen space overlay for text and solid rectangles, drawn on top of the scene
+//!
+//! Shapes are collected with [`Overlay::text`] and [`Overlay::rect`] during a frame, and drawn
+//! in one batch by [`Overlay::draw`]. Positions are in pixels with the origin in the top left
+//! corner.
+use crate::run::mesh::{Color, Mesh, VertexAttribute};
+use crate::run::shader_program::{ShaderProgram, Uniform};
+use crate::run::texture::{Filter, Texture, TextureOptions, Wrap};
+use font::FontAtlas;
+
+mod font;
+
+/// RGBA color of overlay shapes
+pub type Rgba = [f32; 4];
+
+pub const TEXT_COLOR: Rgba = [0.9, 0.9, 0.9, 1.0];
+pub const BACKGROUND_COLOR: Rgba = [0.0, 0.0, 0.0, 0.6];
+
+pub struct Overlay {
+    program: ShaderProgram,
+    screen_uniform: Uniform,
+    font_uniform: Uniform,
+    atlas: FontAtlas,
+    font_texture: Texture,
+    mesh: Mesh,
+    /// Interleaved position, uv and color of the batched triangles
+    vertices: Vec<f32>,
+    /// Integer scale of the font, for high DPI screens
+    pub scale: f32,
+}
+
+impl Overlay {
+    /// Creates the overlay, requires a current GL context
+    pub fn new() -> Result<Self, String> {
+        let program = ShaderProgram::from_source(
+            include_str!("../vertex_shader_overlay.glsl"),
+            include_str!("../fragment_shader_overlay.glsl"),
+        )?;
+        let atlas = FontAtlas::new();
+        let font_texture = Texture::upload(
+            &atlas.data,
+            &TextureOptions {
+                min_filter: Filter::Nearest,
+                mag_filter: Filter::Nearest,
+                wrap: Wrap::ClampToEdge,
+                mipmaps: false,
+                srgb: false,
+            },
+        );
+        let mesh = Mesh::from_vertices(
+            &[],
+            &[
+                VertexAttribute::POSITION_2D,
+                VertexAttribute::UV,
+                VertexAttribute::COLOR,
+            ],
+            None,
+            gl::TRIANGLES,
+            Color::from((1.0, 1.0, 1.0)),
+        );
+
+        Ok(Overlay {
+            screen_uniform: program.get_uniform("u_screen"),
+            font_uniform: program.get_uniform("u_font"),
+            program,
+            atlas,
+            font_texture,
+            mesh,
+            vertices: Vec::new(),
+            scale: 1.0,
+        })
+    }
+
+    /// Height of one line of text in pixels
+    pub fn line_height(&self) -> f32 {
+        self.atlas.glyph_size().1 as f32 * self.scale
+    }
+
+    /// Size of `text` in pixels
+    pub fn text_size(&self, text: &str) -> (f32, f32) {
+        let (width, height) = self.atlas.text_size(text);
+        (width as f32 * self.scale, height as f32 * self.scale)
+    }
+
+    /// Adds text with its top left corner at `x`, `y`, lines are split at `\n`
+    pub fn text(&mut self, x: f32, y: f32, text: &str, color: Rgba) {
+        let (glyph_width, glyph_height) = self.atlas.glyph_size();
+        let (width, height) = (
+            glyph_width as f32 * self.scale,
+            glyph_height as f32 * self.scale,
+        );
+        let advance = self.atlas.advance() as f32 * self.scale;
+
+        for (line_index, line) in text.lines().enumerate() {
+            let top = y + line_index as f32 * height;
+            for (column, c) in line.chars().enumerate() {
+                if c == ' ' {
+                    continue;
+                }
+                let left = x + column as f32 * advance;
+                let uv = self.atlas.glyph_uv(c);
+                self.quad([left, top, left + width, top + height], uv, color);
+            }
+        }
+    }
+
+    /// Adds text on a translucent background box
+    pub fn label(&mut self, x: f32, y: f32, text: &str) {
+        let padding = 4.0 * self.scale;
+        let (width, height) = self.text_size(text);
+        self.rect(
+            x,
+            y,
+            width + padding * 2.0,
+            height + padding * 2.0,
+            BACKGROUND_COLOR,
+        );
+        self.text(x + padding, y + padding, text, TEXT_COLOR);
+    }
+
+    /// Adds a filled rectangle
+    pub fn rect(&mut self, x: f32, y: f32, width: f32, height: f32, color: Rgba) {
+        let [u, v] = self.atlas.white_uv();
+        self.quad([x, y, x + width, y + height], [u, v, u, v], color);
+    }
+
+    fn quad(&mut self, [x0, y0, x1, y1]: [f32; 4], [u0, v0, u1, v1]: [f32; 4], color: Rgba) {
+        for (x, y, u, v) in [
+            (x0, y0, u0, v0),
+            (x0, y1, u0, v1),
+            (x1, y1, u1, v1),
+            (x0, y0, u0, v0),
+            (x1, y1, u1, v1),
+            (x1, y0, u1, v0),
+        ] {
+            self.vertices.extend_from_slice(&[x, y, u, v]);
+            self.vertices.extend_from_slice(&color);
+        }
+    }
+
+    /// Draws everything added since the last call on top of the current framebuffer
+    ///
+    /// # Arguments
+    /// * `width`, `height` - size of the framebuffer in pixels
+    pub fn draw(&mut self, width: f32, height: f32) {
+        if self.vertices.is_empty() {
+            return;
+        }
+        self.mesh.set_vertices(&self.vertices);
+        self.vertices.clear();
+
+        self.program.use_program();
+        self.font_texture.bind(0);
+        self.mesh.render_state.apply();
+        unsafe {
+            gl::Uniform2f(*self.screen_uniform, width, height);
+            gl::Uniform1i(*self.font_uniform, 0);
+        }
+        self.mesh.draw();
+    }
+}
@@ -0,0 +1,144 @@
+//! Bitmap font atlas for the overlay, built from the `embedded-graphics` mono fonts
+use crate::run::texture::{Pixels, TextureData};
+use embedded_graphics::image::GetPixel;
+use embedded_graphics::mono_font::{MonoFont, ascii::FONT_6X10};
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::prelude::{OriginDimensions, Point};
+
+/// Glyph images of a font packed into one texture, rows are stored top first
+///
+/// One extra row of white texels at the bottom is used to draw solid rectangles with the
+/// same texture.
+pub(crate) struct FontAtlas {
+    font: &'static MonoFont<'static>,
+    pub data: TextureData,
+}
+
+impl FontAtlas {
+    pub fn new() -> Self {
+        Self::from_font(&FONT_6X10)
+    }
+
+    pub fn from_font(font: &'static MonoFont<'static>) -> Self {
+        let size = font.image.size();
+        let (width, height) = (size.width, size.height + 1);
+
+        let mut pixels = vec![255; (width * height * 4) as usize];
+        for y in 0..size.height {
+            for x in 0..width {
+                let on = font.image.pixel(Point::new(x as i32, y as i32)) == Some(BinaryColor::On);
+                let alpha = if on { 255 } else { 0 };
+                let index = ((y * width + x) * 4 + 3) as usize;
+                pixels[index] = alpha;
+            }
+        }
+
+        FontAtlas {
+            font,
+            data: TextureData {
+                width,
+                height,
+                pixels: Pixels::Rgba8(pixels),
+            },
+        }
+    }
+
+    /// Size of one glyph in pixels
+    pub fn glyph_size(&self) -> (u32, u32) {
+        (
+            self.font.character_size.width,
+            self.font.character_size.height,
+        )
+    }
+
+    /// Horizontal distance from one glyph to the next
+    pub fn advance(&self) -> u32 {
+        self.font.character_size.width + self.font.character_spacing
+    }
+
+    /// Size of `text` in pixels, lines are split at `\n`
+    pub fn text_size(&self, text: &str) -> (u32, u32) {
+        let columns = text.lines().map(|l| l.chars().count()).max().unwrap_or(0);
+        let lines = text.lines().count().max(1);
+        (
+            columns as u32 * self.advance(),
+            lines as u32 * self.glyph_size().1,
+        )
+    }
+
+    /// Texture coordinates of a glyph as `[u0, v0, u1, v1]`, top left to bottom right
+    ///
+    /// Characters missing from the font get its replacement glyph.
+    pub fn glyph_uv(&self, c: char) -> [f32; 4] {
+        let (glyph_width, glyph_height) = self.glyph_size();
+        let per_row = (self.data.width / glyph_width).max(1);
+        let index = self.font.glyph_mapping.index(c) as u32;
+        let (x, y) = (
+            (index % per_row) * glyph_width,
+            (index / per_row) * glyph_height,
+        );
+
+        let (width, height) = (self.data.width as f32, self.data.height as f32);
+        [
+            x as f32 / width,
+            y as f32 / height,
+            (x + glyph_width) as f32 / width,
+            (y + glyph_height) as f32 / height,
+        ]
+    }
+
+    /// Texture coordinate of a white texel, for solid shapes
+    pub fn white_uv(&self) -> [f32; 2] {
+        [
+            0.5 / self.data.width as f32,
+            1.0 - 0.5 / self.data.height as f32,
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alpha_at(atlas: &FontAtlas, u: f32, v: f32) -> u8 {
+        let Pixels::Rgba8(pixels) = &atlas.data.pixels else {
+            unreachable!()
+        };
+        let x = (u * atlas.data.width as f32) as u32;
+        let y = (v * atlas.data.height as f32) as u32;
+        pixels[((y * atlas.data.width + x) * 4 + 3) as usize]
+    }
+
+    #[test]
+    fn test_glyphs() {
+        let atlas = FontAtlas::new();
+        let (width, height) = atlas.glyph_size();
+        assert_eq!((width, height), (6, 10));
+
+        // a space is empty, a block of `#` is not
+        let count = |c: char| {
+            let [u0, v0, u1, v1] = atlas.glyph_uv(c);
+            let mut on = 0;
+            for y in 0..height {
+                for x in 0..width {
+                    let u = u0 + (u1 - u0) * (x as f32 + 0.5) / width as f32;
+                    let v = v0 + (v1 - v0) * (y as f32 + 0.5) / height as f32;
+                    on += (alpha_at(&atlas, u, v) > 0) as u32;
+                }
+            }
+            on
+        };
+        assert_eq!(count(' '), 0);
+        assert!(count('#') > 10);
+
+        let [u, v] = atlas.white_uv();
+        assert_eq!(alpha_at(&atlas, u, v), 255);
+    }
+
+    #[test]
+    fn test_text_size() {
+        let atlas = FontAtlas::new();
+        assert_eq!(atlas.text_size("abc"), (18, 10));
+        assert_eq!(atlas.text_size("a\nlonger"), (36, 20));
+    }
+}
@@ -0,0 +1,270 @@
+//! Fullscreen post-processing passes run on the rendered frame
+//!
+//! With passes configured the scene is rendered into an offscreen target. Each pass is a
+//! fragment shader drawing a fullscreen triangle and receives the output of the previous one:
+//!
+//! ```glsl
+//! in vec2 v_uv;                // 0..1 across the screen
+//! uniform sampler2D u_texture; // previous pass, or the scene for the first one
+//! uniform vec2 u_resolution;   // in pixels
+//! uniform float u_time;
+//! ```
+//!
+//! The passes render into targets of the configured [`OutputConfig::format`], the output pass
+//! runs last when needed. The overlay is drawn afterwards, so text stays readable.
+use crate::run::backend::GlBackend;
+use crate::run::fullscreen::FullscreenTriangle;
+use crate::run::material::UniformValues;
+use crate::run::output::OutputConfig;
+use crate::run::render_state::{BlendMode, RenderState};
+use crate::run::render_target::RenderTarget;
+use crate::run::shader_program::{ShaderProgram, Uniform};
+use crate::run::texture::TextureFormat;
+use serde::{Deserialize, Deserializer};
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Effects that come with ShaderPreview
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuiltinPass {
+    Bloom,
+    Blur,
+    Vignette,
+    Crt,
+    ChromaticAberration,
+}
+
+impl BuiltinPass {
+    pub const ALL: [BuiltinPass; 5] = [
+        BuiltinPass::Bloom,
+        BuiltinPass::Blur,
+        BuiltinPass::Vignette,
+        BuiltinPass::Crt,
+        BuiltinPass::ChromaticAberration,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BuiltinPass::Bloom => "bloom",
+            BuiltinPass::Blur => "blur",
+            BuiltinPass::Vignette => "vignette",
+            BuiltinPass::Crt => "crt",
+            BuiltinPass::ChromaticAberration => "chromatic_aberration",
+        }
+    }
+
+    fn fragment_source(self) -> &'static str {
+        match self {
+            BuiltinPass::Bloom => include_str!("../fragment_shader_post_bloom.glsl"),
+            BuiltinPass::Blur => include_str!("../fragment_shader_post_blur.glsl"),
+            BuiltinPass::Vignette => include_str!("../fragment_shader_post_vignette.glsl"),
+            BuiltinPass::Crt => include_str!("../fragment_shader_post_crt.glsl"),
+            BuiltinPass::ChromaticAberration => {
+                include_str!("../fragment_shader_post_chromatic_aberration.glsl")
+            }
+        }
+    }
+}
+
+/// Where the fragment shader of a pass comes from, written as the name of a builtin pass or
+/// the path of a user written fragment shader
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PassSource {
+    Builtin(BuiltinPass),
+    File(PathBuf),
+}
+
+impl FromStr for PassSource {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        if name.is_empty() {
+            return Err("Empty post-processing pass".to_string());
+        }
+        Ok(BuiltinPass::ALL
+            .into_iter()
+            .find(|pass| pass.name() == name)
+            .map_or_else(|| PassSource::File(name.into()), PassSource::Builtin))
+    }
+}
+
+impl<'de> Deserialize<'de> for PassSource {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl fmt::Display for PassSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PassSource::Builtin(pass) => write!(f, "{}", pass.name()),
+            PassSource::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// The `[post]` section of the config file
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PostConfig {
+    /// Passes in the order they run
+    pub passes: Vec<PassSource>,
+}
+
+struct PostPass {
+    program: ShaderProgram,
+    texture_uniform: Uniform,
+    resolution_uniform: Uniform,
+    time_uniform: Uniform,
+    /// Uniforms specific to the pass
+    uniforms: UniformValues,
+}
+
+impl PostPass {
+    fn load(source: &PassSource) -> Result<Self, String> {
+        let fragment_source = match source {
+            PassSource::Builtin(pass) => pass.fragment_source().to_string(),
+            PassSource::File(path) => std::fs::read_to_string(path).map_err(|e| {
+                format!(
+                    "Couldn't read post-processing pass '{}': {e}",
+                    path.display()
+                )
+            })?,
+        };
+        PostPass::from_source(&fragment_source, UniformValues::default())
+    }
+
+    fn from_source(fragment_source: &str, uniforms: UniformValues) -> Result<Self, String> {
+        let program = ShaderProgram::from_source(
+            include_str!("../vertex_shader_post.glsl"),
+            fragment_source,
+        )?;
+        Ok(PostPass {
+            texture_uniform: program.get_uniform("u_texture"),
+            resolution_uniform: program.get_uniform("u_resolution"),
+            time_uniform: program.get_uniform("u_time"),
+            program,
+            uniforms,
+        })
+    }
+}
+
+/// A chain of post-processing passes and the offscreen targets they ping-pong between
+pub struct PostChain {
+    passes: Vec<PostPass>,
+    format: TextureFormat,
+    /// Created on the first frame and recreated when the framebuffer size changes
+    targets: Vec<RenderTarget>,
+    triangle: FullscreenTriangle,
+}
+
+impl PostChain {
+    /// Compiles the passes, followed by the output pass if `output` needs one, requires a
+    /// current GL context
+    pub fn load(sources: &[PassSource], output: &OutputConfig) -> Result<Self, String> {
+        let mut passes = sources
+            .iter()
+            .map(PostPass::load)
+            .collect::<Result<Vec<_>, _>>()?;
+        if output.needs_output_pass() {
+            passes.push(PostPass::from_source(
+                include_str!("../fragment_shader_post_output.glsl"),
+                output.uniforms(),
+            )?);
+        }
+        Ok(PostChain {
+            passes,
+            format: output.format,
+            targets: Vec::new(),
+            triangle: FullscreenTriangle::new(),
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.passes.is_empty()
+    }
+
+    /// Redirects drawing into the offscreen target, call before drawing the frame
+    ///
+    /// Does nothing without passes.
+    pub fn begin(&mut self, width: u32, height: u32) -> Result<(), String> {
+        if self.is_empty() {
+            return Ok(());
+        }
+        let targets = self.passes.len().min(2);
+        if self.targets.len() != targets || self.targets.iter().any(|t| t.size() != (width, height))
+        {
+            self.targets = (0..targets)
+                .map(|_| RenderTarget::new(width.max(1), height.max(1), self.format))
+                .collect::<Result<_, _>>()?;
+        }
+        self.targets[0].bind();
+        Ok(())
+    }
+
+    /// Runs the passes on the frame drawn since [`PostChain::begin`], the last one drawing
+    /// into the window
+    pub fn finish(&self, time: f64) {
+        if self.is_empty() {
+            return;
+        }
+        RenderState::default().with_blend(BlendMode::Opaque).apply();
+
+        for (i, pass) in self.passes.iter().enumerate() {
+            let input = &self.targets[i % 2];
+            if i + 1 == self.passes.len() {
+                RenderTarget::bind_window();
+            } else {
+                self.targets[(i + 1) % 2].bind();
+            }
+
+            let (width, height) = input.size();
+            pass.program.use_program();
+            input.color.bind(0);
+            unsafe {
+                gl::Uniform1i(*pass.texture_uniform, 0);
+                gl::Uniform2f(*pass.resolution_uniform, width as f32, height as f32);
+                gl::Uniform1f(*pass.time_uniform, time as f32);
+            }
+            pass.uniforms.apply(&mut GlBackend, &pass.program);
+            self.triangle.draw();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pass_source() {
+        assert_eq!("crt".parse(), Ok(PassSource::Builtin(BuiltinPass::Crt)));
+        assert_eq!(
+            "chromatic_aberration".parse(),
+            Ok(PassSource::Builtin(BuiltinPass::ChromaticAberration))
+        );
+        assert_eq!(
+            "shaders/sepia.glsl".parse(),
+            Ok(PassSource::File("shaders/sepia.glsl".into()))
+        );
+        assert!("".parse::<PassSource>().is_err());
+
+        for pass in BuiltinPass::ALL {
+            assert_eq!(PassSource::Builtin(pass).to_string(), pass.name());
+        }
+    }
+
+    #[test]
+    fn test_parse_post_config() {
+        let config: PostConfig = toml::from_str("passes = [\"bloom\", \"my_pass.glsl\"]").unwrap();
+        assert_eq!(
+            config.passes,
+            [
+                PassSource::Builtin(BuiltinPass::Bloom),
+                PassSource::File("my_pass.glsl".into())
+            ]
+        );
+    }
+}
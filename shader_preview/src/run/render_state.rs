@@ -0,0 +1,146 @@
+//! Fixed function GL state a mesh is drawn with
+//!
+//! Every blend preset keeps the alpha channel of the framebuffer opaque, translucent draws
+//! would otherwise make screenshots see-through.
+use crate::run::backend::{GlBackend, RenderBackend};
+use serde::Deserialize;
+
+/// How the color of a draw is combined with the framebuffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BlendMode {
+    /// Blending disabled, the draw replaces the framebuffer
+    Opaque,
+    /// Classic `src * a + dst * (1 - a)`
+    #[default]
+    Alpha,
+    /// For colors already multiplied by their alpha, `src + dst * (1 - a)`
+    Premultiplied,
+    /// `src * a + dst`, for glows and particles
+    Additive,
+    /// `src * dst`, for shadows and tinting
+    Multiply,
+}
+
+impl BlendMode {
+    /// `glBlendFuncSeparate` factors as `(src rgb, dst rgb, src alpha, dst alpha)`, `None`
+    /// when blending is disabled
+    pub fn factors(self) -> Option<(u32, u32, u32, u32)> {
+        match self {
+            BlendMode::Opaque => None,
+            BlendMode::Alpha => Some((
+                gl::SRC_ALPHA,
+                gl::ONE_MINUS_SRC_ALPHA,
+                gl::ONE,
+                gl::ONE_MINUS_SRC_ALPHA,
+            )),
+            BlendMode::Premultiplied => Some((
+                gl::ONE,
+                gl::ONE_MINUS_SRC_ALPHA,
+                gl::ONE,
+                gl::ONE_MINUS_SRC_ALPHA,
+            )),
+            BlendMode::Additive => Some((gl::SRC_ALPHA, gl::ONE, gl::ZERO, gl::ONE)),
+            BlendMode::Multiply => Some((gl::DST_COLOR, gl::ZERO, gl::ZERO, gl::ONE)),
+        }
+    }
+}
+
+/// Which triangle faces are discarded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CullMode {
+    #[default]
+    None,
+    Back,
+    Front,
+}
+
+/// How triangles are rasterized
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolygonMode {
+    #[default]
+    Fill,
+    /// Only the edges, to inspect the tessellation
+    Wireframe,
+    Points,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RenderState {
+    pub blend: BlendMode,
+    /// Tests against and writes to the depth buffer
+    pub depth_test: bool,
+    pub cull: CullMode,
+    pub polygon_mode: PolygonMode,
+}
+
+impl RenderState {
+    /// Opaque and depth tested, for 3D models
+    pub const OPAQUE_3D: RenderState = RenderState {
+        blend: BlendMode::Opaque,
+        depth_test: true,
+        cull: CullMode::None,
+        polygon_mode: PolygonMode::Fill,
+    };
+
+    /// Whether the result doesn't depend on the order of draws, only for opaque depth tested
+    /// draws, without depth testing the last draw ends up on top
+    pub fn is_order_independent(&self) -> bool {
+        self.blend == BlendMode::Opaque && self.depth_test
+    }
+
+    pub fn with_blend(self, blend: BlendMode) -> Self {
+        RenderState { blend, ..self }
+    }
+
+    pub fn with_polygon_mode(self, polygon_mode: PolygonMode) -> Self {
+        RenderState {
+            polygon_mode,
+            ..self
+        }
+    }
+
+    /// Sets the GL state, requires a current GL context
+    pub fn apply(&self) {
+        GlBackend.apply_render_state(self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blend_keeps_framebuffer_opaque() {
+        // both alpha factor pairs turn a destination alpha of 1 into 1 again
+        for blend in [
+            BlendMode::Alpha,
+            BlendMode::Premultiplied,
+            BlendMode::Additive,
+            BlendMode::Multiply,
+        ] {
+            let (_, _, src_alpha, dst_alpha) = blend.factors().unwrap();
+            let keeps_alpha = matches!(
+                (src_alpha, dst_alpha),
+                (gl::ONE, gl::ONE_MINUS_SRC_ALPHA) | (gl::ZERO, gl::ONE)
+            );
+            assert!(keeps_alpha, "{blend:?}");
+        }
+        assert_eq!(BlendMode::Opaque.factors(), None);
+    }
+
+    #[test]
+    fn test_parse_render_state() {
+        let state: RenderState =
+            toml::from_str("blend = \"additive\"\npolygon_mode = \"wireframe\"").unwrap();
+        assert_eq!(
+            state,
+            RenderState::default()
+                .with_blend(BlendMode::Additive)
+                .with_polygon_mode(PolygonMode::Wireframe)
+        );
+    }
+}
@@ -0,0 +1,94 @@
+//! Offscreen framebuffers to render into instead of the window
+use crate::run::texture::{Filter, Texture, TextureFormat, TextureOptions, Wrap};
+
+/// A framebuffer object with a color texture and a depth buffer
+pub struct RenderTarget {
+    fbo: u32,
+    depth: u32,
+    pub color: Texture,
+}
+
+impl RenderTarget {
+    /// Creates a target of `width` x `height` pixels, requires a current GL context
+    pub fn new(width: u32, height: u32, format: TextureFormat) -> Result<Self, String> {
+        let color = Texture::empty(
+            width,
+            height,
+            format,
+            &TextureOptions {
+                min_filter: Filter::Linear,
+                mag_filter: Filter::Linear,
+                wrap: Wrap::ClampToEdge,
+                mipmaps: false,
+                srgb: false,
+            },
+        );
+
+        let (mut fbo, mut depth) = (0, 0);
+        let status = unsafe {
+            gl::GenFramebuffers(1, &mut fbo);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, fbo);
+            gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0,
+                gl::TEXTURE_2D,
+                color.id(),
+                0,
+            );
+
+            gl::GenRenderbuffers(1, &mut depth);
+            gl::BindRenderbuffer(gl::RENDERBUFFER, depth);
+            gl::RenderbufferStorage(
+                gl::RENDERBUFFER,
+                gl::DEPTH_COMPONENT24,
+                width as i32,
+                height as i32,
+            );
+            gl::FramebufferRenderbuffer(
+                gl::FRAMEBUFFER,
+                gl::DEPTH_ATTACHMENT,
+                gl::RENDERBUFFER,
+                depth,
+            );
+
+            let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            status
+        };
+
+        let target = RenderTarget { fbo, depth, color };
+        if status != gl::FRAMEBUFFER_COMPLETE {
+            return Err(format!(
+                "Offscreen {format:?} framebuffer of {width}x{height} is incomplete (status {status:#x})"
+            ));
+        }
+        Ok(target)
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.color.width, self.color.height)
+    }
+
+    /// Directs drawing into this target
+    pub fn bind(&self) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+        }
+    }
+
+    /// Directs drawing back into the window
+    pub fn bind_window() {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+    }
+}
+
+impl Drop for RenderTarget {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteFramebuffers(1, &self.fbo);
+            gl::DeleteRenderbuffers(1, &self.depth);
+        }
+    }
+}
@@ -0,0 +1,49 @@
+//! The meshes of the 2D scene and the background behind them
+use crate::run::mesh::{Color, Mesh};
+use crate::run::render_state::RenderState;
+use serde::Deserialize;
+
+/// Scene settings, the `[scene]` section of the config file
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SceneConfig {
+    /// Background color the frame is cleared to, RGB from 0 to 1
+    pub clear_color: [f32; 3],
+    /// Render state of the meshes of the 2D scene
+    pub render_state: RenderState,
+}
+
+impl Default for SceneConfig {
+    fn default() -> Self {
+        SceneConfig {
+            clear_color: [0.1, 0.1, 0.1],
+            render_state: RenderState::default(),
+        }
+    }
+}
+
+pub struct Scene {
+    /// Background color the frame is cleared to
+    pub clear_color: Color,
+    pub meshes: Vec<Mesh>,
+}
+
+impl Scene {
+    pub fn new(meshes: Vec<Mesh>) -> Self {
+        Scene {
+            clear_color: SceneConfig::default().clear_color.into(),
+            meshes,
+        }
+    }
+
+    /// Scene of `meshes` with the clear color and render state of `config`
+    pub fn with_config(meshes: Vec<Mesh>, config: &SceneConfig) -> Self {
+        Scene {
+            clear_color: config.clear_color.into(),
+            meshes: meshes
+                .into_iter()
+                .map(|mesh| mesh.with_render_state(config.render_state))
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,36 @@
+//! Reading back the framebuffer
+use image::RgbaImage;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Reads the color buffer of the bound framebuffer into an image, top row first
+pub fn read_framebuffer(width: u32, height: u32) -> RgbaImage {
+    let mut pixels = vec![0u8; (width * height * 4) as usize];
+    unsafe {
+        gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+        gl::ReadPixels(
+            0,
+            0,
+            width as i32,
+            height as i32,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            pixels.as_mut_ptr().cast(),
+        );
+    }
+    let image = RgbaImage::from_raw(width, height, pixels).expect("buffer matches the size");
+    // GL starts at the bottom row
+    image::imageops::flip_vertical(&image)
+}
+
+/// Saves the framebuffer as `screenshot-<unix time>.png` in the working directory
+pub fn save_screenshot(width: u32, height: u32) -> Result<PathBuf, String> {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs());
+    let path = PathBuf::from(format!("screenshot-{seconds}.png"));
+    read_framebuffer(width, height)
+        .save(&path)
+        .map_err(|e| format!("Couldn't save screenshot '{}': {e}", path.display()))?;
+    Ok(path)
+}
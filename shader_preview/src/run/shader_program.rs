@@ -1,6 +1,13 @@
+//! Compiling and linking GLSL programs
 use std::ffi::{CStr, CString};
 use std::ops::Deref;
 
+mod error;
+mod version;
+
+pub use error::{Diagnostic, Severity, ShaderError, ShaderStage, parse_info_log};
+pub use version::GlslVersion;
+
 pub struct ShaderProgram {
     pub(crate) id: u32,
 }
@@ -22,18 +29,22 @@ impl Deref for Uniform {
 }
 
 impl ShaderProgram {
-    pub fn from_source(vertex_src: &str, fragment_src: &str) -> Result<Self, String> {
-        let vertex_shader_src =
-            CString::new(vertex_src).map_err(|_| "Invalid vertex shader source")?;
-        let fragment_shader_src =
-            CString::new(fragment_src).map_err(|_| "Invalid fragment shader source")?;
-
-        let vertex_shader = ShaderProgram::compile_shader(&vertex_shader_src, gl::VERTEX_SHADER);
+    /// Compiles and links a program, requires a current GL context
+    ///
+    /// Desktop GLSL 3.30 shaders are translated to GLSL ES 3.00 on OpenGL ES contexts, other
+    /// versions the context doesn't support are rejected before compiling.
+    pub fn from_source(vertex_src: &str, fragment_src: &str) -> Result<Self, ShaderError> {
+        let vertex_shader = ShaderProgram::compile_shader(vertex_src, ShaderStage::Vertex)?;
         let fragment_shader =
-            ShaderProgram::compile_shader(&fragment_shader_src, gl::FRAGMENT_SHADER);
-
-        let shader_program = ShaderProgram::link_program(vertex_shader, fragment_shader);
-
+            match ShaderProgram::compile_shader(fragment_src, ShaderStage::Fragment) {
+                Ok(shader) => shader,
+                Err(e) => {
+                    unsafe { gl::DeleteShader(vertex_shader) };
+                    return Err(e);
+                }
+            };
+
+        let shader_program = ShaderProgram::link_program(vertex_shader, fragment_shader)?;
         Ok(ShaderProgram { id: shader_program })
     }
 
@@ -59,12 +70,14 @@ impl ShaderProgram {
         }
     }
 
-    fn link_program(vs: u32, fs: u32) -> u32 {
+    fn link_program(vs: u32, fs: u32) -> Result<u32, ShaderError> {
         unsafe {
             let program = gl::CreateProgram();
             gl::AttachShader(program, vs);
             gl::AttachShader(program, fs);
             gl::LinkProgram(program);
+            gl::DeleteShader(vs);
+            gl::DeleteShader(fs);
 
             // errors
             let mut success = gl::FALSE as gl::types::GLint;
@@ -72,20 +85,39 @@ impl ShaderProgram {
             if success != gl::TRUE as gl::types::GLint {
                 let mut len = 0;
                 gl::GetProgramiv(program, gl::INFO_LOG_LENGTH, &mut len);
-                let error = CString::new(vec![b' '; len as usize]).unwrap();
-                gl::GetProgramInfoLog(program, len, std::ptr::null_mut(), error.as_ptr() as *mut _);
-                panic!("Program linking error: {:?}", error);
+                let mut log = vec![0u8; len.max(1) as usize];
+                gl::GetProgramInfoLog(program, len, std::ptr::null_mut(), log.as_mut_ptr().cast());
+                gl::DeleteProgram(program);
+                return Err(ShaderError::Link {
+                    log: info_log_to_string(&log),
+                });
             }
 
-            gl::DeleteShader(vs);
-            gl::DeleteShader(fs);
-
-            program
+            Ok(program)
         }
     }
-    fn compile_shader(src: &CStr, ty: u32) -> u32 {
+
+    fn compile_shader(src: &str, stage: ShaderStage) -> Result<u32, ShaderError> {
+        let translated;
+        let mut src = src;
+        if let (Some(context), Some(shader)) =
+            (GlslVersion::current(), GlslVersion::from_source(src))
+        {
+            if context.es && !shader.es && shader.number <= 330 {
+                translated = version::to_es(src);
+                src = &translated;
+            } else if !context.supports(shader) {
+                return Err(ShaderError::UnsupportedVersion {
+                    stage,
+                    requested: shader.to_string(),
+                    supported: context.to_string(),
+                });
+            }
+        }
+        let src = CString::new(src).map_err(|_| ShaderError::InvalidSource(stage))?;
+
         unsafe {
-            let shader = gl::CreateShader(ty);
+            let shader = gl::CreateShader(stage.gl_enum());
             gl::ShaderSource(shader, 1, &src.as_ptr(), std::ptr::null());
             gl::CompileShader(shader);
 
@@ -95,13 +127,23 @@ impl ShaderProgram {
             if success != gl::TRUE as gl::types::GLint {
                 let mut len = 0;
                 gl::GetShaderiv(shader, gl::INFO_LOG_LENGTH, &mut len);
-                let error = CString::new(vec![b' '; len as usize]).unwrap();
-                gl::GetShaderInfoLog(shader, len, std::ptr::null_mut(), error.as_ptr() as *mut _);
-                // TODO MARK this needs to be thrown up somehow later
-                panic!("Shader compilation error: {:?}", error);
+                let mut log = vec![0u8; len.max(1) as usize];
+                gl::GetShaderInfoLog(shader, len, std::ptr::null_mut(), log.as_mut_ptr().cast());
+                gl::DeleteShader(shader);
+                return Err(ShaderError::Compile {
+                    stage,
+                    diagnostics: parse_info_log(&info_log_to_string(&log)),
+                });
             }
 
-            shader
+            Ok(shader)
         }
     }
 }
+
+/// Converts a nul terminated info log to a string
+fn info_log_to_string(log: &[u8]) -> String {
+    CStr::from_bytes_until_nul(log)
+        .map(|log| log.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| String::from_utf8_lossy(log).into_owned())
+}
@@ -0,0 +1,239 @@
+//! Structured shader compile and link errors
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShaderStage {
+    Vertex,
+    Fragment,
+}
+
+impl ShaderStage {
+    pub fn gl_enum(self) -> u32 {
+        match self {
+            ShaderStage::Vertex => gl::VERTEX_SHADER,
+            ShaderStage::Fragment => gl::FRAGMENT_SHADER,
+        }
+    }
+}
+
+impl FromStr for ShaderStage {
+    type Err = String;
+
+    fn from_str(stage: &str) -> Result<Self, Self::Err> {
+        match stage {
+            "vertex" => Ok(ShaderStage::Vertex),
+            "fragment" => Ok(ShaderStage::Fragment),
+            _ => Err(format!(
+                "Unknown shader stage '{stage}', expected vertex or fragment"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ShaderStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShaderStage::Vertex => write!(f, "vertex"),
+            ShaderStage::Fragment => write!(f, "fragment"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// One message of a shader compiler
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// 1-based line in the source, when the compiler reported one
+    pub line: Option<u32>,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(line) = self.line {
+            write!(f, "{line}: ")?;
+        }
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, "{severity}: {}", self.message)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShaderError {
+    /// The source contains a nul byte
+    InvalidSource(ShaderStage),
+    /// The `#version` of the source isn't supported by the context
+    UnsupportedVersion {
+        stage: ShaderStage,
+        requested: String,
+        /// `GL_SHADING_LANGUAGE_VERSION` of the context
+        supported: String,
+    },
+    Compile {
+        stage: ShaderStage,
+        diagnostics: Vec<Diagnostic>,
+    },
+    Link {
+        log: String,
+    },
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShaderError::InvalidSource(stage) => {
+                write!(f, "The {stage} shader source contains a nul byte")
+            }
+            ShaderError::UnsupportedVersion {
+                stage,
+                requested,
+                supported,
+            } => write!(
+                f,
+                "The {stage} shader needs GLSL {requested}, the context supports up to GLSL {supported}"
+            ),
+            ShaderError::Compile { stage, diagnostics } => {
+                write!(f, "Failed to compile the {stage} shader")?;
+                for diagnostic in diagnostics {
+                    write!(f, "\n  {diagnostic}")?;
+                }
+                Ok(())
+            }
+            ShaderError::Link { log } => write!(f, "Failed to link the shader program: {log}"),
+        }
+    }
+}
+
+impl std::error::Error for ShaderError {}
+
+impl From<ShaderError> for String {
+    fn from(error: ShaderError) -> Self {
+        error.to_string()
+    }
+}
+
+/// Parses the info log of a shader compiler into diagnostics
+///
+/// Understands the formats of Mesa (`0:12(5): error: ...`), NVIDIA (`0(12) : error C1008: ...`)
+/// and AMD/Intel (`ERROR: 0:12: ...`), other lines are kept without a line number.
+pub fn parse_info_log(log: &str) -> Vec<Diagnostic> {
+    log.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(parse_log_line)
+        .collect()
+}
+
+fn parse_log_line(text: &str) -> Diagnostic {
+    let severity_of = |word: &str| {
+        if word.to_ascii_lowercase().starts_with("warning") {
+            Severity::Warning
+        } else {
+            Severity::Error
+        }
+    };
+    let unparsed = || Diagnostic {
+        severity: severity_of(text),
+        line: None,
+        message: text.to_string(),
+    };
+
+    // AMD/Intel: "ERROR: 0:12: message"
+    for prefix in ["ERROR: ", "WARNING: "] {
+        if let Some(rest) = text.strip_prefix(prefix) {
+            let mut parts = rest.splitn(3, ':');
+            let (_, line, message) = (parts.next(), parts.next(), parts.next());
+            return match (line.and_then(|l| l.trim().parse().ok()), message) {
+                (Some(line), Some(message)) => Diagnostic {
+                    severity: severity_of(prefix),
+                    line: Some(line),
+                    message: message.trim().to_string(),
+                },
+                _ => unparsed(),
+            };
+        }
+    }
+
+    // Mesa "0:12(5): error: message" and NVIDIA "0(12) : error C1008: message"
+    let Some((location, rest)) = text.split_once(": ") else {
+        return unparsed();
+    };
+    let location = location.trim();
+    let line = if let Some((_, rest)) = location.split_once(':') {
+        rest.split('(').next()
+    } else if let Some((_, rest)) = location.split_once('(') {
+        rest.strip_suffix(')')
+    } else {
+        None
+    };
+    let Some(line) = line.and_then(|line| line.parse().ok()) else {
+        return unparsed();
+    };
+    let (kind, message) = rest.split_once(": ").unwrap_or(("error", rest));
+    Diagnostic {
+        severity: severity_of(kind),
+        line: Some(line),
+        message: message.trim().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diagnostic(severity: Severity, line: u32, message: &str) -> Diagnostic {
+        Diagnostic {
+            severity,
+            line: Some(line),
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_driver_logs() {
+        let mesa = "0:12(5): error: `foo' undeclared\n0:3(1): warning: unused variable\n";
+        assert_eq!(
+            parse_info_log(mesa),
+            [
+                diagnostic(Severity::Error, 12, "`foo' undeclared"),
+                diagnostic(Severity::Warning, 3, "unused variable"),
+            ]
+        );
+
+        let nvidia = "0(7) : error C1008: undefined variable \"foo\"";
+        assert_eq!(
+            parse_info_log(nvidia),
+            [diagnostic(Severity::Error, 7, "undefined variable \"foo\"")]
+        );
+
+        let amd = "ERROR: 0:4: 'foo' : undeclared identifier\nERROR: 1 compilation errors.";
+        let diagnostics = parse_info_log(amd);
+        assert_eq!(
+            diagnostics[0],
+            diagnostic(Severity::Error, 4, "'foo' : undeclared identifier")
+        );
+        assert_eq!(diagnostics[1].line, None);
+    }
+
+    #[test]
+    fn test_display() {
+        let error = ShaderError::Compile {
+            stage: ShaderStage::Fragment,
+            diagnostics: vec![diagnostic(Severity::Error, 3, "syntax error")],
+        };
+        assert_eq!(
+            error.to_string(),
+            "Failed to compile the fragment shader\n  3: error: syntax error"
+        );
+    }
+}
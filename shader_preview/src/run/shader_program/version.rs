@@ -0,0 +1,144 @@
+//! GLSL versions of shaders and contexts
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GlslVersion {
+    /// Version times 100, e.g. 330
+    pub number: u32,
+    /// GLSL ES, for OpenGL ES contexts
+    pub es: bool,
+}
+
+impl GlslVersion {
+    /// Reads the `#version` directive of a shader source, `None` if there is none
+    pub fn from_source(source: &str) -> Option<Self> {
+        let directive = source
+            .lines()
+            .map(str::trim)
+            .find_map(|line| line.strip_prefix("#version"))?;
+        let mut words = directive.split_whitespace();
+        let number = words.next()?.parse().ok()?;
+        Some(GlslVersion {
+            number,
+            es: words.next() == Some("es"),
+        })
+    }
+
+    /// Parses `GL_SHADING_LANGUAGE_VERSION`, like `4.60 NVIDIA` or `OpenGL ES GLSL ES 3.00`
+    pub fn from_context_string(version: &str) -> Option<Self> {
+        let es = version.starts_with("OpenGL ES");
+        let number = version
+            .split_whitespace()
+            .find(|word| word.starts_with(|c: char| c.is_ascii_digit()))?;
+        let (major, minor) = number.split_once('.')?;
+        let minor: String = minor.chars().take_while(char::is_ascii_digit).collect();
+        // "4.6" means 460 just like "4.60"
+        let minor = format!("{minor:0<2}");
+        Some(GlslVersion {
+            number: major.parse::<u32>().ok()? * 100 + minor[..2].parse::<u32>().ok()?,
+            es,
+        })
+    }
+
+    /// GLSL version of the current context, requires a current GL context
+    pub fn current() -> Option<Self> {
+        let version = unsafe { gl::GetString(gl::SHADING_LANGUAGE_VERSION) };
+        if version.is_null() {
+            return None;
+        }
+        let version = unsafe { std::ffi::CStr::from_ptr(version.cast()) };
+        Self::from_context_string(&version.to_string_lossy())
+    }
+
+    /// Whether a context with this GLSL version compiles shaders written for `shader`
+    pub fn supports(self, shader: GlslVersion) -> bool {
+        match (self.es, shader.es) {
+            (true, true) | (false, false) => shader.number <= self.number,
+            // GLSL ES 3.00 came to desktop GL with 4.3
+            (false, true) => shader.number <= 300 && self.number >= 430,
+            (true, false) => false,
+        }
+    }
+}
+
+impl fmt::Display for GlslVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.number)?;
+        if self.es {
+            write!(f, " es")?;
+        }
+        Ok(())
+    }
+}
+
+/// Rewrites a desktop GLSL 3.30 shader as GLSL ES 3.00 with high default precision
+///
+/// The languages are close enough that this works for the shaders ShaderPreview ships with.
+/// A `#line` directive after the added precision statements keeps compile errors pointing
+/// at lines of `source`.
+pub fn to_es(source: &str) -> String {
+    let mut translated = String::with_capacity(source.len() + 64);
+    for (index, line) in source.lines().enumerate() {
+        if line.trim_start().starts_with("#version") {
+            translated.push_str("#version 300 es\nprecision highp float;\nprecision highp int;\n");
+            translated.push_str(&format!("#line {}\n", index + 2));
+        } else {
+            translated.push_str(line);
+            translated.push('\n');
+        }
+    }
+    translated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const fn version(number: u32, es: bool) -> GlslVersion {
+        GlslVersion { number, es }
+    }
+
+    #[test]
+    fn test_parse_versions() {
+        assert_eq!(
+            GlslVersion::from_source("// comment\n#version 330 core\nvoid main() {}"),
+            Some(version(330, false))
+        );
+        assert_eq!(
+            GlslVersion::from_source("#version 300 es\n"),
+            Some(version(300, true))
+        );
+        assert_eq!(GlslVersion::from_source("void main() {}"), None);
+
+        for (string, expected) in [
+            ("4.60 NVIDIA", version(460, false)),
+            ("3.30", version(330, false)),
+            ("4.6", version(460, false)),
+            ("OpenGL ES GLSL ES 3.00", version(300, true)),
+            ("OpenGL ES GLSL ES 3.20 Mesa", version(320, true)),
+        ] {
+            assert_eq!(GlslVersion::from_context_string(string), Some(expected));
+        }
+        assert_eq!(GlslVersion::from_context_string("unknown"), None);
+    }
+
+    #[test]
+    fn test_supports() {
+        assert!(version(460, false).supports(version(330, false)));
+        assert!(!version(330, false).supports(version(460, false)));
+        assert!(version(460, false).supports(version(300, true)));
+        assert!(!version(330, false).supports(version(300, true)));
+        assert!(!version(300, true).supports(version(330, false)));
+    }
+
+    #[test]
+    fn test_to_es() {
+        let es = to_es("#version 330 core\nout vec4 color;\n");
+        assert_eq!(GlslVersion::from_source(&es), Some(version(300, true)));
+        assert!(es.contains("precision highp float;"));
+        assert!(es.ends_with("#line 2\nout vec4 color;\n"));
+
+        let es = to_es("// header\n#version 330 core\nout vec4 color;\n");
+        assert!(es.contains("#line 3\nout vec4 color;"));
+    }
+}
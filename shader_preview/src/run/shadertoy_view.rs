@@ -0,0 +1,265 @@
+//! Preview mode for shaders exported from Shadertoy, see [`crate::import::shadertoy`]
+//!
+//! Buffer passes render into float targets kept between frames, a pass reading its own
+//! buffer or a later one sees the previous frame. Media is looked up next to the JSON file,
+//! by its `src` path or file name, inputs without a local file get placeholder textures,
+//! cubemaps and volumes always do.
+use crate::import::shadertoy::{Channel, ChannelSource, PassKind, Shadertoy};
+use crate::run::backend::{GlBackend, RenderBackend};
+use crate::run::fullscreen::{DoubleTarget, FullscreenTriangle, utc_date};
+use crate::run::material::{UniformValue, UniformValues};
+use crate::run::render_state::{BlendMode, RenderState};
+use crate::run::shader_program::ShaderProgram;
+use crate::run::texture::{Pixels, Texture, TextureData, TextureFormat, TextureOptions};
+use std::path::Path;
+use std::time::SystemTime;
+
+/// Shadertoy buffers hold 32 bit floats
+const BUFFER_FORMAT: TextureFormat = TextureFormat::Rgba32F;
+
+/// What the sampler of a channel reads
+enum Binding {
+    Buffer {
+        index: usize,
+        options: TextureOptions,
+    },
+    /// Index into [`ShadertoyView::textures`]
+    Texture(usize),
+    /// A channel the pass has no input for, samples black
+    Nothing,
+}
+
+struct Pass {
+    program: ShaderProgram,
+    kind: PassKind,
+    /// By channel index
+    channels: [Binding; 4],
+}
+
+pub(crate) struct ShadertoyView {
+    passes: Vec<Pass>,
+    textures: Vec<Texture>,
+    /// Buffer A to D, created on the first frame and recreated when the size changes
+    buffers: [Option<DoubleTarget>; 4],
+    frame: i32,
+    triangle: FullscreenTriangle,
+}
+
+impl ShadertoyView {
+    /// Loads a JSON export and compiles its passes, requires a current GL context
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let shadertoy = Shadertoy::load(path)?;
+        let media_dir = path.parent().unwrap_or(Path::new("."));
+        let mut textures = Vec::new();
+        let mut passes = Vec::new();
+        for pass in &shadertoy.passes {
+            let program = GlBackend
+                .create_program(
+                    include_str!("../vertex_shader_post.glsl"),
+                    &shadertoy.fragment_source(pass),
+                )
+                .map_err(|e| format!("{} pass of '{}': {e}", pass.name, path.display()))?;
+            let mut channels = [const { Binding::Nothing }; 4];
+            for channel in &pass.channels {
+                channels[channel.index] = match &channel.source {
+                    ChannelSource::Buffer(index) => Binding::Buffer {
+                        index: *index,
+                        options: channel.options,
+                    },
+                    ChannelSource::Texture(src) => {
+                        textures.push(load_media(media_dir, src, channel)?);
+                        Binding::Texture(textures.len() - 1)
+                    }
+                    // keys read as released
+                    ChannelSource::Keyboard => {
+                        let keys = TextureData {
+                            width: 256,
+                            height: 3,
+                            pixels: Pixels::Rgba8(vec![0; 256 * 3 * 4]),
+                        };
+                        textures.push(Texture::upload(&keys, &channel.options));
+                        Binding::Texture(textures.len() - 1)
+                    }
+                    ChannelSource::Cubemap(_) => {
+                        log::warn!(
+                            "iChannel{} of the {} pass is a cubemap, which isn't supported, it gets a placeholder cubemap",
+                            channel.index,
+                            pass.name
+                        );
+                        textures.push(Texture::placeholder_cubemap(&channel.options));
+                        Binding::Texture(textures.len() - 1)
+                    }
+                    ChannelSource::Unsupported(ctype) if ctype == "volume" => {
+                        log::warn!(
+                            "iChannel{} of the {} pass is a volume, which isn't supported, it gets a placeholder volume",
+                            channel.index,
+                            pass.name
+                        );
+                        textures.push(Texture::placeholder_volume(&channel.options));
+                        Binding::Texture(textures.len() - 1)
+                    }
+                    ChannelSource::Unsupported(ctype) => {
+                        log::warn!(
+                            "iChannel{} of the {} pass is a {ctype} input, it gets a placeholder texture",
+                            channel.index,
+                            pass.name
+                        );
+                        textures.push(Texture::upload(
+                            &TextureData::placeholder(),
+                            &channel.options,
+                        ));
+                        Binding::Texture(textures.len() - 1)
+                    }
+                };
+            }
+            passes.push(Pass {
+                program,
+                kind: pass.kind,
+                channels,
+            });
+        }
+        log::info!(
+            "Loaded Shadertoy '{}' with {} passes",
+            shadertoy.name,
+            passes.len()
+        );
+
+        Ok(ShadertoyView {
+            passes,
+            textures,
+            buffers: Default::default(),
+            frame: 0,
+            triangle: FullscreenTriangle::new(),
+        })
+    }
+
+    /// Runs the buffer passes and draws the image pass into the bound framebuffer
+    ///
+    /// `mouse` is the value of `iMouse`, `time_delta` is 0 while paused.
+    pub fn render(
+        &mut self,
+        (width, height): (u32, u32),
+        time: f64,
+        time_delta: f64,
+        mouse: [f32; 4],
+    ) -> Result<(), String> {
+        let (width, height) = (width.max(1), height.max(1));
+        let mut framebuffer = 0;
+        unsafe {
+            gl::GetIntegerv(gl::DRAW_FRAMEBUFFER_BINDING, &mut framebuffer);
+        }
+        for pass in &self.passes {
+            if let PassKind::Buffer(index) = pass.kind {
+                let buffer = &mut self.buffers[index];
+                if buffer.as_ref().is_none_or(|b| b.size() != (width, height)) {
+                    *buffer = Some(DoubleTarget::new(width, height, BUFFER_FORMAT)?);
+                }
+            }
+        }
+
+        let (w, h) = (width as f32, height as f32);
+        let frame_rate = if time_delta > 0.0 {
+            1.0 / time_delta
+        } else {
+            0.0
+        };
+        let uniforms = UniformValues::default()
+            .with("iResolution", UniformValue::Vec3([w, h, 1.0]))
+            .with("iTime", UniformValue::Float(time as f32))
+            .with("iTimeDelta", UniformValue::Float(time_delta as f32))
+            .with("iFrameRate", UniformValue::Float(frame_rate as f32))
+            .with("iFrame", UniformValue::Int(self.frame))
+            .with("iMouse", UniformValue::Vec4(mouse))
+            .with("iDate", UniformValue::Vec4(date(SystemTime::now())))
+            .with("iSampleRate", UniformValue::Float(44100.0));
+
+        let mut backend = GlBackend;
+        backend.apply_render_state(&RenderState::default().with_blend(BlendMode::Opaque));
+        for pass in &self.passes {
+            match pass.kind {
+                PassKind::Buffer(index) => {
+                    let buffer = self.buffers[index].as_ref().expect("buffers are created");
+                    buffer.back().bind();
+                }
+                PassKind::Image => unsafe {
+                    gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer as u32);
+                },
+            }
+            backend.use_program(&pass.program);
+            uniforms.apply(&mut backend, &pass.program);
+            for (unit, binding) in pass.channels.iter().enumerate() {
+                let texture = match binding {
+                    Binding::Buffer { index, options } => {
+                        self.buffers[*index].as_ref().map(|buffer| {
+                            let texture = &buffer.front().color;
+                            texture.set_options(options);
+                            texture
+                        })
+                    }
+                    Binding::Texture(index) => Some(&self.textures[*index]),
+                    Binding::Nothing => None,
+                };
+                // every sampler gets its own unit, samplers of different types can't share one
+                let program = &pass.program;
+                backend.set_uniform(
+                    program,
+                    &format!("iChannel{unit}"),
+                    UniformValue::Int(unit as i32),
+                );
+                match texture {
+                    Some(texture) => {
+                        backend.bind_texture(unit as u32, texture);
+                        let size = [texture.width as f32, texture.height as f32, 1.0];
+                        backend.set_uniform(
+                            program,
+                            &format!("iChannelResolution[{unit}]"),
+                            UniformValue::Vec3(size),
+                        );
+                    }
+                    None => backend.unbind_texture(unit as u32),
+                }
+            }
+            self.triangle.draw();
+            if let PassKind::Buffer(index) = pass.kind {
+                self.buffers[index]
+                    .as_mut()
+                    .expect("buffers are created")
+                    .swap();
+            }
+        }
+        self.frame += 1;
+        Ok(())
+    }
+}
+
+/// Loads the image `src` of an export from `dir`, or a placeholder when it isn't there
+fn load_media(dir: &Path, src: &str, channel: &Channel) -> Result<Texture, String> {
+    let relative = Path::new(src.trim_start_matches('/'));
+    let candidates = [
+        Some(dir.join(relative)),
+        relative.file_name().map(|name| dir.join(name)),
+    ];
+    let Some(path) = candidates.into_iter().flatten().find(|path| path.is_file()) else {
+        log::warn!(
+            "'{src}' of iChannel{} isn't in '{}', using a placeholder",
+            channel.index,
+            dir.display()
+        );
+        return Ok(Texture::upload(
+            &TextureData::placeholder(),
+            &channel.options,
+        ));
+    };
+    let mut data = TextureData::load(path)?;
+    // images load bottom row first, which is what vflip asks for
+    if !channel.vflip {
+        data.flip_vertically();
+    }
+    Ok(Texture::upload(&data, &channel.options))
+}
+
+/// Value of `iDate`: year, month from 0, day of the month and seconds since midnight
+fn date(now: SystemTime) -> [f32; 4] {
+    let (year, month, day, seconds) = utc_date(now);
+    [year as f32, (month - 1) as f32, day as f32, seconds as f32]
+}
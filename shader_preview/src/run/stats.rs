@@ -0,0 +1,255 @@
+//! Frame time measurements and the HUD showing them
+//!
+//! GPU time is measured with `GL_TIME_ELAPSED` queries. Results arrive a few frames late,
+//! so several queries are kept in flight instead of stalling on the newest one.
+use crate::run::overlay::{BACKGROUND_COLOR, Overlay, Rgba};
+use std::collections::VecDeque;
+
+/// Frames kept for averages and the graph
+pub const HISTORY: usize = 120;
+/// Frame time at the top of the graph
+const GRAPH_MAX_MS: f64 = 50.0;
+const GRAPH_HEIGHT: f32 = 60.0;
+
+const CPU_COLOR: Rgba = [0.3, 0.7, 1.0, 0.9];
+const GPU_COLOR: Rgba = [1.0, 0.6, 0.2, 0.9];
+const TARGET_COLOR: Rgba = [1.0, 1.0, 1.0, 0.4];
+
+/// Timings of one frame in milliseconds
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameSample {
+    /// Time since the previous frame started
+    pub frame_ms: f64,
+    /// Time spent preparing and submitting the frame, excluding waiting for v-sync
+    pub cpu_ms: f64,
+    /// Time the GPU spent rendering, when timer queries are available
+    pub gpu_ms: Option<f64>,
+}
+
+/// Rolling history of frame timings
+#[derive(Debug, Clone, Default)]
+pub struct FrameStats {
+    samples: VecDeque<FrameSample>,
+}
+
+impl FrameStats {
+    pub fn push(&mut self, sample: FrameSample) {
+        if self.samples.len() == HISTORY {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    /// Sets the GPU time of a frame `frames_ago` frames back, for late query results
+    pub fn set_gpu_ms(&mut self, frames_ago: usize, gpu_ms: f64) {
+        let len = self.samples.len();
+        if let Some(sample) = len
+            .checked_sub(frames_ago + 1)
+            .and_then(|index| self.samples.get_mut(index))
+        {
+            sample.gpu_ms = Some(gpu_ms);
+        }
+    }
+
+    pub fn samples(&self) -> impl ExactSizeIterator<Item = &FrameSample> {
+        self.samples.iter()
+    }
+
+    fn average(&self, value: impl Fn(&FrameSample) -> Option<f64>) -> Option<f64> {
+        let values: Vec<f64> = self.samples.iter().filter_map(value).collect();
+        (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+    }
+
+    /// Frames per second over the history
+    pub fn fps(&self) -> Option<f64> {
+        self.average(|s| Some(s.frame_ms))
+            .filter(|&ms| ms > 0.0)
+            .map(|ms| 1000.0 / ms)
+    }
+
+    pub fn average_frame_ms(&self) -> Option<f64> {
+        self.average(|s| Some(s.frame_ms))
+    }
+
+    pub fn average_cpu_ms(&self) -> Option<f64> {
+        self.average(|s| Some(s.cpu_ms))
+    }
+
+    pub fn average_gpu_ms(&self) -> Option<f64> {
+        self.average(|s| s.gpu_ms)
+    }
+
+    /// Adds the numbers and a graph of the history to `overlay`, in the top right corner
+    ///
+    /// The graph shows CPU time as blue bars and GPU time as orange marks, the line marks
+    /// 60 FPS.
+    pub fn draw(&self, overlay: &mut Overlay, viewport_width: f32) {
+        let format = |ms: Option<f64>| ms.map_or("-".to_string(), |ms| format!("{ms:.2} ms"));
+        let text = format!(
+            "FPS   {}\nframe {}\ncpu   {}\ngpu   {}",
+            self.fps()
+                .map_or("-".to_string(), |fps| format!("{fps:.0}")),
+            format(self.average_frame_ms()),
+            format(self.average_cpu_ms()),
+            format(self.average_gpu_ms()),
+        );
+
+        let scale = overlay.scale;
+        let margin = 8.0 * scale;
+        let bar_width = scale;
+        let graph_width = HISTORY as f32 * bar_width;
+        let graph_height = GRAPH_HEIGHT * scale;
+        let left = viewport_width - graph_width - margin;
+
+        overlay.label(left, margin, &text);
+        let text_height = overlay.text_size(&text).1 + 8.0 * scale;
+
+        let top = margin + text_height + 4.0 * scale;
+        let bottom = top + graph_height;
+        overlay.rect(left, top, graph_width, graph_height, BACKGROUND_COLOR);
+        let height = |ms: f64| ((ms / GRAPH_MAX_MS).min(1.0) as f32) * graph_height;
+
+        // newest sample on the right
+        let offset = HISTORY - self.samples.len();
+        for (i, sample) in self.samples.iter().enumerate() {
+            let x = left + (offset + i) as f32 * bar_width;
+            let cpu = height(sample.cpu_ms);
+            overlay.rect(x, bottom - cpu, bar_width, cpu, CPU_COLOR);
+            if let Some(gpu_ms) = sample.gpu_ms {
+                let gpu = height(gpu_ms);
+                overlay.rect(x, bottom - gpu, bar_width, 2.0 * scale, GPU_COLOR);
+            }
+        }
+        let target = bottom - height(1000.0 / 60.0);
+        overlay.rect(left, target, graph_width, scale, TARGET_COLOR);
+    }
+}
+
+/// Measures GPU time between [`GpuTimer::begin`] and [`GpuTimer::end`] with a ring of
+/// `GL_TIME_ELAPSED` queries
+pub struct GpuTimer {
+    queries: Vec<u32>,
+    /// Next query to use
+    next: usize,
+    /// Queries waiting for results, oldest first, with the frame they were issued in
+    pending: VecDeque<(usize, u64)>,
+    frame: u64,
+}
+
+impl GpuTimer {
+    const QUERIES: usize = 4;
+
+    /// Creates the queries, `None` if the context doesn't support timer queries
+    pub fn new() -> Option<Self> {
+        if !gl::GenQueries::is_loaded() || !gl::GetQueryObjectui64v::is_loaded() {
+            return None;
+        }
+        let mut queries = vec![0; Self::QUERIES];
+        unsafe {
+            gl::GenQueries(queries.len() as i32, queries.as_mut_ptr());
+        }
+        Some(GpuTimer {
+            queries,
+            next: 0,
+            pending: VecDeque::new(),
+            frame: 0,
+        })
+    }
+
+    /// Starts measuring, returns false if every query is still waiting for a result
+    pub fn begin(&mut self) -> bool {
+        if self.pending.len() == self.queries.len() {
+            return false;
+        }
+        unsafe {
+            gl::BeginQuery(gl::TIME_ELAPSED, self.queries[self.next]);
+        }
+        true
+    }
+
+    /// Stops measuring, only call after a successful [`GpuTimer::begin`]
+    pub fn end(&mut self) {
+        unsafe {
+            gl::EndQuery(gl::TIME_ELAPSED);
+        }
+        self.pending.push_back((self.next, self.frame));
+        self.next = (self.next + 1) % self.queries.len();
+    }
+
+    /// Marks the end of a frame
+    pub fn end_frame(&mut self) {
+        self.frame += 1;
+    }
+
+    /// Returns finished measurements as `(frames ago, milliseconds)`, oldest first
+    pub fn poll(&mut self) -> Vec<(usize, f64)> {
+        let mut results = Vec::new();
+        while let Some(&(index, frame)) = self.pending.front() {
+            let query = self.queries[index];
+            let mut available = 0;
+            unsafe {
+                gl::GetQueryObjectiv(query, gl::QUERY_RESULT_AVAILABLE, &mut available);
+            }
+            if available == 0 {
+                break;
+            }
+            let mut nanoseconds = 0;
+            unsafe {
+                gl::GetQueryObjectui64v(query, gl::QUERY_RESULT, &mut nanoseconds);
+            }
+            self.pending.pop_front();
+            let frames_ago = (self.frame - frame) as usize;
+            results.push((frames_ago, nanoseconds as f64 / 1e6));
+        }
+        results
+    }
+}
+
+impl Drop for GpuTimer {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteQueries(self.queries.len() as i32, self.queries.as_ptr());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(frame_ms: f64) -> FrameSample {
+        FrameSample {
+            frame_ms,
+            cpu_ms: frame_ms / 2.0,
+            gpu_ms: None,
+        }
+    }
+
+    #[test]
+    fn test_averages() {
+        let mut stats = FrameStats::default();
+        assert_eq!(stats.fps(), None);
+
+        stats.push(sample(10.0));
+        stats.push(sample(30.0));
+        assert_eq!(stats.fps(), Some(50.0));
+        assert_eq!(stats.average_cpu_ms(), Some(10.0));
+        assert_eq!(stats.average_gpu_ms(), None);
+
+        // late GPU results are matched to the right frame
+        stats.set_gpu_ms(1, 4.0);
+        assert_eq!(stats.samples().next().unwrap().gpu_ms, Some(4.0));
+        assert_eq!(stats.average_gpu_ms(), Some(4.0));
+        stats.set_gpu_ms(5, 1.0);
+    }
+
+    #[test]
+    fn test_history_is_bounded() {
+        let mut stats = FrameStats::default();
+        for i in 0..HISTORY + 10 {
+            stats.push(sample(i as f64));
+        }
+        assert_eq!(stats.samples().len(), HISTORY);
+        assert_eq!(stats.samples().next().unwrap().frame_ms, 10.0);
+    }
+}
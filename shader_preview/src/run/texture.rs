@@ -0,0 +1,471 @@
+//! Image textures and binding them to `sampler2D` uniforms
+use crate::run::backend::RenderBackend;
+use crate::run::material::UniformValue;
+use crate::run::shader_program::ShaderProgram;
+use serde::Deserialize;
+use std::path::Path;
+
+/// How texels are sampled when a texture is scaled
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Filter {
+    Nearest,
+    Linear,
+}
+
+/// What happens to texture coordinates outside of `0..1`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Wrap {
+    Repeat,
+    MirroredRepeat,
+    ClampToEdge,
+}
+
+/// Sampler settings used when uploading a [`Texture`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextureOptions {
+    pub min_filter: Filter,
+    pub mag_filter: Filter,
+    pub wrap: Wrap,
+    /// Generates mipmaps and samples between them when minifying
+    pub mipmaps: bool,
+    /// Treats 8 bit images as sRGB encoded, so sampling them returns linear values
+    pub srgb: bool,
+}
+
+impl Default for TextureOptions {
+    fn default() -> Self {
+        TextureOptions {
+            min_filter: Filter::Linear,
+            mag_filter: Filter::Linear,
+            wrap: Wrap::Repeat,
+            mipmaps: true,
+            srgb: false,
+        }
+    }
+}
+
+/// Storage of a texture that is rendered into
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+pub enum TextureFormat {
+    /// 8 bits per channel, values are clamped to `0..1`
+    #[default]
+    #[serde(rename = "rgba8")]
+    Rgba8,
+    /// Half floats, enough range and precision for HDR colors
+    #[serde(rename = "rgba16f")]
+    Rgba16F,
+    #[serde(rename = "rgba32f")]
+    Rgba32F,
+}
+
+impl TextureFormat {
+    fn internal_format(self) -> u32 {
+        match self {
+            TextureFormat::Rgba8 => gl::RGBA8,
+            TextureFormat::Rgba16F => gl::RGBA16F,
+            TextureFormat::Rgba32F => gl::RGBA32F,
+        }
+    }
+
+    /// Pixel type matching the internal format, OpenGL ES rejects other combinations
+    fn data_type(self) -> u32 {
+        match self {
+            TextureFormat::Rgba8 => gl::UNSIGNED_BYTE,
+            TextureFormat::Rgba16F => gl::HALF_FLOAT,
+            TextureFormat::Rgba32F => gl::FLOAT,
+        }
+    }
+}
+
+/// Decoded pixels, 4 channels per pixel
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pixels {
+    Rgba8(Vec<u8>),
+    /// Used for HDR images, values can go above 1.0
+    RgbaF32(Vec<f32>),
+}
+
+/// CPU side image, with the bottom row first as OpenGL expects
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextureData {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Pixels,
+}
+
+impl TextureData {
+    /// Loads a PNG, JPEG or Radiance HDR image
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let image = image::open(path)
+            .map_err(|e| format!("Couldn't load image '{}': {e}", path.display()))?;
+        Ok(Self::from_image(image))
+    }
+
+    /// Decodes an image file from memory, the format is guessed from the data
+    pub fn decode(bytes: &[u8]) -> Result<Self, String> {
+        let image =
+            image::load_from_memory(bytes).map_err(|e| format!("Couldn't decode image: {e}"))?;
+        Ok(Self::from_image(image))
+    }
+
+    /// Magenta and black checkerboard standing in for missing images
+    pub fn placeholder() -> Self {
+        let size = 64;
+        let pixels = (0..size)
+            .flat_map(|y| {
+                (0..size).flat_map(move |x| {
+                    if (x / 8 + y / 8) % 2 == 0 {
+                        [255, 0, 255, 255]
+                    } else {
+                        [0, 0, 0, 255]
+                    }
+                })
+            })
+            .collect();
+        TextureData {
+            width: size,
+            height: size,
+            pixels: Pixels::Rgba8(pixels),
+        }
+    }
+
+    /// Reverses the order of the rows, for images that should start at the top
+    pub fn flip_vertically(&mut self) {
+        let row = self.width as usize * 4;
+        match &mut self.pixels {
+            Pixels::Rgba8(pixels) => *pixels = flip_rows(pixels, row),
+            Pixels::RgbaF32(pixels) => *pixels = flip_rows(pixels, row),
+        }
+    }
+
+    fn from_image(image: image::DynamicImage) -> Self {
+        // images are stored top row first, GL textures start at the bottom
+        let image = image.flipv();
+        let (width, height) = (image.width(), image.height());
+        let pixels = match image {
+            image::DynamicImage::ImageRgb32F(_) | image::DynamicImage::ImageRgba32F(_) => {
+                Pixels::RgbaF32(image.into_rgba32f().into_raw())
+            }
+            _ => Pixels::Rgba8(image.into_rgba8().into_raw()),
+        };
+        TextureData {
+            width,
+            height,
+            pixels,
+        }
+    }
+}
+
+fn flip_rows<T: Copy>(pixels: &[T], row: usize) -> Vec<T> {
+    pixels
+        .chunks_exact(row.max(1))
+        .rev()
+        .flatten()
+        .copied()
+        .collect()
+}
+
+/// A texture on the GPU, 2D unless it's one of the placeholders for cube maps and volumes
+pub struct Texture {
+    id: u32,
+    /// `GL_TEXTURE_2D`, `GL_TEXTURE_CUBE_MAP` or `GL_TEXTURE_3D`
+    target: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Texture {
+    /// Loads an image file and uploads it, requires a current GL context
+    pub fn load(path: impl AsRef<Path>, options: &TextureOptions) -> Result<Self, String> {
+        Ok(Self::upload(&TextureData::load(path)?, options))
+    }
+
+    /// Uploads decoded pixels, 8-bit images become `RGBA8` textures and HDR images `RGBA32F`
+    pub fn upload(data: &TextureData, options: &TextureOptions) -> Self {
+        let (internal_format, data_type, pointer) = match &data.pixels {
+            Pixels::Rgba8(pixels) if options.srgb => {
+                (gl::SRGB8_ALPHA8, gl::UNSIGNED_BYTE, pixels.as_ptr().cast())
+            }
+            Pixels::Rgba8(pixels) => (gl::RGBA8, gl::UNSIGNED_BYTE, pixels.as_ptr().cast()),
+            Pixels::RgbaF32(pixels) => (gl::RGBA32F, gl::FLOAT, pixels.as_ptr().cast()),
+        };
+        let texture =
+            Texture::allocate(data.width, data.height, internal_format, data_type, pointer);
+        texture.set_options(options);
+        texture
+    }
+
+    /// Allocates a texture with undefined contents, to render into
+    pub fn empty(width: u32, height: u32, format: TextureFormat, options: &TextureOptions) -> Self {
+        let texture = Texture::allocate(
+            width,
+            height,
+            format.internal_format(),
+            format.data_type(),
+            std::ptr::null(),
+        );
+        texture.set_options(options);
+        texture
+    }
+
+    fn allocate(
+        width: u32,
+        height: u32,
+        internal_format: u32,
+        data_type: u32,
+        pointer: *const std::ffi::c_void,
+    ) -> Self {
+        let mut id = 0;
+        unsafe {
+            gl::GenTextures(1, &mut id);
+            gl::BindTexture(gl::TEXTURE_2D, id);
+            // rows of RGBA8 are always 4 byte aligned, but be safe for odd widths
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                internal_format as i32,
+                width as i32,
+                height as i32,
+                0,
+                gl::RGBA,
+                data_type,
+                pointer,
+            );
+        }
+        Texture {
+            id,
+            target: gl::TEXTURE_2D,
+            width,
+            height,
+        }
+    }
+
+    /// [`TextureData::placeholder`] on all six faces of a cube map, for `samplerCube` uniforms
+    pub fn placeholder_cubemap(options: &TextureOptions) -> Self {
+        let face = TextureData::placeholder();
+        let Pixels::Rgba8(pixels) = &face.pixels else {
+            unreachable!("the placeholder has 8 bit pixels");
+        };
+        let mut id = 0;
+        unsafe {
+            gl::GenTextures(1, &mut id);
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, id);
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+            for side in 0..6 {
+                gl::TexImage2D(
+                    gl::TEXTURE_CUBE_MAP_POSITIVE_X + side,
+                    0,
+                    gl::RGBA8 as i32,
+                    face.width as i32,
+                    face.height as i32,
+                    0,
+                    gl::RGBA,
+                    gl::UNSIGNED_BYTE,
+                    pixels.as_ptr().cast(),
+                );
+            }
+        }
+        let texture = Texture {
+            id,
+            target: gl::TEXTURE_CUBE_MAP,
+            width: face.width,
+            height: face.height,
+        };
+        texture.set_options(options);
+        texture
+    }
+
+    /// Magenta and black 3D checkerboard, for `sampler3D` uniforms
+    pub fn placeholder_volume(options: &TextureOptions) -> Self {
+        let size = 32;
+        let pixels: Vec<u8> = (0..size * size * size)
+            .flat_map(|i| {
+                let (x, y, z) = (i % size, i / size % size, i / (size * size));
+                if (x / 8 + y / 8 + z / 8) % 2 == 0 {
+                    [255, 0, 255, 255]
+                } else {
+                    [0, 0, 0, 255]
+                }
+            })
+            .collect();
+        let mut id = 0;
+        unsafe {
+            gl::GenTextures(1, &mut id);
+            gl::BindTexture(gl::TEXTURE_3D, id);
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+            gl::TexImage3D(
+                gl::TEXTURE_3D,
+                0,
+                gl::RGBA8 as i32,
+                size as i32,
+                size as i32,
+                size as i32,
+                0,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                pixels.as_ptr().cast(),
+            );
+        }
+        let texture = Texture {
+            id,
+            target: gl::TEXTURE_3D,
+            width: size,
+            height: size,
+        };
+        texture.set_options(options);
+        texture
+    }
+
+    pub(crate) fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Changes the sampler settings, generating mipmaps if they are turned on
+    pub fn set_options(&self, options: &TextureOptions) {
+        let min_filter = match (options.min_filter, options.mipmaps) {
+            (Filter::Nearest, false) => gl::NEAREST,
+            (Filter::Linear, false) => gl::LINEAR,
+            (Filter::Nearest, true) => gl::NEAREST_MIPMAP_NEAREST,
+            (Filter::Linear, true) => gl::LINEAR_MIPMAP_LINEAR,
+        };
+        let mag_filter = match options.mag_filter {
+            Filter::Nearest => gl::NEAREST,
+            Filter::Linear => gl::LINEAR,
+        };
+        let wrap = match options.wrap {
+            Wrap::Repeat => gl::REPEAT,
+            Wrap::MirroredRepeat => gl::MIRRORED_REPEAT,
+            Wrap::ClampToEdge => gl::CLAMP_TO_EDGE,
+        };
+
+        let target = self.target;
+        unsafe {
+            gl::BindTexture(target, self.id);
+            gl::TexParameteri(target, gl::TEXTURE_MIN_FILTER, min_filter as i32);
+            gl::TexParameteri(target, gl::TEXTURE_MAG_FILTER, mag_filter as i32);
+            gl::TexParameteri(target, gl::TEXTURE_WRAP_S, wrap as i32);
+            gl::TexParameteri(target, gl::TEXTURE_WRAP_T, wrap as i32);
+            gl::TexParameteri(target, gl::TEXTURE_WRAP_R, wrap as i32);
+            if options.mipmaps {
+                gl::GenerateMipmap(target);
+            }
+        }
+    }
+
+    /// Binds the texture to texture unit `unit`
+    pub fn bind(&self, unit: u32) {
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0 + unit);
+            gl::BindTexture(self.target, self.id);
+        }
+    }
+}
+
+impl Drop for Texture {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteTextures(1, &self.id);
+        }
+    }
+}
+
+/// Textures bound to `sampler2D` uniforms by name, each getting its own texture unit
+#[derive(Default)]
+pub struct TextureBindings {
+    bindings: Vec<(String, Texture)>,
+}
+
+impl TextureBindings {
+    /// Adds a texture for the sampler uniform `name`, replacing an earlier one with that name
+    pub fn insert(&mut self, name: &str, texture: Texture) {
+        self.bindings.retain(|(existing, _)| existing != name);
+        self.bindings.push((name.to_string(), texture));
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Texture> {
+        self.bindings
+            .iter()
+            .find(|(existing, _)| existing == name)
+            .map(|(_, texture)| texture)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bindings.is_empty()
+    }
+
+    /// Names of the bound sampler uniforms, in texture unit order
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.bindings.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Binds every texture to its unit and points the sampler uniforms of `program` at them
+    ///
+    /// `program` has to be in use. Samplers the program doesn't have are skipped.
+    pub fn bind(&self, backend: &mut impl RenderBackend, program: &ShaderProgram) {
+        for (unit, (name, texture)) in self.bindings.iter().enumerate() {
+            backend.bind_texture(unit as u32, texture);
+            backend.set_uniform(program, name, UniformValue::Int(unit as i32));
+        }
+    }
+
+    /// Unbinds the units [`TextureBindings::bind`] bound
+    pub fn unbind(&self, backend: &mut impl RenderBackend) {
+        for unit in 0..self.bindings.len() {
+            backend.unbind_texture(unit as u32);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_decode_flips_rows() {
+        // 1x2 image, red on top and blue at the bottom
+        let mut image = image::RgbaImage::new(1, 2);
+        image.put_pixel(0, 0, image::Rgba([255, 0, 0, 255]));
+        image.put_pixel(0, 1, image::Rgba([0, 0, 255, 255]));
+        let mut png = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+
+        let mut data = TextureData::decode(&png).unwrap();
+        assert_eq!((data.width, data.height), (1, 2));
+        assert_eq!(
+            data.pixels,
+            Pixels::Rgba8(vec![0, 0, 255, 255, 255, 0, 0, 255])
+        );
+        data.flip_vertically();
+        assert_eq!(
+            data.pixels,
+            Pixels::Rgba8(vec![255, 0, 0, 255, 0, 0, 255, 255])
+        );
+    }
+
+    #[test]
+    fn test_decode_hdr() {
+        let image = image::Rgb32FImage::from_pixel(2, 1, image::Rgb([4.0, 0.5, 0.0]));
+        let mut hdr = Vec::new();
+        image::DynamicImage::ImageRgb32F(image)
+            .write_to(&mut Cursor::new(&mut hdr), image::ImageFormat::Hdr)
+            .unwrap();
+
+        let data = TextureData::decode(&hdr).unwrap();
+        let Pixels::RgbaF32(pixels) = data.pixels else {
+            panic!("HDR image should decode to float pixels");
+        };
+        assert_eq!(pixels.len(), 2 * 4);
+        assert!((pixels[0] - 4.0).abs() < 0.1);
+        assert_eq!(pixels[3], 1.0);
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        assert!(TextureData::decode(b"not an image").is_err());
+    }
+}
@@ -0,0 +1,158 @@
+//! Window creation and fullscreen switching
+use glfw::{Context, Glfw, GlfwReceiver, Monitor, PWindow, WindowEvent, WindowHint, WindowMode};
+use serde::Deserialize;
+
+/// Window settings, the `[window]` section of the config file
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WindowConfig {
+    /// Size of the window in screen coordinates, ignored in fullscreen
+    pub width: u32,
+    pub height: u32,
+    pub title: String,
+    /// Starts in fullscreen, using the current video mode of the monitor
+    pub fullscreen: bool,
+    /// Index of the monitor used for fullscreen, the primary monitor if not set
+    pub monitor: Option<usize>,
+    /// Waits for the vertical blank before swapping, turn it off to benchmark
+    pub vsync: bool,
+    /// Multisample anti-aliasing samples per pixel, 0 turns it off
+    pub samples: u32,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        WindowConfig {
+            width: 800,
+            height: 600,
+            title: "ShaderPreview".to_string(),
+            fullscreen: false,
+            monitor: None,
+            vsync: true,
+            samples: 0,
+        }
+    }
+}
+
+/// Runs `f` with the monitor at `index`, or the primary monitor
+fn with_monitor<T>(
+    glfw: &mut Glfw,
+    index: Option<usize>,
+    f: impl FnOnce(&mut Glfw, &Monitor) -> T,
+) -> Result<T, String> {
+    // with_connected_monitors builds a slice from a null pointer when there are no monitors
+    if glfw.with_primary_monitor(|_, monitor| monitor.is_none()) {
+        return Err("No monitor connected".to_string());
+    }
+    match index {
+        None => glfw.with_primary_monitor(|glfw, monitor| match monitor {
+            Some(monitor) => Ok(f(glfw, monitor)),
+            None => Err("No monitor connected".to_string()),
+        }),
+        Some(index) => glfw.with_connected_monitors(|glfw, monitors| match monitors.get(index) {
+            Some(monitor) => Ok(f(glfw, monitor)),
+            None => {
+                let names: Vec<String> = monitors
+                    .iter()
+                    .enumerate()
+                    .map(|(i, m)| format!("{i}: {}", m.get_name().unwrap_or_default()))
+                    .collect();
+                Err(format!(
+                    "Monitor {index} doesn't exist, connected monitors are [{}]",
+                    names.join(", ")
+                ))
+            }
+        }),
+    }
+}
+
+/// Creates the window and makes its context current
+pub fn create_window(
+    glfw: &mut Glfw,
+    config: &WindowConfig,
+) -> Result<(PWindow, GlfwReceiver<(f64, WindowEvent)>), String> {
+    glfw.window_hint(WindowHint::Samples(
+        (config.samples > 0).then_some(config.samples),
+    ));
+
+    let (mut window, events) = if config.fullscreen {
+        with_monitor(glfw, config.monitor, |glfw, monitor| {
+            let (width, height) = monitor
+                .get_video_mode()
+                .map_or((config.width, config.height), |mode| {
+                    (mode.width, mode.height)
+                });
+            glfw.create_window(
+                width,
+                height,
+                &config.title,
+                WindowMode::FullScreen(monitor),
+            )
+        })?
+    } else {
+        glfw.create_window(
+            config.width,
+            config.height,
+            &config.title,
+            WindowMode::Windowed,
+        )
+    }
+    .ok_or("Failed to create GLFW window.")?;
+
+    window.make_current();
+    glfw.set_swap_interval(if config.vsync {
+        glfw::SwapInterval::Sync(1)
+    } else {
+        glfw::SwapInterval::None
+    });
+    Ok((window, events))
+}
+
+/// Position and size of the window before going fullscreen
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindowedState {
+    pub position: (i32, i32),
+    pub size: (i32, i32),
+}
+
+/// Switches between windowed and fullscreen on the configured monitor
+pub fn toggle_fullscreen(
+    glfw: &mut Glfw,
+    window: &mut PWindow,
+    monitor: Option<usize>,
+    windowed: &mut WindowedState,
+) -> Result<(), String> {
+    let is_fullscreen = window.with_window_mode(|mode| matches!(mode, WindowMode::FullScreen(_)));
+    if is_fullscreen {
+        let WindowedState {
+            position: (x, y),
+            size: (width, height),
+        } = *windowed;
+        window.set_monitor(
+            WindowMode::Windowed,
+            x,
+            y,
+            width as u32,
+            height as u32,
+            None,
+        );
+        return Ok(());
+    }
+
+    *windowed = WindowedState {
+        position: window.get_pos(),
+        size: window.get_size(),
+    };
+    with_monitor(glfw, monitor, |_, monitor| {
+        if let Some(mode) = monitor.get_video_mode() {
+            window.set_monitor(
+                WindowMode::FullScreen(monitor),
+                0,
+                0,
+                mode.width,
+                mode.height,
+                Some(mode.refresh_rate),
+            );
+        }
+    })
+}
@@ -0,0 +1,356 @@
+//! Generators for common 2D shapes
+//!
+//! Outline generators return the boundary of the shape as a counter-clockwise [`Polygon`],
+//! which can be drawn directly with `gl::LINE_LOOP`. Use [`triangulate`] to turn an outline
+//! into a triangle list that can be passed to `Mesh::from_polygon` with `gl::TRIANGLES`.
+use crate::Polygon;
+use std::f32::consts::TAU;
+use std::ops::RangeInclusive;
+use vatnar_linalg::Point2;
+
+/// Regular polygon with `sides` corners, the first corner pointing along the positive x-axis
+///
+/// # Arguments
+/// * `center` - center of the polygon
+/// * `radius` - distance from center to every corner
+/// * `sides` - number of corners, at least 3
+///
+/// # Examples
+/// ```
+/// use shader_preview::shapes;
+/// use vatnar_linalg::Point2;
+///
+/// let hexagon = shapes::regular_polygon(Point2::new(0.0, 0.0), 1.0, 6);
+/// assert_eq!(hexagon.len(), 6);
+/// ```
+pub fn regular_polygon(center: Point2<f32>, radius: f32, sides: usize) -> Polygon {
+    assert!(sides >= 3, "A polygon needs at least 3 sides, got {sides}");
+    Polygon(
+        (0..sides)
+            .map(|i| point_on_circle(center, radius, TAU * i as f32 / sides as f32))
+            .collect(),
+    )
+}
+
+/// Circle approximated by a regular polygon with `segments` sides
+pub fn circle(center: Point2<f32>, radius: f32, segments: usize) -> Polygon {
+    regular_polygon(center, radius, segments)
+}
+
+/// Axis aligned rectangle
+///
+/// # Arguments
+/// * `min` - bottom left corner
+/// * `width`, `height` - size of the rectangle
+pub fn rectangle(min: Point2<f32>, width: f32, height: f32) -> Polygon {
+    Polygon(vec![
+        Point2::new(min.x, min.y),
+        Point2::new(min.x + width, min.y),
+        Point2::new(min.x + width, min.y + height),
+        Point2::new(min.x, min.y + height),
+    ])
+}
+
+/// Axis aligned rectangle with rounded corners
+///
+/// # Arguments
+/// * `min` - bottom left corner
+/// * `width`, `height` - size of the rectangle
+/// * `radius` - corner radius, clamped to half of the shortest side, and to 0 for negative
+///   sizes
+/// * `corner_segments` - amount of segments used for each corner, 0 gives sharp corners
+pub fn rounded_rectangle(
+    min: Point2<f32>,
+    width: f32,
+    height: f32,
+    radius: f32,
+    corner_segments: usize,
+) -> Polygon {
+    let radius = radius.clamp(0.0, (width.min(height) / 2.0).max(0.0));
+    if corner_segments == 0 || radius == 0.0 {
+        return rectangle(min, width, height);
+    }
+
+    // corner centers, counter-clockwise starting bottom right
+    let corners = [
+        Point2::new(min.x + width - radius, min.y + radius),
+        Point2::new(min.x + width - radius, min.y + height - radius),
+        Point2::new(min.x + radius, min.y + height - radius),
+        Point2::new(min.x + radius, min.y + radius),
+    ];
+
+    let mut points = Vec::with_capacity(4 * (corner_segments + 1));
+    for (i, center) in corners.into_iter().enumerate() {
+        // bottom right corner sweeps from -90 to 0 degrees, the rest follow a quarter turn each
+        let start = TAU * (i as f32 - 1.0) / 4.0;
+        for step in 0..=corner_segments {
+            let angle = start + TAU / 4.0 * step as f32 / corner_segments as f32;
+            points.push(point_on_circle(center, radius, angle));
+        }
+    }
+    Polygon(points)
+}
+
+/// Star with `points` tips, alternating between `outer_radius` and `inner_radius`
+///
+/// The first tip points along the positive y-axis.
+pub fn star(center: Point2<f32>, outer_radius: f32, inner_radius: f32, points: usize) -> Polygon {
+    assert!(points >= 2, "A star needs at least 2 points, got {points}");
+    let corners = points * 2;
+    Polygon(
+        (0..corners)
+            .map(|i| {
+                let radius = if i % 2 == 0 {
+                    outer_radius
+                } else {
+                    inner_radius
+                };
+                let angle = TAU / 4.0 + TAU * i as f32 / corners as f32;
+                point_on_circle(center, radius, angle)
+            })
+            .collect(),
+    )
+}
+
+/// Open arc from `start_angle` to `end_angle` (radians, counter-clockwise from the x-axis)
+///
+/// Returns `segments + 1` points, meant to be drawn with `gl::LINE_STRIP`
+/// or turned into a thick line with [`crate::stroke::stroke`].
+pub fn arc(
+    center: Point2<f32>,
+    radius: f32,
+    start_angle: f32,
+    end_angle: f32,
+    segments: usize,
+) -> Polygon {
+    assert!(segments >= 1, "An arc needs at least 1 segment");
+    Polygon(
+        (0..=segments)
+            .map(|i| {
+                let t = i as f32 / segments as f32;
+                point_on_circle(center, radius, start_angle + (end_angle - start_angle) * t)
+            })
+            .collect(),
+    )
+}
+
+/// Filled ring (annulus) between `inner_radius` and `outer_radius`
+///
+/// Unlike the outline generators this returns a triangle list for `gl::TRIANGLES`,
+/// since a ring can not be described by a single outline.
+pub fn ring(center: Point2<f32>, inner_radius: f32, outer_radius: f32, segments: usize) -> Polygon {
+    assert!(
+        segments >= 3,
+        "A ring needs at least 3 segments, got {segments}"
+    );
+    let mut triangles = Vec::with_capacity(segments * 6);
+    for i in 0..segments {
+        let a0 = TAU * i as f32 / segments as f32;
+        let a1 = TAU * (i + 1) as f32 / segments as f32;
+
+        let inner0 = point_on_circle(center, inner_radius, a0);
+        let inner1 = point_on_circle(center, inner_radius, a1);
+        let outer0 = point_on_circle(center, outer_radius, a0);
+        let outer1 = point_on_circle(center, outer_radius, a1);
+
+        triangles.extend([inner0, outer0, outer1, inner0, outer1, inner1]);
+    }
+    Polygon(triangles)
+}
+
+/// Grid of lines covering the rectangle from `min` to `max`, one line every `spacing` units
+///
+/// Lines are placed on multiples of `spacing`, so grids of different extents line up.
+/// Returns pairs of points meant to be drawn with `gl::LINES`.
+pub fn grid(min: Point2<f32>, max: Point2<f32>, spacing: f32) -> Polygon {
+    assert!(
+        spacing > 0.0,
+        "Grid spacing has to be positive, got {spacing}"
+    );
+    let multiples = |min: f32, max: f32| {
+        grid_multiples(min as f64, max as f64, spacing as f64).map(|i| i as f32 * spacing)
+    };
+    let mut lines = Vec::new();
+    for x in multiples(min.x, max.x) {
+        lines.extend([Point2::new(x, min.y), Point2::new(x, max.y)]);
+    }
+    for y in multiples(min.y, max.y) {
+        lines.extend([Point2::new(min.x, y), Point2::new(max.x, y)]);
+    }
+    Polygon(lines)
+}
+
+/// Indices `i` of the grid lines `i * spacing` between `min` and `max`
+///
+/// Counting multiples instead of adding up `spacing`, which stops advancing once it's
+/// below the precision of large coordinates.
+pub fn grid_multiples(min: f64, max: f64, spacing: f64) -> RangeInclusive<i64> {
+    (min / spacing).ceil() as i64..=(max / spacing).floor() as i64
+}
+
+/// Triangulates a simple (non self-intersecting) outline into a triangle list using ear clipping
+///
+/// Works for both convex and concave outlines of either winding order.
+/// The returned [`Polygon`] holds 3 points per triangle, ready for `gl::TRIANGLES`.
+///
+/// # Examples
+/// ```
+/// use shader_preview::shapes;
+/// use vatnar_linalg::Point2;
+///
+/// let star = shapes::star(Point2::new(0.0, 0.0), 1.0, 0.5, 5);
+/// let triangles = shapes::triangulate(&star);
+/// assert_eq!(triangles.len(), (star.len() - 2) * 3);
+/// ```
+pub fn triangulate(polygon: &Polygon) -> Polygon {
+    let mut remaining: Vec<usize> = (0..polygon.len()).collect();
+    if remaining.len() < 3 {
+        return Polygon(Vec::new());
+    }
+    // work on a counter-clockwise outline so convex corners have a positive cross product
+    if signed_area(polygon) < 0.0 {
+        remaining.reverse();
+    }
+
+    let mut triangles = Vec::with_capacity((polygon.len() - 2) * 3);
+    while remaining.len() > 3 {
+        let count = remaining.len();
+        let ear = (0..count).find(|&i| {
+            let prev = polygon[remaining[(i + count - 1) % count]];
+            let current = polygon[remaining[i]];
+            let next = polygon[remaining[(i + 1) % count]];
+
+            cross(prev, current, next) > 0.0
+                && !remaining.iter().any(|&other| {
+                    let p = polygon[other];
+                    p != prev && p != current && p != next && in_triangle(p, prev, current, next)
+                })
+        });
+
+        // degenerate outlines (collinear or self intersecting) have no proper ear left,
+        // clip any corner so we always terminate
+        let i = ear.unwrap_or(0);
+        triangles.extend([
+            polygon[remaining[(i + count - 1) % count]],
+            polygon[remaining[i]],
+            polygon[remaining[(i + 1) % count]],
+        ]);
+        remaining.remove(i);
+    }
+    triangles.extend(remaining.iter().map(|&i| polygon[i]));
+    Polygon(triangles)
+}
+
+/// Signed area of an outline, positive when the winding is counter-clockwise
+pub fn signed_area(polygon: &Polygon) -> f32 {
+    let n = polygon.len();
+    (0..n)
+        .map(|i| {
+            let a = polygon[i];
+            let b = polygon[(i + 1) % n];
+            a.x * b.y - b.x * a.y
+        })
+        .sum::<f32>()
+        / 2.0
+}
+
+fn point_on_circle(center: Point2<f32>, radius: f32, angle: f32) -> Point2<f32> {
+    Point2::new(
+        center.x + radius * angle.cos(),
+        center.y + radius * angle.sin(),
+    )
+}
+
+/// z component of the cross product of `a -> b` and `b -> c`
+fn cross(a: Point2<f32>, b: Point2<f32>, c: Point2<f32>) -> f32 {
+    (b.x - a.x) * (c.y - b.y) - (b.y - a.y) * (c.x - b.x)
+}
+
+fn in_triangle(p: Point2<f32>, a: Point2<f32>, b: Point2<f32>, c: Point2<f32>) -> bool {
+    cross(a, b, p) >= 0.0 && cross(b, c, p) >= 0.0 && cross(c, a, p) >= 0.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn triangle_area(triangles: &Polygon) -> f32 {
+        triangles
+            .chunks(3)
+            .map(|t| signed_area(&Polygon(t.to_vec())).abs())
+            .sum()
+    }
+
+    #[test]
+    fn test_regular_polygon() {
+        let square = regular_polygon(Point2::new(0.0, 0.0), 1.0, 4);
+        assert_eq!(square.len(), 4);
+        assert!((signed_area(&square) - 2.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_rounded_rectangle() {
+        let rect = rounded_rectangle(Point2::new(0.0, 0.0), 4.0, 2.0, 10.0, 8);
+        assert_eq!(rect.len(), 4 * 9);
+        // radius clamps to 1.0, so the area is the rectangle minus the 4 missing corner pieces
+        let expected = 8.0 - (4.0 - std::f32::consts::PI);
+        assert!((signed_area(&rect) - expected).abs() < 0.05);
+
+        let sharp = rounded_rectangle(Point2::new(0.0, 0.0), 4.0, 2.0, 1.0, 0);
+        assert_eq!(sharp.len(), 4);
+
+        let negative = rounded_rectangle(Point2::new(0.0, 0.0), -1.0, 5.0, 2.0, 8);
+        assert_eq!(negative.len(), 4);
+    }
+
+    #[test]
+    fn test_triangulate_concave() {
+        let star = star(Point2::new(1.0, 1.0), 2.0, 0.5, 5);
+        let triangles = triangulate(&star);
+        assert_eq!(triangles.len(), 8 * 3);
+        assert!((triangle_area(&triangles) - signed_area(&star)).abs() < 1e-4);
+
+        // clockwise input gives the same area
+        let mut reversed = star.0.clone();
+        reversed.reverse();
+        let triangles = triangulate(&Polygon(reversed));
+        assert!((triangle_area(&triangles) - signed_area(&star)).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_ring() {
+        let ring = ring(Point2::new(0.0, 0.0), 1.0, 2.0, 64);
+        assert_eq!(ring.len(), 64 * 6);
+        let expected = std::f32::consts::PI * 3.0;
+        assert!((triangle_area(&ring) - expected).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_grid() {
+        let lines = grid(Point2::new(-1.0, -1.0), Point2::new(1.0, 1.0), 0.5);
+        // 5 vertical and 5 horizontal lines
+        assert_eq!(lines.len(), 20);
+
+        // 1e8 + 1 rounds back to 1e8 in f32
+        let lines = grid(Point2::new(1e8, 0.0), Point2::new(1e8 + 16.0, 1.0), 1.0);
+        assert_eq!(lines.len(), (17 + 2) * 2);
+        assert_eq!(lines[0].x, 1e8);
+        assert_eq!(lines[32].x, 1e8 + 16.0);
+    }
+
+    #[test]
+    fn test_grid_multiples() {
+        assert_eq!(
+            grid_multiples(-2.5, 1.0, 1.0).collect::<Vec<_>>(),
+            [-2, -1, 0, 1]
+        );
+        assert_eq!(grid_multiples(0.1, 0.9, 0.5).collect::<Vec<_>>(), [1]);
+    }
+
+    #[test]
+    fn test_arc() {
+        let half = arc(Point2::new(0.0, 0.0), 1.0, 0.0, std::f32::consts::PI, 4);
+        assert_eq!(half.len(), 5);
+        assert!((half[4].x + 1.0).abs() < 1e-5);
+    }
+}
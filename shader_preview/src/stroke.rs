@@ -0,0 +1,367 @@
+//! Stroking of polylines into triangle geometry
+//!
+//! `glLineWidth` is capped at 1 pixel on most core profile drivers, so thick lines are built
+//! out of triangles instead. [`stroke`] returns a triangle list for `gl::TRIANGLES`.
+//!
+//! Segments and joins are emitted as separate triangles that overlap on the inside of turns,
+//! which is invisible for opaque colors but doubles up when drawn with a translucent color.
+use crate::Polygon;
+use std::f32::consts::PI;
+use vatnar_linalg::Point2;
+
+/// How two segments are connected at a corner
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineJoin {
+    /// Extends the outer edges until they meet, falls back to [`LineJoin::Bevel`]
+    /// when the miter would be longer than [`StrokeStyle::miter_limit`]
+    Miter,
+    /// Circular arc around the corner
+    Round,
+    /// Straight edge between the outer corners of both segments
+    Bevel,
+}
+
+/// How the ends of an open polyline are finished
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineCap {
+    /// Ends exactly at the end point
+    Butt,
+    /// Half circle around the end point
+    Round,
+    /// Extends half the line width past the end point
+    Square,
+}
+
+/// Parameters used by [`stroke`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StrokeStyle {
+    /// Full width of the line, in the same units as the points
+    pub width: f32,
+    pub join: LineJoin,
+    pub cap: LineCap,
+    /// Max ratio between miter length and line width before a miter join is beveled
+    pub miter_limit: f32,
+    /// Segments used for a half circle in round joins and caps
+    pub round_segments: usize,
+}
+
+impl Default for StrokeStyle {
+    fn default() -> Self {
+        StrokeStyle {
+            width: 1.0,
+            join: LineJoin::Miter,
+            cap: LineCap::Butt,
+            miter_limit: 4.0,
+            round_segments: 8,
+        }
+    }
+}
+
+impl StrokeStyle {
+    /// Default style with the given `width`
+    pub fn with_width(width: f32) -> Self {
+        StrokeStyle {
+            width,
+            ..Default::default()
+        }
+    }
+}
+
+/// Converts a polyline into a triangle list with the thickness, joins and caps of `style`
+///
+/// # Arguments
+/// * `points` - points of the polyline, consecutive duplicates are ignored
+/// * `closed` - connects the last point back to the first, caps are not drawn for closed loops
+/// * `style` - width, join and cap settings
+///
+/// # Examples
+/// ```
+/// use shader_preview::stroke::{stroke, StrokeStyle};
+/// use vatnar_linalg::Point2;
+///
+/// let line = [Point2::new(0.0, 0.0), Point2::new(1.0, 0.0)];
+/// let triangles = stroke(&line, false, &StrokeStyle::with_width(0.1));
+/// assert_eq!(triangles.len(), 6);
+/// ```
+pub fn stroke(points: &[Point2<f32>], closed: bool, style: &StrokeStyle) -> Polygon {
+    let mut points: Vec<Point2<f32>> = points.to_vec();
+    points.dedup();
+    if closed && points.len() > 1 && points.first() == points.last() {
+        points.pop();
+    }
+
+    let mut triangles = Vec::new();
+    if points.len() < 2 {
+        return Polygon(triangles);
+    }
+
+    let half_width = style.width / 2.0;
+    let segment_count = if closed {
+        points.len()
+    } else {
+        points.len() - 1
+    };
+    let segment = |i: usize| (points[i], points[(i + 1) % points.len()]);
+
+    for i in 0..segment_count {
+        let (a, b) = segment(i);
+        let normal = scale(normal(a, b), half_width);
+        triangles.extend([
+            offset(a, normal, 1.0),
+            offset(a, normal, -1.0),
+            offset(b, normal, 1.0),
+            offset(b, normal, 1.0),
+            offset(a, normal, -1.0),
+            offset(b, normal, -1.0),
+        ]);
+    }
+
+    // joins between every pair of consecutive segments
+    let join_count = if closed {
+        segment_count
+    } else {
+        segment_count - 1
+    };
+    for i in 0..join_count {
+        let (a, corner) = segment(i);
+        let (_, c) = segment((i + 1) % segment_count);
+        add_join(&mut triangles, a, corner, c, half_width, style);
+    }
+
+    if !closed {
+        let last = points.len() - 1;
+        add_cap(&mut triangles, points[1], points[0], half_width, style);
+        add_cap(
+            &mut triangles,
+            points[last - 1],
+            points[last],
+            half_width,
+            style,
+        );
+    }
+
+    Polygon(triangles)
+}
+
+/// Fills the outer wedge at `corner` between the segments `a -> corner` and `corner -> c`
+fn add_join(
+    triangles: &mut Vec<Point2<f32>>,
+    a: Point2<f32>,
+    corner: Point2<f32>,
+    c: Point2<f32>,
+    half_width: f32,
+    style: &StrokeStyle,
+) {
+    let d0 = direction(a, corner);
+    let d1 = direction(corner, c);
+    let turn = d0.0 * d1.1 - d0.1 * d1.0;
+    if turn.abs() < 1e-6 && d0.0 * d1.0 + d0.1 * d1.1 > 0.0 {
+        return; // straight continuation, nothing to fill
+    }
+
+    // the outer side of a left turn is the right side of the line and vice versa
+    let side = if turn > 0.0 { -1.0 } else { 1.0 };
+    let n0 = scale(normal(a, corner), half_width * side);
+    let n1 = scale(normal(corner, c), half_width * side);
+    let outer0 = offset(corner, n0, 1.0);
+    let outer1 = offset(corner, n1, 1.0);
+
+    match style.join {
+        LineJoin::Bevel => triangles.extend([corner, outer0, outer1]),
+        LineJoin::Round => add_fan(triangles, corner, n0, n1, half_width, style.round_segments),
+        LineJoin::Miter => {
+            let bisector = (n0.0 + n1.0, n0.1 + n1.1);
+            let bisector_len = (bisector.0 * bisector.0 + bisector.1 * bisector.1).sqrt();
+            // cos of half the angle between the normals
+            let cos_half = bisector_len / (2.0 * half_width);
+            if cos_half < 1e-6 || 1.0 / cos_half > style.miter_limit {
+                triangles.extend([corner, outer0, outer1]);
+                return;
+            }
+            let miter = offset(
+                corner,
+                scale(
+                    (bisector.0 / bisector_len, bisector.1 / bisector_len),
+                    half_width / cos_half,
+                ),
+                1.0,
+            );
+            triangles.extend([corner, outer0, miter, corner, miter, outer1]);
+        }
+    }
+}
+
+/// Adds the cap at `end`, for a segment coming from `from`
+fn add_cap(
+    triangles: &mut Vec<Point2<f32>>,
+    from: Point2<f32>,
+    end: Point2<f32>,
+    half_width: f32,
+    style: &StrokeStyle,
+) {
+    let n = scale(normal(from, end), half_width);
+    match style.cap {
+        LineCap::Butt => {}
+        LineCap::Square => {
+            let d = scale(direction(from, end), half_width);
+            let extended = offset(end, d, 1.0);
+            triangles.extend([
+                offset(end, n, 1.0),
+                offset(end, n, -1.0),
+                offset(extended, n, 1.0),
+                offset(extended, n, 1.0),
+                offset(end, n, -1.0),
+                offset(extended, n, -1.0),
+            ]);
+        }
+        LineCap::Round => {
+            // sweep clockwise from the left edge, around the end, to the right edge
+            let minus_n = (-n.0, -n.1);
+            add_fan(triangles, end, n, minus_n, half_width, style.round_segments);
+        }
+    }
+}
+
+/// Triangle fan around `center` sweeping from offset `from` to offset `to`
+/// the short way around, or clockwise when they are opposite
+fn add_fan(
+    triangles: &mut Vec<Point2<f32>>,
+    center: Point2<f32>,
+    from: (f32, f32),
+    to: (f32, f32),
+    radius: f32,
+    half_circle_segments: usize,
+) {
+    let start = from.1.atan2(from.0);
+    let mut sweep = to.1.atan2(to.0) - start;
+    if sweep > PI {
+        sweep -= 2.0 * PI;
+    } else if sweep <= -PI {
+        sweep += 2.0 * PI;
+    }
+    if (sweep.abs() - PI).abs() < 1e-4 {
+        sweep = -PI;
+    }
+
+    let steps = ((sweep.abs() / PI * half_circle_segments as f32).ceil() as usize).max(1);
+    let at = |i: usize| {
+        let angle = start + sweep * i as f32 / steps as f32;
+        Point2::new(
+            center.x + radius * angle.cos(),
+            center.y + radius * angle.sin(),
+        )
+    };
+    for i in 0..steps {
+        triangles.extend([center, at(i), at(i + 1)]);
+    }
+}
+
+/// Unit direction from `a` to `b`
+fn direction(a: Point2<f32>, b: Point2<f32>) -> (f32, f32) {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let len = (dx * dx + dy * dy).sqrt();
+    (dx / len, dy / len)
+}
+
+/// Unit normal pointing to the left of the direction from `a` to `b`
+fn normal(a: Point2<f32>, b: Point2<f32>) -> (f32, f32) {
+    let (dx, dy) = direction(a, b);
+    (-dy, dx)
+}
+
+fn scale(v: (f32, f32), s: f32) -> (f32, f32) {
+    (v.0 * s, v.1 * s)
+}
+
+fn offset(p: Point2<f32>, v: (f32, f32), sign: f32) -> Point2<f32> {
+    Point2::new(p.x + v.0 * sign, p.y + v.1 * sign)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shapes::signed_area;
+
+    fn area(triangles: &Polygon) -> f32 {
+        triangles
+            .chunks(3)
+            .map(|t| signed_area(&Polygon(t.to_vec())).abs())
+            .sum()
+    }
+
+    fn max_x(triangles: &Polygon) -> f32 {
+        triangles.iter().map(|p| p.x).fold(f32::MIN, f32::max)
+    }
+
+    #[test]
+    fn test_stroke_caps() {
+        let line = [Point2::new(0.0, 0.0), Point2::new(2.0, 0.0)];
+        let mut style = StrokeStyle::with_width(0.5);
+
+        let butt = stroke(&line, false, &style);
+        assert!((area(&butt) - 1.0).abs() < 1e-5);
+
+        style.cap = LineCap::Square;
+        let square = stroke(&line, false, &style);
+        assert!((area(&square) - 1.25).abs() < 1e-5);
+        assert!((max_x(&square) - 2.25).abs() < 1e-5);
+
+        style.cap = LineCap::Round;
+        style.round_segments = 64;
+        let round = stroke(&line, false, &style);
+        let expected = 1.0 + PI * 0.25 * 0.25;
+        assert!((area(&round) - expected).abs() < 1e-3);
+        assert!((max_x(&round) - 2.25).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_stroke_joins() {
+        let corner = [
+            Point2::new(0.0, 0.0),
+            Point2::new(1.0, 0.0),
+            Point2::new(1.0, 1.0),
+        ];
+        let mut style = StrokeStyle::with_width(0.2);
+
+        // a square miter reaches the corner of the outer edges
+        let miter = stroke(&corner, false, &style);
+        assert!((max_x(&miter) - 1.1).abs() < 1e-5);
+        assert!(
+            miter
+                .iter()
+                .any(|p| (p.y + 0.1).abs() < 1e-5 && (p.x - 1.1).abs() < 1e-5)
+        );
+
+        style.miter_limit = 1.0;
+        let limited = stroke(&corner, false, &style);
+        assert_eq!(limited.len(), 12 + 3);
+
+        style.join = LineJoin::Round;
+        let round = stroke(&corner, false, &style);
+        assert!(round.len() > limited.len());
+    }
+
+    #[test]
+    fn test_stroke_closed() {
+        let square = [
+            Point2::new(0.0, 0.0),
+            Point2::new(1.0, 0.0),
+            Point2::new(1.0, 1.0),
+            Point2::new(0.0, 1.0),
+            Point2::new(0.0, 0.0),
+        ];
+        let style = StrokeStyle::with_width(0.2);
+        let triangles = stroke(&square, true, &style);
+        // 4 segments and 4 miter joins
+        assert_eq!(triangles.len(), 4 * 6 + 4 * 6);
+        assert!((max_x(&triangles) - 1.1).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_stroke_degenerate() {
+        let style = StrokeStyle::default();
+        assert!(stroke(&[], false, &style).is_empty());
+        let point = [Point2::new(1.0, 1.0), Point2::new(1.0, 1.0)];
+        assert!(stroke(&point, false, &style).is_empty());
+    }
+}
@@ -3,16 +3,12 @@
 layout (location = 0) in vec2 aPos;
 uniform float u_time;
 
-uniform vec2 u_offset;
-// TODO offset by zoom somehow, scaling
-uniform float u_zoom;
+// world to clip space, from Camera2D
+uniform mat3 u_view;
 
 void main() {
-    // zoom center is at screen center (0,0) — no need to compute zoom center in this case
-    vec2 zoomedPos = (aPos + u_offset) * u_zoom;
+    vec3 clipPos = u_view * vec3(aPos, 1.0);
 
-    gl_Position = vec4(zoomedPos, 0.0, 1.0);
+    gl_Position = vec4(clipPos.xy, 0.0, 1.0);
     gl_PointSize = 20.0;
 }
-
-
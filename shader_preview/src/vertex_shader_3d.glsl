@@ -0,0 +1,19 @@
+#version 330 core
+
+layout (location = 0) in vec3 aPos;
+layout (location = 1) in vec3 aNormal;
+layout (location = 2) in vec2 aUv;
+
+uniform mat4 u_model;
+uniform mat4 u_view;
+uniform mat4 u_projection;
+
+out vec3 vNormal;
+out vec2 vUv;
+
+void main() {
+    // model matrix only rotates and uniformly scales, so it is fine for normals too
+    vNormal = mat3(u_model) * aNormal;
+    vUv = aUv;
+    gl_Position = u_projection * u_view * u_model * vec4(aPos, 1.0);
+}
@@ -0,0 +1,16 @@
+#version 330 core
+
+layout (location = 0) in vec2 aPos;
+layout (location = 3) in vec4 aColor;
+
+// world to clip space, from Camera2D
+uniform mat3 u_view;
+
+out vec4 vColor;
+
+void main() {
+    vec3 clipPos = u_view * vec3(aPos, 1.0);
+
+    gl_Position = vec4(clipPos.xy, 0.0, 1.0);
+    vColor = aColor;
+}
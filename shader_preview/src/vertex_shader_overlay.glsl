@@ -0,0 +1,19 @@
+#version 330 core
+
+// position in pixels, origin in the top left corner
+layout (location = 0) in vec2 aPos;
+layout (location = 2) in vec2 aUv;
+layout (location = 3) in vec4 aColor;
+
+uniform vec2 u_screen;
+
+out vec2 vUv;
+out vec4 vColor;
+
+void main() {
+    vec2 clipPos = aPos / u_screen * 2.0 - 1.0;
+
+    gl_Position = vec4(clipPos.x, -clipPos.y, 0.0, 1.0);
+    vUv = aUv;
+    vColor = aColor;
+}
@@ -3,7 +3,7 @@
 pub use matrix::{Matrix, matrix_view::MatrixView};
 pub use point::Point2;
 pub use scalar::Scalar;
-pub use vector::Vector2;
+pub use vector::{Vector2, Vector3};
 
 pub mod matrix;
 pub mod point;
@@ -1,6 +1,7 @@
 //! Trait implementations for [`Matrix`]
 use super::Matrix;
 use std::fmt;
+use std::ops::{Index, IndexMut, Mul};
 
 impl fmt::Display for Matrix {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -32,3 +33,50 @@ impl PartialEq<Self> for Matrix {
 }
 
 impl Eq for Matrix {}
+
+/// Indexes the matrix with **0-based** `(row, col)`, unlike the 1-based [`Matrix::get`]
+impl Index<(usize, usize)> for Matrix {
+    type Output = f64;
+
+    fn index(&self, (row, col): (usize, usize)) -> &Self::Output {
+        assert!(row < self.rows && col < self.cols);
+        &self.data[row * self.cols + col]
+    }
+}
+
+impl IndexMut<(usize, usize)> for Matrix {
+    fn index_mut(&mut self, (row, col): (usize, usize)) -> &mut Self::Output {
+        assert!(row < self.rows && col < self.cols);
+        &mut self.data[row * self.cols + col]
+    }
+}
+
+/// Matrix product, panics if the columns of `self` don't match the rows of `rhs`
+impl Mul<&Matrix> for &Matrix {
+    type Output = Matrix;
+
+    fn mul(self, rhs: &Matrix) -> Self::Output {
+        assert_eq!(
+            self.cols, rhs.rows,
+            "Can't multiply {}x{} matrix with {}x{} matrix",
+            self.rows, self.cols, rhs.rows, rhs.cols
+        );
+        let mut data = vec![0.0; self.rows * rhs.cols];
+        for row in 0..self.rows {
+            for col in 0..rhs.cols {
+                data[row * rhs.cols + col] = (0..self.cols)
+                    .map(|k| self.data[row * self.cols + k] * rhs.data[k * rhs.cols + col])
+                    .sum();
+            }
+        }
+        Matrix::new(self.rows, rhs.cols, &data)
+    }
+}
+
+impl Mul for Matrix {
+    type Output = Matrix;
+
+    fn mul(self, rhs: Matrix) -> Self::Output {
+        &self * &rhs
+    }
+}
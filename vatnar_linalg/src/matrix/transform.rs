@@ -0,0 +1,212 @@
+//! Transformation matrices for 2D and 3D graphics
+//!
+//! 3D transforms are 4x4 matrices and 2D transforms (the `_2d` functions) 3x3 matrices, both
+//! meant to be applied to column vectors, `M * v`, so `a * b` applies `b` first.
+//! Use [`Matrix::to_column_major_f32`] to upload them to OpenGL.
+use super::Matrix;
+use crate::{Vector2, Vector3};
+
+impl Matrix {
+    /// Translation by `offset`
+    pub fn translation(offset: Vector3<f64>) -> Self {
+        let mut m = Matrix::identity(4);
+        m[(0, 3)] = offset.x;
+        m[(1, 3)] = offset.y;
+        m[(2, 3)] = offset.z;
+        m
+    }
+
+    /// Non-uniform scaling along each axis
+    pub fn scaling(factors: Vector3<f64>) -> Self {
+        let mut m = Matrix::identity(4);
+        m[(0, 0)] = factors.x;
+        m[(1, 1)] = factors.y;
+        m[(2, 2)] = factors.z;
+        m
+    }
+
+    /// Counter-clockwise rotation around the x-axis by `angle` radians
+    pub fn rotation_x(angle: f64) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        let mut m = Matrix::identity(4);
+        m[(1, 1)] = cos;
+        m[(1, 2)] = -sin;
+        m[(2, 1)] = sin;
+        m[(2, 2)] = cos;
+        m
+    }
+
+    /// Counter-clockwise rotation around the y-axis by `angle` radians
+    pub fn rotation_y(angle: f64) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        let mut m = Matrix::identity(4);
+        m[(0, 0)] = cos;
+        m[(0, 2)] = sin;
+        m[(2, 0)] = -sin;
+        m[(2, 2)] = cos;
+        m
+    }
+
+    /// Counter-clockwise rotation around the z-axis by `angle` radians
+    pub fn rotation_z(angle: f64) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        let mut m = Matrix::identity(4);
+        m[(0, 0)] = cos;
+        m[(0, 1)] = -sin;
+        m[(1, 0)] = sin;
+        m[(1, 1)] = cos;
+        m
+    }
+
+    /// OpenGL style perspective projection, mapping the view frustum to clip space
+    ///
+    /// # Arguments
+    /// * `fov_y` - vertical field of view in radians
+    /// * `aspect` - width divided by height of the viewport
+    /// * `near`, `far` - distance to the clipping planes, both positive
+    pub fn perspective(fov_y: f64, aspect: f64, near: f64, far: f64) -> Self {
+        let f = 1.0 / (fov_y / 2.0).tan();
+        let mut m = Matrix::new(4, 4, &[0.0; 16]);
+        m[(0, 0)] = f / aspect;
+        m[(1, 1)] = f;
+        m[(2, 2)] = (far + near) / (near - far);
+        m[(2, 3)] = 2.0 * far * near / (near - far);
+        m[(3, 2)] = -1.0;
+        m
+    }
+
+    /// View matrix for a camera at `eye` looking at `target`, right handed like OpenGL
+    ///
+    /// # Arguments
+    /// * `eye` - position of the camera
+    /// * `target` - point the camera looks at
+    /// * `up` - direction that should appear upwards, must not be parallel to the view direction
+    pub fn look_at(eye: Vector3<f64>, target: Vector3<f64>, up: Vector3<f64>) -> Self {
+        let forward = (target - eye).normalized();
+        let right = forward.cross(up).normalized();
+        let up = right.cross(forward);
+
+        #[rustfmt::skip]
+        let data = [
+            right.x, right.y, right.z, -right.dot(eye),
+            up.x, up.y, up.z, -up.dot(eye),
+            -forward.x, -forward.y, -forward.z, forward.dot(eye),
+            0.0, 0.0, 0.0, 1.0,
+        ];
+        Matrix::new(4, 4, &data)
+    }
+
+    /// Transforms a point with a 4x4 matrix, including the perspective divide
+    pub fn transform_point3(&self, point: Vector3<f64>) -> Vector3<f64> {
+        assert!(self.rows == 4 && self.cols == 4);
+        let row = |r: usize| {
+            self[(r, 0)] * point.x + self[(r, 1)] * point.y + self[(r, 2)] * point.z + self[(r, 3)]
+        };
+        let w = row(3);
+        Vector3::new(row(0) / w, row(1) / w, row(2) / w)
+    }
+
+    /// 2D translation by `offset`
+    pub fn translation_2d(offset: Vector2<f64>) -> Self {
+        let mut m = Matrix::identity(3);
+        m[(0, 2)] = offset.x;
+        m[(1, 2)] = offset.y;
+        m
+    }
+
+    /// 2D non-uniform scaling along each axis
+    pub fn scaling_2d(factors: Vector2<f64>) -> Self {
+        let mut m = Matrix::identity(3);
+        m[(0, 0)] = factors.x;
+        m[(1, 1)] = factors.y;
+        m
+    }
+
+    /// 2D counter-clockwise rotation by `angle` radians
+    pub fn rotation_2d(angle: f64) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        let mut m = Matrix::identity(3);
+        m[(0, 0)] = cos;
+        m[(0, 1)] = -sin;
+        m[(1, 0)] = sin;
+        m[(1, 1)] = cos;
+        m
+    }
+
+    /// Transforms a point with a 3x3 matrix, including the homogeneous divide
+    pub fn transform_point2(&self, point: Vector2<f64>) -> Vector2<f64> {
+        assert!(self.rows == 3 && self.cols == 3);
+        let row = |r: usize| self[(r, 0)] * point.x + self[(r, 1)] * point.y + self[(r, 2)];
+        let w = row(2);
+        Vector2::new(row(0) / w, row(1) / w)
+    }
+
+    /// Returns the matrix as [`f32`] in column-major order, the layout `glUniformMatrix*fv`
+    /// expects when `transpose` is `GL_FALSE`
+    pub fn to_column_major_f32(&self) -> Vec<f32> {
+        (0..self.cols)
+            .flat_map(|col| (0..self.rows).map(move |row| self[(row, col)] as f32))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: Vector3<f64>, b: Vector3<f64>) -> bool {
+        (a - b).mag() < 1e-9
+    }
+
+    #[test]
+    fn test_transform_order() {
+        let m = Matrix::translation(Vector3::new(1.0, 0.0, 0.0))
+            * Matrix::rotation_z(std::f64::consts::FRAC_PI_2);
+        // rotated first, then translated
+        let p = m.transform_point3(Vector3::new(1.0, 0.0, 0.0));
+        assert!(close(p, Vector3::new(1.0, 1.0, 0.0)));
+
+        let s = Matrix::scaling(Vector3::new(2.0, 3.0, 4.0));
+        let p = s.transform_point3(Vector3::new(1.0, 1.0, 1.0));
+        assert!(close(p, Vector3::new(2.0, 3.0, 4.0)));
+    }
+
+    #[test]
+    fn test_look_at_perspective() {
+        let eye = Vector3::new(0.0, 0.0, 5.0);
+        let view = Matrix::look_at(
+            eye,
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0),
+        );
+        // the target ends up straight ahead, 5 units down the negative z-axis
+        let p = view.transform_point3(Vector3::new(0.0, 0.0, 0.0));
+        assert!(close(p, Vector3::new(0.0, 0.0, -5.0)));
+
+        let projection = Matrix::perspective(std::f64::consts::FRAC_PI_2, 1.0, 1.0, 10.0);
+        let near = projection.transform_point3(Vector3::new(0.0, 0.0, -1.0));
+        let far = projection.transform_point3(Vector3::new(0.0, 0.0, -10.0));
+        assert!((near.z + 1.0).abs() < 1e-9);
+        assert!((far.z - 1.0).abs() < 1e-9);
+        // at 90 degrees fov the frustum edge at distance 1 is at y = 1
+        let edge = projection.transform_point3(Vector3::new(0.0, 1.0, -1.0));
+        assert!((edge.y - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_transform_2d() {
+        let m = Matrix::translation_2d(Vector2::new(1.0, 2.0))
+            * Matrix::rotation_2d(std::f64::consts::FRAC_PI_2)
+            * Matrix::scaling_2d(Vector2::new(2.0, 1.0));
+        // scaled, rotated, then translated
+        let p = m.transform_point2(Vector2::new(1.0, 0.0));
+        assert!((p - Vector2::new(1.0, 4.0)).mag() < 1e-9);
+    }
+
+    #[test]
+    fn test_column_major() {
+        let m = Matrix::translation(Vector3::new(1.0, 2.0, 3.0));
+        let data = m.to_column_major_f32();
+        assert_eq!(&data[12..15], &[1.0, 2.0, 3.0]);
+    }
+}
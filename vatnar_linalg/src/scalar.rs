@@ -23,7 +23,6 @@ pub trait Scalar:
 {
 }
 /// Blanket implementation, implement on all types that implement traits: \
-
 impl<T> Scalar for T where
     T: Num
         + Copy
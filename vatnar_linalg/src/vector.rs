@@ -1,4 +1,6 @@
 //! Vector types
 pub use vector2::Vector2;
+pub use vector3::Vector3;
 
 mod vector2;
+mod vector3;
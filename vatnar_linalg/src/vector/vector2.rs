@@ -5,7 +5,7 @@ use crate::scalar::Scalar;
 use num_traits::{CheckedSub, Float};
 
 /// Holds a vector with coordinates `x`, `y` that implements [`Scalar`]
-#[derive(Debug, Copy, Clone, PartialOrd, PartialEq)]
+#[derive(Debug, Copy, Clone, Default, PartialOrd, PartialEq)]
 pub struct Vector2<T: Scalar> {
     pub x: T,
     pub y: T,
@@ -0,0 +1,101 @@
+//! [`Vector3`] type
+mod trait_impl;
+
+use crate::scalar::Scalar;
+use num_traits::Float;
+
+/// Holds a vector with coordinates `x`, `y`, `z` that implements [`Scalar`]
+#[derive(Debug, Copy, Clone, Default, PartialOrd, PartialEq)]
+pub struct Vector3<T: Scalar> {
+    pub x: T,
+    pub y: T,
+    pub z: T,
+}
+
+impl<T: Scalar> Vector3<T> {
+    /// Creates a new vector from coordinates
+    pub fn new(x: T, y: T, z: T) -> Self {
+        Vector3 { x, y, z }
+    }
+
+    /// Returns dot product of two vectors
+    pub fn dot(self, v: Self) -> T {
+        self.x * v.x + self.y * v.y + self.z * v.z
+    }
+
+    /// Returns the cross product `self × v`, perpendicular to both vectors
+    pub fn cross(self, v: Self) -> Self {
+        Vector3::new(
+            self.y * v.z - self.z * v.y,
+            self.z * v.x - self.x * v.z,
+            self.x * v.y - self.y * v.x,
+        )
+    }
+}
+
+impl<T> Vector3<T>
+where
+    T: Float,
+{
+    /// Returns magnitude of vector
+    pub fn mag(&self) -> T {
+        self.dot(*self).sqrt()
+    }
+
+    /// Returns the vector scaled to length 1, or the zero vector if it has no length
+    pub fn normalized(&self) -> Self {
+        let mag = self.mag();
+        if mag.is_zero() {
+            Self::new(T::zero(), T::zero(), T::zero())
+        } else {
+            Self::new(self.x / mag, self.y / mag, self.z / mag)
+        }
+    }
+}
+
+impl<T> From<(T, T, T)> for Vector3<T>
+where
+    T: Scalar,
+{
+    /// Create Vector from tuple
+    fn from(tuple: (T, T, T)) -> Self {
+        Vector3::new(tuple.0, tuple.1, tuple.2)
+    }
+}
+
+impl<T> From<Vector3<T>> for (T, T, T)
+where
+    T: Scalar,
+{
+    /// create tuple from vector
+    fn from(val: Vector3<T>) -> Self {
+        (val.x, val.y, val.z)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vector3_cross() {
+        let x = Vector3::new(1, 0, 0);
+        let y = Vector3::new(0, 1, 0);
+        assert_eq!(x.cross(y), Vector3::new(0, 0, 1));
+        assert_eq!(y.cross(x), Vector3::new(0, 0, -1));
+        assert_eq!(x.dot(y), 0);
+    }
+
+    #[test]
+    fn test_vector3_ops() {
+        let a = Vector3::new(1.0, 2.0, 2.0);
+        assert!((a.mag() - 3.0).abs() < 1e-10);
+        assert!((a.normalized().mag() - 1.0).abs() < 1e-10);
+
+        let b = a - Vector3::new(1.0, 1.0, 1.0);
+        assert_eq!(b, Vector3::new(0.0, 1.0, 1.0));
+        assert_eq!(b + a, Vector3::new(1.0, 3.0, 3.0));
+        assert_eq!(a * 2.0, Vector3::new(2.0, 4.0, 4.0));
+        assert_eq!(-a, Vector3::new(-1.0, -2.0, -2.0));
+    }
+}
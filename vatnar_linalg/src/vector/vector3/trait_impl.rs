@@ -0,0 +1,73 @@
+use crate::Vector3;
+use crate::scalar::Scalar;
+use num_traits::Signed;
+use std::fmt::{Display, Formatter};
+use std::{fmt, ops};
+
+impl<T: Display + Scalar> Display for Vector3<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}, {}, {}]", self.x, self.y, self.z)
+    }
+}
+
+impl<T> ops::Add<Self> for Vector3<T>
+where
+    T: Scalar,
+{
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Vector3::new(self.x + rhs.x, self.y + rhs.y, self.z + rhs.z)
+    }
+}
+
+impl<T> ops::AddAssign<Self> for Vector3<T>
+where
+    T: Scalar,
+{
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl<T> ops::Sub for Vector3<T>
+where
+    T: Scalar + Signed,
+{
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Vector3::new(self.x - rhs.x, self.y - rhs.y, self.z - rhs.z)
+    }
+}
+
+impl<T> ops::SubAssign for Vector3<T>
+where
+    T: Scalar + Signed,
+{
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl<T> ops::Neg for Vector3<T>
+where
+    T: Scalar + Signed,
+{
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Vector3::new(-self.x, -self.y, -self.z)
+    }
+}
+
+impl<T> ops::Mul<T> for Vector3<T>
+where
+    T: Scalar,
+{
+    type Output = Self;
+
+    fn mul(self, rhs: T) -> Self::Output {
+        Vector3::new(self.x * rhs, self.y * rhs, self.z * rhs)
+    }
+}